@@ -0,0 +1,83 @@
+-- PostgreSQL schema, equivalent to all SQLite migrations up to and including
+-- 20231029090000_alert_events
+
+CREATE TABLE IF NOT EXISTS alerts
+(
+    id              BIGSERIAL     PRIMARY KEY,
+    text            TEXT          NOT NULL,
+    resolved        BOOLEAN       NOT NULL DEFAULT false,
+    fingerprint     TEXT          DEFAULT NULL,
+    occurrence      BIGINT        NOT NULL DEFAULT 1,
+    notebook_id     TEXT          DEFAULT NULL,
+    chart_filename  TEXT          DEFAULT NULL,
+    slack_channel   TEXT          DEFAULT NULL,
+    slack_ts        TEXT          DEFAULT NULL,
+    sloth_service   TEXT          DEFAULT NULL,
+    sloth_slo       TEXT          DEFAULT NULL,
+    objective_name  TEXT          DEFAULT NULL,
+    severity        TEXT          DEFAULT NULL,
+    acknowledged_by TEXT          DEFAULT NULL,
+    acknowledged_at TIMESTAMPTZ   DEFAULT NULL,
+    silenced_by     TEXT          DEFAULT NULL,
+    silenced_at     TIMESTAMPTZ   DEFAULT NULL,
+    silenced_until  TIMESTAMPTZ   DEFAULT NULL,
+    silence_id      TEXT          DEFAULT NULL,
+    resolved_by     TEXT          DEFAULT NULL,
+    labels          JSONB         NOT NULL DEFAULT '{}',
+    annotations     JSONB         NOT NULL DEFAULT '{}',
+    generator_url   TEXT          DEFAULT NULL,
+    starts_at       TIMESTAMPTZ   DEFAULT NULL,
+    ends_at         TIMESTAMPTZ   DEFAULT NULL,
+    created_at      TIMESTAMPTZ   NOT NULL,
+    updated_at      TIMESTAMPTZ   NOT NULL
+);
+
+CREATE INDEX alerts_fingerprint ON alerts(fingerprint, id);
+
+CREATE TABLE IF NOT EXISTS event_queue
+(
+    id              BIGSERIAL     PRIMARY KEY,
+    alert_id        BIGINT        NOT NULL REFERENCES alerts(id) ON DELETE CASCADE,
+    payload         JSONB         NOT NULL,
+    claimed_at      TIMESTAMPTZ   DEFAULT NULL,
+    attempts        BIGINT        NOT NULL DEFAULT 0,
+    available_at    TIMESTAMPTZ   DEFAULT NULL,
+    last_error      TEXT          DEFAULT NULL,
+    created_at      TIMESTAMPTZ   NOT NULL
+);
+
+CREATE INDEX event_queue_alert_id ON event_queue(alert_id);
+
+CREATE TABLE IF NOT EXISTS dead_letter_events
+(
+    id              BIGSERIAL     PRIMARY KEY,
+    alert_id        BIGINT        NOT NULL REFERENCES alerts(id) ON DELETE CASCADE,
+    payload         JSONB         NOT NULL,
+    attempts        BIGINT        NOT NULL,
+    last_error      TEXT          DEFAULT NULL,
+    created_at      TIMESTAMPTZ   NOT NULL,
+    failed_at       TIMESTAMPTZ   NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS slack_messages
+(
+    id              BIGSERIAL     PRIMARY KEY,
+    alert_id        BIGINT        NOT NULL REFERENCES alerts(id) ON DELETE CASCADE,
+    channel         TEXT          NOT NULL,
+    channel_id      TEXT          NOT NULL,
+    ts              TEXT          NOT NULL,
+    created_at      TIMESTAMPTZ   NOT NULL,
+    UNIQUE (alert_id, channel)
+);
+
+CREATE TABLE IF NOT EXISTS alert_events
+(
+    id              BIGSERIAL     PRIMARY KEY,
+    alert_id        BIGINT        NOT NULL REFERENCES alerts(id) ON DELETE CASCADE,
+    kind            TEXT          NOT NULL,
+    actor           TEXT          DEFAULT NULL,
+    created_at      TIMESTAMPTZ   NOT NULL
+);
+
+CREATE INDEX alert_events_alert_id ON alert_events(alert_id, id);
+CREATE INDEX alert_events_created_at ON alert_events(created_at);
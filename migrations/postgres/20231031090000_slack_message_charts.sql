@@ -0,0 +1,4 @@
+-- Remember which chart was uploaded to the thread of a Slack message, so it
+-- isn't uploaded twice
+
+ALTER TABLE slack_messages ADD COLUMN chart_file_id TEXT DEFAULT NULL;
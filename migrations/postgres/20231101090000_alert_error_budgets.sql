@@ -0,0 +1,4 @@
+-- Store the error budget of SLO alerts, and the chart of how it was consumed
+
+ALTER TABLE alerts ADD COLUMN error_budget JSONB DEFAULT NULL;
+ALTER TABLE alerts ADD COLUMN error_budget_chart_filename TEXT DEFAULT NULL;
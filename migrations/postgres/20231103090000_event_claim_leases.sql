@@ -0,0 +1,8 @@
+-- Claims on events expire, so events claimed by a replica that went away are
+-- processed by another one, without touching events that are still being
+-- processed
+
+ALTER TABLE event_queue ADD COLUMN claim_expires_at TIMESTAMPTZ DEFAULT NULL;
+
+-- Events claimed before this migration were interrupted by the restart
+UPDATE event_queue SET claim_expires_at = claimed_at WHERE claimed_at IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Remember which error budget chart was uploaded to the thread of a Slack
+-- message, so it isn't uploaded twice either
+
+ALTER TABLE slack_messages ADD COLUMN error_budget_file_id TEXT DEFAULT NULL;
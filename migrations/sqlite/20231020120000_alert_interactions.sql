@@ -0,0 +1,7 @@
+-- Track interactions with alerts from Slack
+
+ALTER TABLE alerts ADD COLUMN acknowledged_by TEXT DEFAULT NULL;
+ALTER TABLE alerts ADD COLUMN acknowledged_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE alerts ADD COLUMN silenced_by TEXT DEFAULT NULL;
+ALTER TABLE alerts ADD COLUMN silenced_until TIMESTAMP DEFAULT NULL;
+ALTER TABLE alerts ADD COLUMN resolved_by TEXT DEFAULT NULL;
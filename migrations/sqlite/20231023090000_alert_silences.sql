@@ -0,0 +1,5 @@
+-- Store the labels of alerts, so we can create Alertmanager silences for them
+
+ALTER TABLE alerts ADD COLUMN labels TEXT NOT NULL DEFAULT '{}';
+ALTER TABLE alerts ADD COLUMN silenced_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE alerts ADD COLUMN silence_id TEXT DEFAULT NULL;
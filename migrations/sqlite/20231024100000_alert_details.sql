@@ -0,0 +1,6 @@
+-- Store everything Alertmanager tells us about an alert
+
+ALTER TABLE alerts ADD COLUMN annotations TEXT NOT NULL DEFAULT '{}';
+ALTER TABLE alerts ADD COLUMN generator_url TEXT DEFAULT NULL;
+ALTER TABLE alerts ADD COLUMN starts_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE alerts ADD COLUMN ends_at TIMESTAMP DEFAULT NULL;
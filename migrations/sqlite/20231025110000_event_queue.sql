@@ -0,0 +1,12 @@
+-- Durable queue of events to be processed by the event loop
+
+CREATE TABLE IF NOT EXISTS event_queue
+(
+    id              INTEGER       PRIMARY KEY AUTOINCREMENT,
+    alert_id        INTEGER       NOT NULL REFERENCES alerts(id) ON DELETE CASCADE,
+    payload         TEXT          NOT NULL,
+    claimed_at      TIMESTAMP     DEFAULT NULL,
+    created_at      TIMESTAMP     NOT NULL
+);
+
+CREATE INDEX event_queue_alert_id ON event_queue(alert_id);
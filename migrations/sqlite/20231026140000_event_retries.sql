@@ -0,0 +1,16 @@
+-- Retry failed events with a backoff, and keep events that keep failing
+
+ALTER TABLE event_queue ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE event_queue ADD COLUMN available_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE event_queue ADD COLUMN last_error TEXT DEFAULT NULL;
+
+CREATE TABLE IF NOT EXISTS dead_letter_events
+(
+    id              INTEGER       PRIMARY KEY AUTOINCREMENT,
+    alert_id        INTEGER       NOT NULL REFERENCES alerts(id) ON DELETE CASCADE,
+    payload         TEXT          NOT NULL,
+    attempts        INTEGER       NOT NULL,
+    last_error      TEXT          DEFAULT NULL,
+    created_at      TIMESTAMP     NOT NULL,
+    failed_at       TIMESTAMP     NOT NULL
+);
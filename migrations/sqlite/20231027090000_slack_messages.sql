@@ -0,0 +1,18 @@
+-- Keep track of every Slack message posted for an alert, since an alert may
+-- be posted to multiple channels
+
+CREATE TABLE IF NOT EXISTS slack_messages
+(
+    id              INTEGER       PRIMARY KEY AUTOINCREMENT,
+    alert_id        INTEGER       NOT NULL REFERENCES alerts(id) ON DELETE CASCADE,
+    channel         TEXT          NOT NULL,
+    channel_id      TEXT          NOT NULL,
+    ts              TEXT          NOT NULL,
+    created_at      TIMESTAMP     NOT NULL,
+    UNIQUE (alert_id, channel)
+);
+
+INSERT INTO slack_messages ( alert_id, channel, channel_id, ts, created_at )
+SELECT id, slack_channel, slack_channel, slack_ts, created_at
+FROM alerts
+WHERE slack_channel IS NOT NULL AND slack_ts IS NOT NULL;
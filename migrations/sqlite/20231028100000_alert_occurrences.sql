@@ -0,0 +1,7 @@
+-- Alerts that fire again after they were resolved create a new occurrence,
+-- so multiple alerts can share a fingerprint
+
+DROP INDEX IF EXISTS alerts_fingerprint;
+CREATE INDEX alerts_fingerprint ON alerts(fingerprint, id);
+
+ALTER TABLE alerts ADD COLUMN occurrence INTEGER NOT NULL DEFAULT 1;
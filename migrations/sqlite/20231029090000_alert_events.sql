@@ -0,0 +1,13 @@
+-- History of the state transitions of every alert
+
+CREATE TABLE IF NOT EXISTS alert_events
+(
+    id              INTEGER       PRIMARY KEY AUTOINCREMENT,
+    alert_id        INTEGER       NOT NULL REFERENCES alerts(id) ON DELETE CASCADE,
+    kind            TEXT          NOT NULL,
+    actor           TEXT          DEFAULT NULL,
+    created_at      TIMESTAMP     NOT NULL
+);
+
+CREATE INDEX alert_events_alert_id ON alert_events(alert_id, id);
+CREATE INDEX alert_events_created_at ON alert_events(created_at);
@@ -0,0 +1,9 @@
+-- Remember which Slack messages a thread reply was posted to while the event
+-- posting it is queued, so a retry doesn't post it twice
+
+CREATE TABLE IF NOT EXISTS slack_thread_replies
+(
+    event_id          INTEGER       NOT NULL REFERENCES event_queue(id) ON DELETE CASCADE,
+    slack_message_id  INTEGER       NOT NULL REFERENCES slack_messages(id) ON DELETE CASCADE,
+    PRIMARY KEY (event_id, slack_message_id)
+);
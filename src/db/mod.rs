@@ -1,9 +1,23 @@
-pub mod models;
-pub mod sqlite;
+/// Runs the given expression against the connection of whichever backend the
+/// transaction belongs to. The expression can refer to the transaction through
+/// the given identifier, which is rebound to the backend-specific transaction.
+macro_rules! on_tx {
+    ($tx:ident, $body:expr) => {
+        match $tx {
+            $crate::db::Transaction::Sqlite($tx) => $body,
+            $crate::db::Transaction::Postgres($tx) => $body,
+        }
+    };
+}
 
-pub use sqlite::Db;
+pub mod models;
+mod queries;
 
 use serde_with::SerializeDisplay;
+use sqlx::{
+    postgres::PgPoolOptions, sqlite::SqlitePoolOptions, PgPool, Postgres, Sqlite, SqlitePool,
+};
+use std::time::Duration;
 use strum_macros::IntoStaticStr;
 use thiserror::Error;
 
@@ -44,3 +58,106 @@ impl From<sqlx::Error> for DbError {
         }
     }
 }
+
+/// The storage backend, which is either SQLite or PostgreSQL depending on the
+/// connection string.
+#[derive(Clone)]
+pub struct Db {
+    pool: DbPool,
+}
+
+#[derive(Clone)]
+enum DbPool {
+    Sqlite(SqlitePool),
+    Postgres(PgPool),
+}
+
+/// A transaction on either of the supported backends.
+pub enum Transaction<'a> {
+    Sqlite(sqlx::Transaction<'a, Sqlite>),
+    Postgres(sqlx::Transaction<'a, Postgres>),
+}
+
+impl<'a> Transaction<'a> {
+    pub async fn commit(self) -> Result<(), DbError> {
+        match self {
+            Transaction::Sqlite(tx) => tx.commit().await?,
+            Transaction::Postgres(tx) => tx.commit().await?,
+        }
+        Ok(())
+    }
+
+    /// Returns the clause to skip rows that are locked by other transactions
+    /// when selecting rows to update, if the backend supports it.
+    fn skip_locked_clause(&self) -> &'static str {
+        match self {
+            Transaction::Sqlite(_) => "",
+            Transaction::Postgres(_) => "FOR UPDATE SKIP LOCKED",
+        }
+    }
+}
+
+impl Db {
+    /// Connects to the database with the given connection string. Connection
+    /// strings starting with `postgres://` or `postgresql://` use PostgreSQL,
+    /// anything else is treated as SQLite.
+    pub async fn connect(connection_string: &str) -> Result<Db, DbError> {
+        let pool = if is_postgres(connection_string) {
+            let pool = PgPoolOptions::new()
+                .max_connections(10)
+                .acquire_timeout(Duration::from_secs(5))
+                .connect(connection_string)
+                .await?;
+            DbPool::Postgres(pool)
+        } else {
+            let pool = SqlitePoolOptions::new()
+                .max_connections(5)
+                .acquire_timeout(Duration::from_secs(5))
+                .connect(connection_string)
+                .await?;
+            DbPool::Sqlite(pool)
+        };
+
+        Ok(Db { pool })
+    }
+
+    pub fn from_sqlite_pool(pool: SqlitePool) -> Db {
+        Db {
+            pool: DbPool::Sqlite(pool),
+        }
+    }
+
+    pub fn from_postgres_pool(pool: PgPool) -> Db {
+        Db {
+            pool: DbPool::Postgres(pool),
+        }
+    }
+
+    /// Returns the name of the backend, for logging.
+    pub fn backend_name(&self) -> &'static str {
+        match self.pool {
+            DbPool::Sqlite(_) => "sqlite",
+            DbPool::Postgres(_) => "postgres",
+        }
+    }
+
+    /// Runs the migrations for the backend in use. Each backend has its own
+    /// set of migrations, since their SQL dialects differ.
+    pub async fn migrate(&self) -> Result<(), sqlx::migrate::MigrateError> {
+        match &self.pool {
+            DbPool::Sqlite(pool) => sqlx::migrate!("./migrations/sqlite").run(pool).await,
+            DbPool::Postgres(pool) => sqlx::migrate!("./migrations/postgres").run(pool).await,
+        }
+    }
+
+    pub async fn close(&self) {
+        match &self.pool {
+            DbPool::Sqlite(pool) => pool.close().await,
+            DbPool::Postgres(pool) => pool.close().await,
+        }
+    }
+}
+
+pub fn is_postgres(connection_string: &str) -> bool {
+    connection_string.starts_with("postgres://") || connection_string.starts_with("postgresql://")
+}
@@ -1,7 +1,10 @@
-use serde::Serialize;
-use sqlx::{types::time::OffsetDateTime, FromRow};
+use crate::events::Event;
+use serde::{Deserialize, Serialize};
+use sqlx::types::{time::OffsetDateTime, Json};
+use sqlx::FromRow;
+use std::collections::BTreeMap;
 
-#[derive(Clone, Debug, Eq, FromRow, PartialEq, Serialize)]
+#[derive(Clone, Debug, FromRow, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Alert {
     /// ID of the alert.
@@ -14,8 +17,15 @@ pub struct Alert {
     pub resolved: bool,
 
     /// Fingerprint generated by Alertmanager used to lookup alerts.
+    ///
+    /// Every time an alert fires again after it was resolved, a new
+    /// occurrence is created with the same fingerprint.
     pub fingerprint: Option<String>,
 
+    /// Number of the occurrence among all alerts with the same fingerprint,
+    /// starting at 1.
+    pub occurrence: i64,
+
     /// Optional ID of a notebook that has been created in response to the
     /// alert.
     pub notebook_id: Option<String>,
@@ -23,6 +33,14 @@ pub struct Alert {
     /// Optional file name of the generated chart for this alert.
     pub chart_filename: Option<String>,
 
+    /// Optional error budget of the SLO, as it was when the chart for this
+    /// alert was generated.
+    pub error_budget: Option<Json<ErrorBudget>>,
+
+    /// Optional file name of the generated chart of the error budget
+    /// consumption for this alert.
+    pub error_budget_chart_filename: Option<String>,
+
     /// Optional channel the Slack message was posted to.
     pub slack_channel: Option<String>,
 
@@ -42,6 +60,48 @@ pub struct Alert {
     /// Optional severity of the alert.
     pub severity: Option<String>,
 
+    /// Optional ID of the Slack user who acknowledged the alert.
+    pub acknowledged_by: Option<String>,
+
+    /// Optional timestamp at which the alert was acknowledged.
+    pub acknowledged_at: Option<OffsetDateTime>,
+
+    /// Optional ID of the Slack user who silenced the alert.
+    pub silenced_by: Option<String>,
+
+    /// Optional timestamp at which the alert was silenced.
+    pub silenced_at: Option<OffsetDateTime>,
+
+    /// Optional timestamp until which the alert is silenced.
+    pub silenced_until: Option<OffsetDateTime>,
+
+    /// Optional ID of the Alertmanager silence created for the alert.
+    pub silence_id: Option<String>,
+
+    /// Optional ID of the Slack user who marked the alert as resolved.
+    ///
+    /// This is only set if the alert was resolved manually, rather than by
+    /// Alertmanager.
+    pub resolved_by: Option<String>,
+
+    /// All the labels of the alert, as reported by Alertmanager.
+    pub labels: Json<BTreeMap<String, String>>,
+
+    /// All the annotations of the alert, as reported by Alertmanager.
+    pub annotations: Json<BTreeMap<String, String>>,
+
+    /// Optional URL of the entity that caused the alert, such as the
+    /// Prometheus graph of the alerting rule.
+    pub generator_url: Option<String>,
+
+    /// Optional timestamp at which the alert started firing, according to
+    /// Alertmanager.
+    pub starts_at: Option<OffsetDateTime>,
+
+    /// Optional timestamp at which the alert was resolved, according to
+    /// Alertmanager.
+    pub ends_at: Option<OffsetDateTime>,
+
     /// Timestamp at which the alert was created.
     pub created_at: OffsetDateTime,
 
@@ -49,6 +109,142 @@ pub struct Alert {
     pub updated_at: OffsetDateTime,
 }
 
+/// A change in the state of an alert, that is worth notifying people about.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertTransition {
+    /// The alert was resolved, either by Alertmanager or by a user.
+    Resolved,
+
+    /// The alert started firing again after it was resolved.
+    Refired,
+
+    /// A user acknowledged the alert.
+    Acknowledged,
+}
+
+/// An entry in the history of an alert.
+#[derive(Clone, Debug, FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertEvent {
+    /// ID of the alert event.
+    pub id: i64,
+
+    /// ID of the alert the event applies to.
+    pub alert_id: i64,
+
+    /// What happened to the alert.
+    pub kind: AlertEventKind,
+
+    /// Optional ID of the Slack user who caused the event.
+    ///
+    /// Events caused by Alertmanager or the Slack app itself have no actor.
+    pub actor: Option<String>,
+
+    /// Timestamp at which the event happened.
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum AlertEventKind {
+    /// Alertmanager reported the alert as firing for the first time.
+    Fired,
+
+    /// Alertmanager reported the alert as firing again after it was resolved.
+    Refired,
+
+    /// The alert was resolved, either by Alertmanager or by a user.
+    Resolved,
+
+    /// The alert was posted to Slack.
+    Posted,
+
+    /// A user acknowledged the alert.
+    Acknowledged,
+
+    /// A user silenced the alert.
+    Silenced,
+
+    /// A user expired the silence of the alert.
+    SilenceExpired,
+}
+
+impl From<AlertTransition> for AlertEventKind {
+    fn from(transition: AlertTransition) -> Self {
+        match transition {
+            AlertTransition::Resolved => Self::Resolved,
+            AlertTransition::Refired => Self::Refired,
+            AlertTransition::Acknowledged => Self::Acknowledged,
+        }
+    }
+}
+
+/// The error budget of an SLO, and how fast it is being consumed.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorBudget {
+    /// Length of the SLO period the budget applies to, in days.
+    pub period_days: u32,
+
+    /// Optional fraction of the budget for the period that is left. This is
+    /// negative once the budget is exhausted, and missing if there was no
+    /// traffic during the period.
+    pub remaining: Option<f64>,
+
+    /// Rates at which the budget was consumed over several windows, shortest
+    /// window first.
+    pub burn_rates: Vec<BurnRate>,
+}
+
+/// The rate at which an error budget was consumed over a window. At a burn
+/// rate of 1, the budget would be used up exactly at the end of the period.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BurnRate {
+    /// The window, as a Prometheus duration such as `5m`.
+    pub window: String,
+
+    /// Optional burn rate over the window, missing if there was no traffic.
+    pub rate: Option<f64>,
+}
+
+/// Statistics about an alert, derived from its history.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertStats {
+    /// Number of times alerts with the same fingerprint fired in the last 24
+    /// hours, counting every occurrence and every time one fired again.
+    pub fired_last_24h: i64,
+
+    /// Optional number of seconds it took to resolve the alert, if it is
+    /// resolved.
+    pub time_to_resolve_secs: Option<i64>,
+}
+
+/// Filters for listing alerts. Filters that are `None` match all alerts.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AlertFilter {
+    /// Only return alerts that are (or aren't) resolved.
+    pub resolved: Option<bool>,
+
+    /// Only return alerts with the given severity.
+    pub severity: Option<String>,
+
+    /// Only return alerts for the given Sloth service.
+    pub sloth_service: Option<String>,
+
+    /// Only return alerts for the given objective.
+    pub objective_name: Option<String>,
+
+    /// Only return alerts that were created at or after the given time.
+    pub created_from: Option<OffsetDateTime>,
+
+    /// Only return alerts that were created before the given time.
+    pub created_to: Option<OffsetDateTime>,
+}
+
 #[derive(Debug)]
 pub struct NewAlert {
     /// The alert text.
@@ -85,4 +281,105 @@ pub struct NewAlert {
 
     /// Optional severity of the alert.
     pub severity: Option<String>,
+
+    /// All the labels of the alert, as reported by Alertmanager.
+    pub labels: BTreeMap<String, String>,
+
+    /// All the annotations of the alert, as reported by Alertmanager.
+    pub annotations: BTreeMap<String, String>,
+
+    /// Optional URL of the entity that caused the alert.
+    pub generator_url: Option<String>,
+
+    /// Optional timestamp at which the alert started firing.
+    pub starts_at: Option<OffsetDateTime>,
+
+    /// Optional timestamp at which the alert was resolved.
+    pub ends_at: Option<OffsetDateTime>,
+}
+
+/// A Slack message that was posted for an alert.
+#[derive(Clone, Debug, FromRow)]
+pub struct SlackMessage {
+    /// ID of the Slack message.
+    pub id: i64,
+
+    /// ID of the alert the message was posted for.
+    pub alert_id: i64,
+
+    /// The channel the message was posted to, as it appears in the routing
+    /// table.
+    pub channel: String,
+
+    /// ID of the channel the message was posted to, as returned from the Slack
+    /// API.
+    pub channel_id: String,
+
+    /// Timestamp of the message, as returned from the Slack API.
+    pub ts: String,
+
+    /// ID of the chart that was uploaded to the thread of the message, if
+    /// any.
+    pub chart_file_id: Option<String>,
+
+    /// ID of the error budget chart that was uploaded to the thread of the
+    /// message, if any.
+    pub error_budget_file_id: Option<String>,
+
+    /// Timestamp at which the message was posted.
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct QueuedEvent {
+    /// ID of the queued event.
+    pub id: i64,
+
+    /// ID of the alert the event applies to.
+    pub alert_id: i64,
+
+    /// The event to process.
+    pub payload: Json<Event>,
+
+    /// Optional timestamp at which the event loop claimed the event for
+    /// processing.
+    pub claimed_at: Option<OffsetDateTime>,
+
+    /// Number of times the event loop attempted to process the event,
+    /// including the current attempt if it is claimed.
+    pub attempts: i64,
+
+    /// Optional timestamp before which the event should not be processed.
+    pub available_at: Option<OffsetDateTime>,
+
+    /// Optional error of the last failed attempt.
+    pub last_error: Option<String>,
+
+    /// Timestamp at which the event was queued.
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetterEvent {
+    /// ID of the dead-letter event.
+    pub id: i64,
+
+    /// ID of the alert the event applies to.
+    pub alert_id: i64,
+
+    /// The event that could not be processed.
+    pub payload: Json<Event>,
+
+    /// Number of times the event loop attempted to process the event.
+    pub attempts: i64,
+
+    /// Optional error of the last failed attempt.
+    pub last_error: Option<String>,
+
+    /// Timestamp at which the event was originally queued.
+    pub created_at: OffsetDateTime,
+
+    /// Timestamp at which the event was given up on.
+    pub failed_at: OffsetDateTime,
 }
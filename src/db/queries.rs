@@ -0,0 +1,930 @@
+use super::{models::*, Db, DbError, DbPool, Transaction};
+use crate::events::Event;
+use autometrics::autometrics;
+use sqlx::types::{time::OffsetDateTime, Json};
+use time::Duration;
+use tracing::{instrument, trace};
+
+#[autometrics]
+impl Db {
+    #[instrument(skip(self, tx))]
+    pub async fn alert_create(
+        &self,
+        tx: &mut Transaction<'_>,
+        new_alert: NewAlert,
+    ) -> Result<Alert, DbError> {
+        let now = OffsetDateTime::now_utc();
+        let alert = on_tx!(
+            tx,
+            sqlx::query_as(
+                "INSERT INTO alerts ( text, resolved, fingerprint, occurrence, notebook_id, chart_filename, slack_channel, slack_ts, sloth_slo, sloth_service, objective_name, severity, labels, annotations, generator_url, starts_at, ends_at, created_at, updated_at )
+                 VALUES ( $1, $2, $3, (SELECT COALESCE(MAX(occurrence), 0) + 1 FROM alerts WHERE fingerprint = $3), $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18 )
+                 RETURNING *",
+            )
+            .bind(&new_alert.text)
+            .bind(new_alert.resolved)
+            .bind(new_alert.fingerprint.as_ref())
+            .bind(new_alert.notebook_id.as_ref())
+            .bind(new_alert.chart_filename.as_ref())
+            .bind(new_alert.slack_channel.as_ref())
+            .bind(new_alert.slack_ts.as_ref())
+            .bind(new_alert.sloth_slo.as_ref())
+            .bind(new_alert.sloth_service.as_ref())
+            .bind(new_alert.objective_name.as_ref())
+            .bind(new_alert.severity.as_ref())
+            .bind(Json(&new_alert.labels))
+            .bind(Json(&new_alert.annotations))
+            .bind(new_alert.generator_url.as_ref())
+            .bind(new_alert.starts_at)
+            .bind(new_alert.ends_at)
+            .bind(now)
+            .bind(now)
+            .fetch_one(&mut **tx)
+            .await
+        )?;
+
+        Ok(alert)
+    }
+
+    #[instrument(skip(self, tx))]
+    pub async fn alert_get(
+        &self,
+        tx: &mut Transaction<'_>,
+        alert_id: i64,
+    ) -> Result<Alert, DbError> {
+        let alert = on_tx!(
+            tx,
+            sqlx::query_as(
+                "SELECT *
+                 FROM alerts
+                 WHERE id = $1",
+            )
+            .bind(alert_id)
+            .fetch_one(&mut **tx)
+            .await
+        )?;
+
+        Ok(alert)
+    }
+
+    /// Returns the latest occurrence of the alert with the given fingerprint,
+    /// if any.
+    #[instrument(skip(self, tx))]
+    pub async fn alert_get_latest_by_fingerprint(
+        &self,
+        tx: &mut Transaction<'_>,
+        fingerprint: &str,
+    ) -> Result<Option<Alert>, DbError> {
+        let alert = on_tx!(
+            tx,
+            sqlx::query_as(
+                "SELECT *
+                 FROM alerts
+                 WHERE fingerprint = $1
+                 ORDER BY id DESC
+                 LIMIT 1",
+            )
+            .bind(fingerprint)
+            .fetch_optional(&mut **tx)
+            .await
+        )?;
+
+        Ok(alert)
+    }
+
+    /// Returns all occurrences of the alert with the given fingerprint, most
+    /// recent first.
+    #[instrument(skip(self, tx))]
+    pub async fn alert_list_by_fingerprint(
+        &self,
+        tx: &mut Transaction<'_>,
+        fingerprint: &str,
+    ) -> Result<Vec<Alert>, DbError> {
+        let alerts = on_tx!(
+            tx,
+            sqlx::query_as(
+                "SELECT *
+                 FROM alerts
+                 WHERE fingerprint = $1
+                 ORDER BY id DESC",
+            )
+            .bind(fingerprint)
+            .fetch_all(&mut **tx)
+            .await
+        )?;
+
+        Ok(alerts)
+    }
+
+    /// Returns all alerts that have a label with the given name and value,
+    /// most recent first.
+    #[instrument(skip(self, tx))]
+    pub async fn alert_list_by_label(
+        &self,
+        tx: &mut Transaction<'_>,
+        name: &str,
+        value: &str,
+    ) -> Result<Vec<Alert>, DbError> {
+        // Labels are stored as JSON, which both backends can query, albeit
+        // with a different syntax.
+        let alerts = match tx {
+            Transaction::Sqlite(tx) => {
+                sqlx::query_as(
+                    "SELECT *
+                     FROM alerts
+                     WHERE json_extract(labels, $1) = $2
+                     ORDER BY id DESC",
+                )
+                .bind(json_path_for_key(name))
+                .bind(value)
+                .fetch_all(&mut **tx)
+                .await
+            }
+            Transaction::Postgres(tx) => {
+                sqlx::query_as(
+                    "SELECT *
+                     FROM alerts
+                     WHERE labels ->> $1 = $2
+                     ORDER BY id DESC",
+                )
+                .bind(name)
+                .bind(value)
+                .fetch_all(&mut **tx)
+                .await
+            }
+        }?;
+
+        Ok(alerts)
+    }
+
+    #[instrument(skip(self, tx))]
+    pub async fn alert_update(
+        &self,
+        tx: &mut Transaction<'_>,
+        alert: &Alert,
+    ) -> Result<(), DbError> {
+        let result = on_tx!(
+            tx,
+            sqlx::query(
+                "UPDATE alerts
+                 SET resolved = $1, notebook_id = $2, slack_channel = $3, slack_ts = $4, chart_filename = $5, acknowledged_by = $6, acknowledged_at = $7, silenced_by = $8, silenced_at = $9, silenced_until = $10, silence_id = $11, resolved_by = $12, labels = $13, annotations = $14, generator_url = $15, starts_at = $16, ends_at = $17, error_budget = $18, error_budget_chart_filename = $19, updated_at = $20
+                 WHERE id = $21",
+            )
+            .bind(alert.resolved)
+            .bind(alert.notebook_id.as_ref())
+            .bind(alert.slack_channel.as_ref())
+            .bind(alert.slack_ts.as_ref())
+            .bind(alert.chart_filename.as_ref())
+            .bind(alert.acknowledged_by.as_ref())
+            .bind(alert.acknowledged_at)
+            .bind(alert.silenced_by.as_ref())
+            .bind(alert.silenced_at)
+            .bind(alert.silenced_until)
+            .bind(alert.silence_id.as_ref())
+            .bind(alert.resolved_by.as_ref())
+            .bind(&alert.labels)
+            .bind(&alert.annotations)
+            .bind(alert.generator_url.as_ref())
+            .bind(alert.starts_at)
+            .bind(alert.ends_at)
+            .bind(alert.error_budget.as_ref())
+            .bind(alert.error_budget_chart_filename.as_ref())
+            .bind(OffsetDateTime::now_utc())
+            .bind(alert.id)
+            .execute(&mut **tx)
+            .await
+        )?;
+
+        match result.rows_affected() {
+            0 => Err(DbError::NotFound),
+            1 => Ok(()),
+            _ => Err(DbError::UnknownError),
+        }
+    }
+
+    /// Returns alerts that match the given filter, most recent first.
+    ///
+    /// Only alerts with an ID lower than `before_id` are returned, if given,
+    /// so the results can be paginated.
+    #[instrument(skip(self, tx))]
+    pub async fn alert_list(
+        &self,
+        tx: &mut Transaction<'_>,
+        filter: &AlertFilter,
+        before_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<Alert>, DbError> {
+        let alerts = on_tx!(
+            tx,
+            sqlx::query_as(
+                "SELECT *
+                 FROM alerts
+                 WHERE ($1 IS NULL OR resolved = $1)
+                    AND ($2 IS NULL OR severity = $2)
+                    AND ($3 IS NULL OR sloth_service = $3)
+                    AND ($4 IS NULL OR objective_name = $4)
+                    AND ($5 IS NULL OR created_at >= $5)
+                    AND ($6 IS NULL OR created_at < $6)
+                    AND ($7 IS NULL OR id < $7)
+                 ORDER BY id DESC
+                 LIMIT $8",
+            )
+            .bind(filter.resolved)
+            .bind(filter.severity.as_ref())
+            .bind(filter.sloth_service.as_ref())
+            .bind(filter.objective_name.as_ref())
+            .bind(filter.created_from)
+            .bind(filter.created_to)
+            .bind(before_id)
+            .bind(limit)
+            .fetch_all(&mut **tx)
+            .await
+        )?;
+
+        Ok(alerts)
+    }
+
+    /// Returns resolved alerts that haven't been updated since the given
+    /// cutoff, oldest first.
+    #[instrument(skip(self, tx))]
+    pub async fn alert_list_resolved_before(
+        &self,
+        tx: &mut Transaction<'_>,
+        cutoff: OffsetDateTime,
+        limit: i64,
+    ) -> Result<Vec<Alert>, DbError> {
+        let alerts = on_tx!(
+            tx,
+            sqlx::query_as(
+                "SELECT *
+                 FROM alerts
+                 WHERE resolved = true AND updated_at < $1
+                 ORDER BY id
+                 LIMIT $2",
+            )
+            .bind(cutoff)
+            .bind(limit)
+            .fetch_all(&mut **tx)
+            .await
+        )?;
+
+        Ok(alerts)
+    }
+
+    /// Deletes the alert, together with its history, Slack messages and any
+    /// events that are still queued for it.
+    #[instrument(skip(self, tx))]
+    pub async fn alert_delete(
+        &self,
+        tx: &mut Transaction<'_>,
+        alert_id: i64,
+    ) -> Result<(), DbError> {
+        let result = on_tx!(
+            tx,
+            sqlx::query(
+                "DELETE FROM alerts
+                 WHERE id = $1",
+            )
+            .bind(alert_id)
+            .execute(&mut **tx)
+            .await
+        )?;
+
+        match result.rows_affected() {
+            0 => Err(DbError::NotFound),
+            1 => Ok(()),
+            _ => Err(DbError::UnknownError),
+        }
+    }
+
+    /// Adds an entry to the history of an alert.
+    #[instrument(skip(self, tx))]
+    pub async fn alert_event_create(
+        &self,
+        tx: &mut Transaction<'_>,
+        alert_id: i64,
+        kind: AlertEventKind,
+        actor: Option<&str>,
+    ) -> Result<AlertEvent, DbError> {
+        let event = on_tx!(
+            tx,
+            sqlx::query_as(
+                "INSERT INTO alert_events ( alert_id, kind, actor, created_at )
+                 VALUES ( $1, $2, $3, $4 )
+                 RETURNING *",
+            )
+            .bind(alert_id)
+            .bind(kind)
+            .bind(actor)
+            .bind(OffsetDateTime::now_utc())
+            .fetch_one(&mut **tx)
+            .await
+        )?;
+
+        Ok(event)
+    }
+
+    /// Returns the history of an alert, oldest first.
+    #[instrument(skip(self, tx))]
+    pub async fn alert_event_list(
+        &self,
+        tx: &mut Transaction<'_>,
+        alert_id: i64,
+    ) -> Result<Vec<AlertEvent>, DbError> {
+        let events = on_tx!(
+            tx,
+            sqlx::query_as(
+                "SELECT *
+                 FROM alert_events
+                 WHERE alert_id = $1
+                 ORDER BY id",
+            )
+            .bind(alert_id)
+            .fetch_all(&mut **tx)
+            .await
+        )?;
+
+        Ok(events)
+    }
+
+    /// Returns statistics about an alert, based on its history and that of
+    /// the other occurrences with the same fingerprint.
+    #[instrument(skip(self, tx, alert), fields(alert_id = alert.id))]
+    pub async fn alert_stats(
+        &self,
+        tx: &mut Transaction<'_>,
+        alert: &Alert,
+        now: OffsetDateTime,
+    ) -> Result<AlertStats, DbError> {
+        let (fired_last_24h,): (i64,) = on_tx!(
+            tx,
+            sqlx::query_as(
+                "SELECT COUNT(*)
+                 FROM alert_events
+                 JOIN alerts ON alerts.id = alert_events.alert_id
+                 WHERE (alerts.id = $1 OR alerts.fingerprint = $2)
+                    AND alert_events.kind IN ($3, $4)
+                    AND alert_events.created_at >= $5",
+            )
+            .bind(alert.id)
+            .bind(alert.fingerprint.as_ref())
+            .bind(AlertEventKind::Fired)
+            .bind(AlertEventKind::Refired)
+            .bind(now - Duration::hours(24))
+            .fetch_one(&mut **tx)
+            .await
+        )?;
+
+        let time_to_resolve_secs = if alert.resolved {
+            let events = self.alert_event_list(tx, alert.id).await?;
+            let fired_at = events
+                .iter()
+                .find(|event| event.kind == AlertEventKind::Fired)
+                .map(|event| event.created_at);
+            let resolved_at = events
+                .iter()
+                .rev()
+                .find(|event| event.kind == AlertEventKind::Resolved)
+                .map(|event| event.created_at);
+            fired_at
+                .zip(resolved_at)
+                .map(|(fired_at, resolved_at)| (resolved_at - fired_at).whole_seconds())
+        } else {
+            None
+        };
+
+        Ok(AlertStats {
+            fired_last_24h,
+            time_to_resolve_secs,
+        })
+    }
+
+    /// Records a Slack message that was posted for an alert.
+    #[instrument(skip(self, tx))]
+    pub async fn slack_message_create(
+        &self,
+        tx: &mut Transaction<'_>,
+        alert_id: i64,
+        channel: &str,
+        channel_id: &str,
+        ts: &str,
+    ) -> Result<SlackMessage, DbError> {
+        let message = on_tx!(
+            tx,
+            sqlx::query_as(
+                "INSERT INTO slack_messages ( alert_id, channel, channel_id, ts, created_at )
+                 VALUES ( $1, $2, $3, $4, $5 )
+                 RETURNING *",
+            )
+            .bind(alert_id)
+            .bind(channel)
+            .bind(channel_id)
+            .bind(ts)
+            .bind(OffsetDateTime::now_utc())
+            .fetch_one(&mut **tx)
+            .await
+        )?;
+
+        Ok(message)
+    }
+
+    /// Records the chart that was uploaded to the thread of a Slack message.
+    #[instrument(skip(self, tx))]
+    pub async fn slack_message_set_chart_file_id(
+        &self,
+        tx: &mut Transaction<'_>,
+        slack_message_id: i64,
+        chart_file_id: &str,
+    ) -> Result<(), DbError> {
+        let result = on_tx!(
+            tx,
+            sqlx::query(
+                "UPDATE slack_messages
+                 SET chart_file_id = $1
+                 WHERE id = $2",
+            )
+            .bind(chart_file_id)
+            .bind(slack_message_id)
+            .execute(&mut **tx)
+            .await
+        )?;
+
+        match result.rows_affected() {
+            0 => Err(DbError::NotFound),
+            1 => Ok(()),
+            _ => Err(DbError::UnknownError),
+        }
+    }
+
+    /// Records the error budget chart that was uploaded to the thread of a
+    /// Slack message.
+    #[instrument(skip(self, tx))]
+    pub async fn slack_message_set_error_budget_file_id(
+        &self,
+        tx: &mut Transaction<'_>,
+        slack_message_id: i64,
+        error_budget_file_id: &str,
+    ) -> Result<(), DbError> {
+        let result = on_tx!(
+            tx,
+            sqlx::query(
+                "UPDATE slack_messages
+                 SET error_budget_file_id = $1
+                 WHERE id = $2",
+            )
+            .bind(error_budget_file_id)
+            .bind(slack_message_id)
+            .execute(&mut **tx)
+            .await
+        )?;
+
+        match result.rows_affected() {
+            0 => Err(DbError::NotFound),
+            1 => Ok(()),
+            _ => Err(DbError::UnknownError),
+        }
+    }
+
+    /// Forgets the charts that were uploaded to the threads of the Slack
+    /// messages for an alert, so the current charts are uploaded again.
+    #[instrument(skip(self, tx))]
+    pub async fn slack_message_clear_file_ids_by_alert(
+        &self,
+        tx: &mut Transaction<'_>,
+        alert_id: i64,
+    ) -> Result<(), DbError> {
+        on_tx!(
+            tx,
+            sqlx::query(
+                "UPDATE slack_messages
+                 SET chart_file_id = NULL, error_budget_file_id = NULL
+                 WHERE alert_id = $1",
+            )
+            .bind(alert_id)
+            .execute(&mut **tx)
+            .await
+        )?;
+
+        Ok(())
+    }
+
+    /// Deletes a Slack message that was deleted from Slack.
+    #[instrument(skip(self, tx))]
+    pub async fn slack_message_delete(
+        &self,
+        tx: &mut Transaction<'_>,
+        slack_message_id: i64,
+    ) -> Result<(), DbError> {
+        let result = on_tx!(
+            tx,
+            sqlx::query(
+                "DELETE FROM slack_messages
+                 WHERE id = $1",
+            )
+            .bind(slack_message_id)
+            .execute(&mut **tx)
+            .await
+        )?;
+
+        match result.rows_affected() {
+            0 => Err(DbError::NotFound),
+            1 => Ok(()),
+            _ => Err(DbError::UnknownError),
+        }
+    }
+
+    /// Returns all the Slack messages that were posted for an alert, in the
+    /// order they were posted.
+    #[instrument(skip(self, tx))]
+    pub async fn slack_message_list_by_alert(
+        &self,
+        tx: &mut Transaction<'_>,
+        alert_id: i64,
+    ) -> Result<Vec<SlackMessage>, DbError> {
+        let messages = on_tx!(
+            tx,
+            sqlx::query_as(
+                "SELECT *
+                 FROM slack_messages
+                 WHERE alert_id = $1
+                 ORDER BY id",
+            )
+            .bind(alert_id)
+            .fetch_all(&mut **tx)
+            .await
+        )?;
+
+        Ok(messages)
+    }
+
+    /// Records that the thread reply posted by the given event was posted to
+    /// the thread of a Slack message.
+    ///
+    /// The record is removed along with the event once it completes.
+    #[instrument(skip(self, tx))]
+    pub async fn slack_thread_reply_create(
+        &self,
+        tx: &mut Transaction<'_>,
+        event_id: i64,
+        slack_message_id: i64,
+    ) -> Result<(), DbError> {
+        on_tx!(
+            tx,
+            sqlx::query(
+                "INSERT INTO slack_thread_replies ( event_id, slack_message_id )
+                 VALUES ( $1, $2 )",
+            )
+            .bind(event_id)
+            .bind(slack_message_id)
+            .execute(&mut **tx)
+            .await
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the IDs of the Slack messages to which the thread reply posted
+    /// by the given event was posted already.
+    #[instrument(skip(self, tx))]
+    pub async fn slack_thread_reply_list_by_event(
+        &self,
+        tx: &mut Transaction<'_>,
+        event_id: i64,
+    ) -> Result<Vec<i64>, DbError> {
+        let rows: Vec<(i64,)> = on_tx!(
+            tx,
+            sqlx::query_as(
+                "SELECT slack_message_id
+                 FROM slack_thread_replies
+                 WHERE event_id = $1",
+            )
+            .bind(event_id)
+            .fetch_all(&mut **tx)
+            .await
+        )?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(slack_message_id,)| slack_message_id)
+            .collect())
+    }
+
+    /// Adds an event to the event queue.
+    ///
+    /// The event only becomes visible to the event loop once the transaction
+    /// is committed.
+    #[instrument(skip(self, tx))]
+    pub async fn event_enqueue(
+        &self,
+        tx: &mut Transaction<'_>,
+        event: &Event,
+    ) -> Result<i64, DbError> {
+        let (id,): (i64,) = on_tx!(
+            tx,
+            sqlx::query_as(
+                "INSERT INTO event_queue ( alert_id, payload, created_at )
+                 VALUES ( $1, $2, $3 )
+                 RETURNING id",
+            )
+            .bind(event.alert_id())
+            .bind(Json(event))
+            .bind(OffsetDateTime::now_utc())
+            .fetch_one(&mut **tx)
+            .await
+        )?;
+
+        Ok(id)
+    }
+
+    /// Adds an event to the event queue, that shouldn't be processed before
+    /// the given time.
+    ///
+    /// Unlike events that are waiting to be retried, scheduled events don't
+    /// hold up the events that are queued after them for the same alert.
+    #[instrument(skip(self, tx))]
+    pub async fn event_schedule(
+        &self,
+        tx: &mut Transaction<'_>,
+        event: &Event,
+        available_at: OffsetDateTime,
+    ) -> Result<i64, DbError> {
+        let (id,): (i64,) = on_tx!(
+            tx,
+            sqlx::query_as(
+                "INSERT INTO event_queue ( alert_id, payload, available_at, created_at )
+                 VALUES ( $1, $2, $3, $4 )
+                 RETURNING id",
+            )
+            .bind(event.alert_id())
+            .bind(Json(event))
+            .bind(available_at)
+            .bind(OffsetDateTime::now_utc())
+            .fetch_one(&mut **tx)
+            .await
+        )?;
+
+        Ok(id)
+    }
+
+    /// Claims the oldest unclaimed event in the queue that is available for
+    /// processing, if any.
+    ///
+    /// Events are only claimed once all earlier events for the same alert have
+    /// been completed, so events for a single alert are processed in order,
+    /// even when events for different alerts are processed concurrently.
+    /// Events that were scheduled for later are the exception: they let later
+    /// events go first while they wait, and wait in turn for those to finish.
+    ///
+    /// The claim expires after the given lease, after which the event may be
+    /// claimed again. This way events that were being processed by a replica
+    /// that crashed or was restarted still get processed, while events that
+    /// other replicas are processing are left alone.
+    #[instrument(skip(self, tx))]
+    pub async fn event_claim_next(
+        &self,
+        tx: &mut Transaction<'_>,
+        lease: Duration,
+    ) -> Result<Option<QueuedEvent>, DbError> {
+        // With multiple replicas on Postgres, the row lock prevents two
+        // replicas from claiming the same event. SQLite only allows a single
+        // writer anyway.
+        let query = format!(
+            "UPDATE event_queue
+             SET claimed_at = $1, claim_expires_at = $2, attempts = attempts + 1
+             WHERE id = (
+                SELECT id
+                FROM event_queue AS queued
+                WHERE (claimed_at IS NULL OR claim_expires_at <= $1)
+                    AND (available_at IS NULL OR available_at <= $1)
+                    AND NOT EXISTS (
+                        SELECT 1
+                        FROM event_queue AS other
+                        WHERE other.alert_id = queued.alert_id
+                            AND other.id <> queued.id
+                            AND (
+                                (other.id < queued.id AND NOT {scheduled_for_later})
+                                OR (other.claimed_at IS NOT NULL AND other.claim_expires_at > $1)
+                            )
+                    )
+                ORDER BY id
+                LIMIT 1
+                {skip_locked}
+             )
+             RETURNING *",
+            skip_locked = tx.skip_locked_clause(),
+            scheduled_for_later = scheduled_for_later("other"),
+        );
+        let now = OffsetDateTime::now_utc();
+        let event = on_tx!(
+            tx,
+            sqlx::query_as(&query)
+                .bind(now)
+                .bind(now + lease)
+                .fetch_optional(&mut **tx)
+                .await
+        )?;
+
+        Ok(event)
+    }
+
+    /// Returns the earliest time at which a queued event that is waiting to
+    /// be retried becomes available, or at which the claim on an event
+    /// expires.
+    ///
+    /// Events that are still waiting for an earlier event for the same alert
+    /// are not taken into account, since they cannot be claimed yet anyway.
+    #[instrument(skip(self, tx))]
+    pub async fn event_next_available_at(
+        &self,
+        tx: &mut Transaction<'_>,
+    ) -> Result<Option<OffsetDateTime>, DbError> {
+        let query = format!(
+            "SELECT MIN(CASE WHEN claimed_at IS NULL THEN available_at ELSE claim_expires_at END)
+             FROM event_queue AS queued
+             WHERE NOT EXISTS (
+                SELECT 1
+                FROM event_queue AS earlier
+                WHERE earlier.alert_id = queued.alert_id
+                    AND earlier.id < queued.id
+                    AND NOT {scheduled_for_later}
+             )",
+            scheduled_for_later = scheduled_for_later("earlier"),
+        );
+        let (available_at,): (Option<OffsetDateTime>,) = on_tx!(
+            tx,
+            sqlx::query_as(&query)
+                .bind(OffsetDateTime::now_utc())
+                .fetch_one(&mut **tx)
+                .await
+        )?;
+
+        Ok(available_at)
+    }
+
+    /// Releases a claimed event so it will be retried once `available_at` has
+    /// passed.
+    #[instrument(skip(self, tx))]
+    pub async fn event_retry_later(
+        &self,
+        tx: &mut Transaction<'_>,
+        event_id: i64,
+        available_at: OffsetDateTime,
+        error: &str,
+    ) -> Result<(), DbError> {
+        let result = on_tx!(
+            tx,
+            sqlx::query(
+                "UPDATE event_queue
+                 SET claimed_at = NULL, claim_expires_at = NULL, available_at = $1, last_error = $2
+                 WHERE id = $3",
+            )
+            .bind(available_at)
+            .bind(error)
+            .bind(event_id)
+            .execute(&mut **tx)
+            .await
+        )?;
+
+        match result.rows_affected() {
+            0 => Err(DbError::NotFound),
+            1 => Ok(()),
+            _ => Err(DbError::UnknownError),
+        }
+    }
+
+    /// Moves a queued event to the dead-letter events, so it won't be
+    /// processed anymore until it is replayed.
+    #[instrument(skip(self, tx))]
+    pub async fn event_move_to_dead_letter(
+        &self,
+        tx: &mut Transaction<'_>,
+        event_id: i64,
+        error: &str,
+    ) -> Result<DeadLetterEvent, DbError> {
+        let dead_letter_event = on_tx!(
+            tx,
+            sqlx::query_as(
+                "INSERT INTO dead_letter_events ( alert_id, payload, attempts, last_error, created_at, failed_at )
+                 SELECT alert_id, payload, attempts, $1, created_at, $2
+                 FROM event_queue
+                 WHERE id = $3
+                 RETURNING *",
+            )
+            .bind(error)
+            .bind(OffsetDateTime::now_utc())
+            .bind(event_id)
+            .fetch_one(&mut **tx)
+            .await
+        )?;
+
+        self.event_complete(tx, event_id).await?;
+
+        Ok(dead_letter_event)
+    }
+
+    #[instrument(skip(self, tx))]
+    pub async fn dead_letter_event_list(
+        &self,
+        tx: &mut Transaction<'_>,
+    ) -> Result<Vec<DeadLetterEvent>, DbError> {
+        let events = on_tx!(
+            tx,
+            sqlx::query_as(
+                "SELECT *
+                 FROM dead_letter_events
+                 ORDER BY id",
+            )
+            .fetch_all(&mut **tx)
+            .await
+        )?;
+
+        Ok(events)
+    }
+
+    /// Moves a dead-letter event back into the event queue, and returns the ID
+    /// of the newly queued event.
+    #[instrument(skip(self, tx))]
+    pub async fn dead_letter_event_replay(
+        &self,
+        tx: &mut Transaction<'_>,
+        dead_letter_event_id: i64,
+    ) -> Result<i64, DbError> {
+        let (event_id,): (i64,) = on_tx!(
+            tx,
+            sqlx::query_as(
+                "INSERT INTO event_queue ( alert_id, payload, created_at )
+                 SELECT alert_id, payload, $1
+                 FROM dead_letter_events
+                 WHERE id = $2
+                 RETURNING id",
+            )
+            .bind(OffsetDateTime::now_utc())
+            .bind(dead_letter_event_id)
+            .fetch_one(&mut **tx)
+            .await
+        )?;
+
+        on_tx!(
+            tx,
+            sqlx::query("DELETE FROM dead_letter_events WHERE id = $1")
+                .bind(dead_letter_event_id)
+                .execute(&mut **tx)
+                .await
+        )?;
+
+        Ok(event_id)
+    }
+
+    /// Removes a processed event from the queue.
+    #[instrument(skip(self, tx))]
+    pub async fn event_complete(
+        &self,
+        tx: &mut Transaction<'_>,
+        event_id: i64,
+    ) -> Result<(), DbError> {
+        let result = on_tx!(
+            tx,
+            sqlx::query("DELETE FROM event_queue WHERE id = $1")
+                .bind(event_id)
+                .execute(&mut **tx)
+                .await
+        )?;
+
+        match result.rows_affected() {
+            0 => Err(DbError::NotFound),
+            1 => Ok(()),
+            _ => Err(DbError::UnknownError),
+        }
+    }
+
+    #[instrument(skip(self))]
+    pub async fn start_transaction(&self) -> Result<Transaction<'_>, DbError> {
+        trace!("starting db transaction");
+        let tx = match &self.pool {
+            DbPool::Sqlite(pool) => Transaction::Sqlite(pool.begin().await?),
+            DbPool::Postgres(pool) => Transaction::Postgres(pool.begin().await?),
+        };
+        Ok(tx)
+    }
+
+    #[instrument(skip(self, tx))]
+    pub async fn commit(&self, tx: Transaction<'_>) -> Result<(), DbError> {
+        trace!("committing db transaction");
+        tx.commit().await
+    }
+}
+
+/// Returns the SQLite JSON path for the given top-level object key.
+fn json_path_for_key(key: &str) -> String {
+    format!("$.\"{}\"", key.replace('"', "\\\""))
+}
+
+/// Returns the condition that holds for events in the given table that were
+/// scheduled for later, and haven't been attempted yet. These don't hold up
+/// later events for the same alert. The current time is expected to be bound
+/// to `$1`.
+fn scheduled_for_later(table: &str) -> String {
+    format!(
+        "({table}.attempts = 0 AND {table}.available_at IS NOT NULL AND {table}.available_at > $1)"
+    )
+}
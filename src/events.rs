@@ -1,6 +1,10 @@
-use crate::db::models::Alert;
+use crate::db::models::AlertTransition;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+/// Events are persisted in the event queue before being processed by the event
+/// loop, so they survive restarts of the service.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Event {
     /// Fetches data from Prometheus and generates a chart for the given alert.
     ///
@@ -9,15 +13,52 @@ pub enum Event {
     ///
     /// If chart generation fails for whatever reason, it continues posting to
     /// Slack without a chart.
-    CreateChartAndPostToSlack { alert: Alert },
+    CreateChartAndPostToSlack { alert_id: i64 },
 
     /// Posts a new Slack message for the given alert.
-    PostSlackAlert { alert: Alert },
+    PostSlackAlert { alert_id: i64 },
 
     /// Fetches the alert with the given ID from the DB, and updates the
     /// corresponding Slack message if its timestamp is known.
+    ///
+    /// If charts are uploaded to Slack, they are uploaded to the threads of
+    /// the messages that don't have them yet.
     UpdateSlackAlert { alert_id: i64 },
 
-    /// Shuts down the service.
+    /// Generates the chart for the given alert again, so it shows the
+    /// resolution of the alert, and follows up with an `UpdateSlackAlert`
+    /// event.
+    ///
+    /// If the chart is uploaded to Slack, that event uploads the new chart.
+    RegenerateChart { alert_id: i64 },
+
+    /// Posts a reply about the given transition in the threads of the Slack
+    /// messages for the given alert.
+    PostSlackThreadReply {
+        alert_id: i64,
+        transition: AlertTransition,
+    },
+}
+
+impl Event {
+    /// Returns the ID of the alert this event applies to.
+    pub fn alert_id(&self) -> i64 {
+        match self {
+            Self::CreateChartAndPostToSlack { alert_id }
+            | Self::PostSlackAlert { alert_id }
+            | Self::UpdateSlackAlert { alert_id }
+            | Self::RegenerateChart { alert_id }
+            | Self::PostSlackThreadReply { alert_id, .. } => *alert_id,
+        }
+    }
+}
+
+/// Signals sent to the event loop over its channel.
+#[derive(Debug)]
+pub enum EventLoopSignal {
+    /// New events have been added to the event queue.
+    EventsQueued,
+
+    /// Shuts down the event loop.
     Shutdown,
 }
@@ -13,12 +13,16 @@ use autometrics::prometheus_exporter;
 use axum::Server;
 use clap::{Parser, ValueEnum};
 use db::Db;
-use events::Event;
+use events::EventLoopSignal;
 use opentelemetry::sdk::{trace, Resource};
 use opentelemetry::KeyValue;
 use opentelemetry_otlp::WithExportConfig;
-use service::event_loop::handle_events;
-use service::{ChartServiceConfig, PrometheusServiceConfig, Service, SlackServiceConfig};
+use service::event_loop::{handle_events, EventLoopConfig};
+use service::retention::{prune_periodically, RetentionConfig};
+use service::{
+    AdminServiceConfig, AlertmanagerServiceConfig, ChartServiceConfig, PrometheusServiceConfig,
+    Service, SlackServiceConfig,
+};
 use std::net::IpAddr;
 use std::process::ExitCode;
 use std::{env, io};
@@ -58,19 +62,32 @@ struct CliArguments {
 
 #[derive(Parser)]
 struct DbArguments {
-    /// Sqlite connection string.
+    /// Database connection string. Use `sqlite://` for a local SQLite file, or
+    /// `postgres://` to store everything in PostgreSQL.
     #[clap(long, env, default_value = "sqlite:///tmp/slack-app.db?mode=rwc")]
     db_connection_string: String,
 }
 
 #[derive(Parser)]
 struct ServeArguments {
+    #[clap(flatten)]
+    admin_config: AdminServiceConfig,
+
+    #[clap(flatten)]
+    alertmanager_config: AlertmanagerServiceConfig,
+
     #[clap(flatten)]
     chart_config: ChartServiceConfig,
 
     #[clap(flatten)]
     db: DbArguments,
 
+    #[clap(flatten)]
+    event_loop_config: EventLoopConfig,
+
+    #[clap(flatten)]
+    retention_config: RetentionConfig,
+
     /// Server port number
     #[clap(long, short, env, default_value = "3031")]
     port: u16,
@@ -180,17 +197,10 @@ fn initialize_logger(args: &CliArguments) -> Result<()> {
 
 async fn handle_serve(args: ServeArguments) -> Result<()> {
     let connection_string = &args.db.db_connection_string;
-    info!(connection_string, "Opening Sqlite DB");
-
-    let pool = sqlx::sqlite::SqlitePoolOptions::new()
-        .max_connections(5)
-        .acquire_timeout(std::time::Duration::from_secs(5))
-        .connect(connection_string)
-        .await?;
+    let db = Db::connect(connection_string).await?;
+    info!(backend = db.backend_name(), "Opened DB");
 
-    sqlx::migrate!("./migrations").run(&pool).await?;
-
-    let db = Db::new(pool.clone());
+    db.migrate().await?;
 
     let commit = option_env!("GITHUB_SHA").unwrap_or("unknown");
 
@@ -202,9 +212,11 @@ async fn handle_serve(args: ServeArguments) -> Result<()> {
         "Starting server"
     );
 
-    let (event_sender, event_receiver) = tokio::sync::mpsc::channel::<Event>(64);
+    let (event_sender, event_receiver) = tokio::sync::mpsc::channel::<EventLoopSignal>(64);
     let service = Service::new(
         args.base_url,
+        args.admin_config,
+        args.alertmanager_config,
         args.chart_config,
         db,
         event_sender.clone(),
@@ -215,10 +227,13 @@ async fn handle_serve(args: ServeArguments) -> Result<()> {
 
     let app = service::router::create_router(service.clone());
 
+    let retention_task = tokio::spawn(prune_periodically(service.clone(), args.retention_config));
+
+    let event_loop_config = args.event_loop_config;
     let service_task = tokio::spawn(async move {
         let mut service = service;
 
-        handle_events(&mut service, event_receiver)
+        handle_events(&mut service, event_loop_config, event_receiver)
             .await
             .expect("Unable to handle event");
     });
@@ -246,13 +261,14 @@ async fn handle_serve(args: ServeArguments) -> Result<()> {
     };
 
     event_sender
-        .send(Event::Shutdown)
+        .send(EventLoopSignal::Shutdown)
         .await
         .expect("Could not send shutdown event");
     shutdown_trigger
         .send(())
         .await
         .expect("Could not trigger shutdown signal");
+    retention_task.abort();
 
     let api_tasks = futures::future::join_all(vec![service_task, server_task]);
 
@@ -0,0 +1,44 @@
+use crate::db::DbError;
+use axum::extract::Json;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error, Serialize)]
+#[serde(tag = "error", content = "details", rename_all = "snake_case")]
+pub enum AdminHandlerError {
+    #[error("Database error: {0}")]
+    DatabaseError(DbError),
+
+    #[error("Admin endpoints are disabled")]
+    Disabled,
+
+    #[error("Entity not found")]
+    NotFound,
+
+    #[error("Missing or invalid credentials")]
+    Unauthorized,
+}
+
+impl From<DbError> for AdminHandlerError {
+    fn from(error: DbError) -> Self {
+        match error {
+            DbError::NotFound => AdminHandlerError::NotFound,
+            error => AdminHandlerError::DatabaseError(error),
+        }
+    }
+}
+
+impl IntoResponse for AdminHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match self {
+            Self::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Disabled => StatusCode::NOT_FOUND,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+        };
+
+        (status_code, Json(self)).into_response()
+    }
+}
@@ -0,0 +1,56 @@
+use super::AdminHandlerError;
+use crate::db::models::DeadLetterEvent;
+use crate::service::{Service, SLACK_APP_SLO};
+use autometrics::autometrics;
+use axum::extract::{Json, Path, State};
+use axum::http::HeaderMap;
+use serde::Serialize;
+use tracing::{info, instrument};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayedEvent {
+    /// ID of the event in the event queue.
+    pub event_id: i64,
+}
+
+#[autometrics(objective = SLACK_APP_SLO)]
+#[instrument(err, skip_all)]
+pub async fn dead_letter_events_list(
+    State(service): State<Service>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<DeadLetterEvent>>, AdminHandlerError> {
+    service.admin.authenticate(&headers)?;
+
+    let mut tx = service.db.start_transaction().await?;
+    let events = service.db.dead_letter_event_list(&mut tx).await?;
+    service.db.commit(tx).await?;
+
+    Ok(Json(events))
+}
+
+#[autometrics(objective = SLACK_APP_SLO)]
+#[instrument(err, skip(service, headers))]
+pub async fn dead_letter_event_replay(
+    State(service): State<Service>,
+    headers: HeaderMap,
+    Path(dead_letter_event_id): Path<i64>,
+) -> Result<Json<ReplayedEvent>, AdminHandlerError> {
+    service.admin.authenticate(&headers)?;
+
+    let mut tx = service.db.start_transaction().await?;
+    let event_id = service
+        .db
+        .dead_letter_event_replay(&mut tx, dead_letter_event_id)
+        .await?;
+    service.db.commit(tx).await?;
+
+    info!(
+        dead_letter_event_id,
+        event_id, "Replaying dead-letter event"
+    );
+
+    service.notify_event_loop();
+
+    Ok(Json(ReplayedEvent { event_id }))
+}
@@ -0,0 +1,49 @@
+mod errors;
+#[cfg(test)]
+mod tests;
+
+pub mod handlers;
+
+use super::auth::has_bearer_token;
+use axum::http::HeaderMap;
+use secrecy::SecretString;
+
+pub use errors::AdminHandlerError;
+
+#[derive(clap::Args, Debug)]
+pub struct AdminServiceConfig {
+    /// Bearer token for accessing the admin endpoints.
+    ///
+    /// If no token is set, the admin endpoints are disabled.
+    #[clap(long, env, help_heading = "Admin")]
+    admin_token: Option<SecretString>,
+}
+
+#[cfg(test)]
+impl AdminServiceConfig {
+    pub fn new_test_config() -> Self {
+        Self {
+            admin_token: Some("admin-s3cr3t".to_owned().into()),
+        }
+    }
+}
+
+pub struct AdminService {
+    config: AdminServiceConfig,
+}
+
+impl AdminService {
+    pub fn new(config: AdminServiceConfig) -> Self {
+        Self { config }
+    }
+
+    /// Verifies the bearer token sent along with a request to an admin
+    /// endpoint.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Result<(), AdminHandlerError> {
+        match self.config.admin_token.as_ref() {
+            Some(token) if has_bearer_token(headers, token) => Ok(()),
+            Some(_) => Err(AdminHandlerError::Unauthorized),
+            None => Err(AdminHandlerError::Disabled),
+        }
+    }
+}
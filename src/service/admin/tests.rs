@@ -0,0 +1,110 @@
+use crate::db::models::NewAlert;
+use crate::events::Event;
+use crate::service::admin::*;
+use crate::testutil::*;
+use axum::extract::{Path, State};
+use axum::headers::{Authorization, HeaderMapExt};
+use axum::http::HeaderMap;
+
+fn admin_headers(token: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.typed_insert(Authorization::bearer(token).unwrap());
+    headers
+}
+
+#[tokio::test]
+async fn admin_rejects_invalid_token() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { service, .. }| async move {
+            // act
+            let missing_token_result =
+                handlers::dead_letter_events_list(State(service.clone()), HeaderMap::new()).await;
+            let invalid_token_result =
+                handlers::dead_letter_events_list(State(service), admin_headers("wrong")).await;
+
+            // assert
+            assert_matches!(missing_token_result, Err(AdminHandlerError::Unauthorized));
+            assert_matches!(invalid_token_result, Err(AdminHandlerError::Unauthorized));
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn admin_lists_and_replays_dead_letter_events() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let mut tx = db.start_transaction().await.unwrap();
+            let alert = db
+                .alert_create(
+                    &mut tx,
+                    NewAlert {
+                        text: "Something is wrong".to_owned(),
+                        resolved: false,
+                        fingerprint: Some("01234".to_owned()),
+                        notebook_id: None,
+                        chart_filename: None,
+                        slack_channel: None,
+                        slack_ts: None,
+                        sloth_slo: None,
+                        sloth_service: None,
+                        objective_name: None,
+                        severity: None,
+                        labels: Default::default(),
+                        annotations: Default::default(),
+                        generator_url: None,
+                        starts_at: None,
+                        ends_at: None,
+                    },
+                )
+                .await
+                .unwrap();
+            let event = Event::PostSlackAlert { alert_id: alert.id };
+            let event_id = db.event_enqueue(&mut tx, &event).await.unwrap();
+            db.event_move_to_dead_letter(&mut tx, event_id, "Slack is down")
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // act
+            let listed_events = handlers::dead_letter_events_list(
+                State(service.clone()),
+                admin_headers("admin-s3cr3t"),
+            )
+            .await
+            .expect("Error listing dead-letter events");
+
+            let replayed_event = handlers::dead_letter_event_replay(
+                State(service.clone()),
+                admin_headers("admin-s3cr3t"),
+                Path(listed_events[0].id),
+            )
+            .await
+            .expect("Error replaying dead-letter event");
+
+            let unknown_event_result = handlers::dead_letter_event_replay(
+                State(service),
+                admin_headers("admin-s3cr3t"),
+                Path(listed_events[0].id),
+            )
+            .await;
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let queued_event = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap().unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(listed_events.len(), 1);
+            assert_eq!(listed_events[0].payload.0, event);
+            assert_eq!(queued_event.id, replayed_event.event_id);
+            assert_eq!(queued_event.payload.0, event);
+            assert_matches!(unknown_event_result, Err(AdminHandlerError::NotFound));
+        },
+    )
+    .await;
+}
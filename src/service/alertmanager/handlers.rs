@@ -1,19 +1,25 @@
 use super::{
-    create_alert_text, get_label, AlertmanagerWebhookHandlerError, AlertmanagerWebhookPayload,
+    create_alert_text, get_label, is_resolved_from_slack_only, AlertmanagerWebhookHandlerError,
+    AlertmanagerWebhookPayload,
 };
-use crate::db::models::NewAlert;
+use crate::db::models::{AlertEventKind, AlertTransition, NewAlert};
 use crate::events::Event;
 use crate::service::{Service, SLACK_APP_SLO};
 use autometrics::autometrics;
 use axum::extract::{Json, State};
+use axum::http::HeaderMap;
+use time::OffsetDateTime;
 use tracing::{debug, instrument};
 
 #[autometrics(objective = SLACK_APP_SLO)]
-#[instrument(err, skip(service))]
+#[instrument(err, skip(service, headers))]
 pub async fn receive_alertmanager_webhook(
     State(service): State<Service>,
+    headers: HeaderMap,
     Json(payload): Json<AlertmanagerWebhookPayload>,
 ) -> Result<String, AlertmanagerWebhookHandlerError> {
+    service.alertmanager.authenticate_webhook(&headers)?;
+
     debug!(?payload, "Received alertmanager webhook");
 
     if payload.version != "4" {
@@ -22,34 +28,67 @@ pub async fn receive_alertmanager_webhook(
         ));
     }
 
+    let now = OffsetDateTime::now_utc();
     let mut tx = service.db.start_transaction().await?;
 
     for alert in &payload.alerts {
+        let resolved = alert.status.is_resolved();
         let existing_alert = service
             .db
-            .alert_get_by_fingerprint(&mut tx, &alert.fingerprint)
-            .await?;
+            .alert_get_latest_by_fingerprint(&mut tx, &alert.fingerprint)
+            .await?
+            .filter(|existing_alert| {
+                // An alert that fires again after it was resolved becomes a
+                // new occurrence, unless it happens within the quiet period.
+                resolved || service.alertmanager.reopens_occurrence(existing_alert, now)
+            });
 
         if let Some(mut existing_alert) = existing_alert {
-            let resolved = alert.status.is_resolved();
+            if is_resolved_from_slack_only(&existing_alert) {
+                // Notifications about an alert that is still firing don't
+                // undo its resolution from Slack. Once Alertmanager resolves
+                // it as well, that time is recorded, so the quiet period
+                // starts from there.
+                if resolved {
+                    existing_alert.ends_at = alert.resolved_at();
+                    service.db.alert_update(&mut tx, &existing_alert).await?;
+                }
+                continue;
+            }
+
             if existing_alert.resolved == resolved {
                 continue;
             }
 
             existing_alert.resolved = resolved;
+            if !resolved {
+                // The alert fired again, so it is no longer resolved by
+                // whoever resolved it before.
+                existing_alert.resolved_by = None;
+            }
+            existing_alert.ends_at = alert.resolved_at();
+            existing_alert.labels = alert.labels.clone().into();
+            existing_alert.annotations = alert.annotations.clone().into();
 
             service.db.alert_update(&mut tx, &existing_alert).await?;
 
+            let transition = if resolved {
+                AlertTransition::Resolved
+            } else {
+                AlertTransition::Refired
+            };
+            service
+                .db
+                .alert_event_create(&mut tx, existing_alert.id, transition.into(), None)
+                .await?;
+
             service
-                .event_sender
-                .send(Event::UpdateSlackAlert {
-                    alert_id: existing_alert.id,
-                })
+                .queue_alert_update(&mut tx, &existing_alert, Some(transition))
                 .await?;
         } else {
             let new_alert = NewAlert {
                 text: create_alert_text(alert, &payload),
-                resolved: alert.status.is_resolved(),
+                resolved,
                 fingerprint: Some(alert.fingerprint.clone()),
                 chart_filename: None, // Will be filled in later, if applicable.
                 notebook_id: None,
@@ -59,18 +98,40 @@ pub async fn receive_alertmanager_webhook(
                 sloth_service: get_label(alert, &payload, "sloth_service").map(str::to_owned),
                 objective_name: get_label(alert, &payload, "objective_name").map(str::to_owned),
                 severity: get_label(alert, &payload, "severity").map(str::to_owned),
+                labels: alert.labels.clone(),
+                annotations: alert.annotations.clone(),
+                generator_url: Some(alert.generator_url.clone()).filter(|url| !url.is_empty()),
+                starts_at: Some(alert.starts_at),
+                ends_at: alert.resolved_at(),
             };
 
             let db_alert = service.db.alert_create(&mut tx, new_alert).await?;
 
             service
-                .event_sender
-                .send(Event::CreateChartAndPostToSlack { alert: db_alert })
+                .db
+                .alert_event_create(&mut tx, db_alert.id, AlertEventKind::Fired, None)
+                .await?;
+            if resolved {
+                service
+                    .db
+                    .alert_event_create(&mut tx, db_alert.id, AlertEventKind::Resolved, None)
+                    .await?;
+            }
+
+            service
+                .queue_event(
+                    &mut tx,
+                    Event::CreateChartAndPostToSlack {
+                        alert_id: db_alert.id,
+                    },
+                )
                 .await?;
         }
     }
 
     service.db.commit(tx).await?;
 
+    service.notify_event_loop();
+
     Ok("ok".to_owned())
 }
@@ -1,14 +1,232 @@
 mod errors;
+mod silences;
 #[cfg(test)]
 mod tests;
 
 pub mod handlers;
 
+use super::auth::{has_basic_auth, has_bearer_token};
+use crate::db::models::Alert;
+use axum::http::HeaderMap;
+use reqwest::Client;
+use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
+use silences::{PostSilenceResponse, PostableSilence};
 use sqlx::types::time::OffsetDateTime;
 use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::debug;
+use url::Url;
 
-pub use errors::AlertmanagerWebhookHandlerError;
+pub use errors::{AlertmanagerServiceError, AlertmanagerWebhookHandlerError};
+
+#[derive(clap::Args, Debug)]
+pub struct AlertmanagerServiceConfig {
+    /// Base URL on which Alertmanager can be reached.
+    ///
+    /// This is used for creating silences from Slack. If it is not set,
+    /// silences are only recorded in the Slack message.
+    #[clap(long, env, help_heading = "Alertmanager")]
+    alertmanager_url: Option<Url>,
+
+    /// Bearer token Alertmanager must send along with its webhook requests.
+    ///
+    /// This corresponds to the `authorization` setting in the `http_config`
+    /// of the Alertmanager webhook receiver.
+    #[clap(long, env, help_heading = "Alertmanager webhook")]
+    alertmanager_webhook_bearer_token: Option<SecretString>,
+
+    /// Username Alertmanager must send using HTTP basic auth.
+    ///
+    /// This corresponds to the `basic_auth` setting in the `http_config` of
+    /// the Alertmanager webhook receiver.
+    #[clap(
+        long,
+        env,
+        requires = "alertmanager_webhook_password",
+        help_heading = "Alertmanager webhook"
+    )]
+    alertmanager_webhook_username: Option<String>,
+
+    /// Password Alertmanager must send using HTTP basic auth.
+    #[clap(
+        long,
+        env,
+        requires = "alertmanager_webhook_username",
+        help_heading = "Alertmanager webhook"
+    )]
+    alertmanager_webhook_password: Option<SecretString>,
+
+    /// Minimum time an alert must have been resolved, in seconds, before it
+    /// firing again is treated as a new occurrence, with its own Slack message.
+    ///
+    /// Alerts that fire again sooner reopen their latest occurrence instead,
+    /// which avoids flooding the channel with alerts that are flapping.
+    #[clap(long, env, default_value = "0", help_heading = "Alertmanager webhook")]
+    alert_quiet_period_secs: u64,
+}
+
+#[cfg(test)]
+impl AlertmanagerServiceConfig {
+    pub fn new_test_config() -> Self {
+        Self {
+            alertmanager_url: None,
+            alertmanager_webhook_bearer_token: None,
+            alertmanager_webhook_username: None,
+            alertmanager_webhook_password: None,
+            alert_quiet_period_secs: 0,
+        }
+    }
+}
+
+pub struct AlertmanagerService {
+    client: Client,
+    config: AlertmanagerServiceConfig,
+}
+
+impl AlertmanagerService {
+    pub fn new(config: AlertmanagerServiceConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .expect("Error building reqwest client");
+
+        Self { client, config }
+    }
+
+    /// Returns whether an alert that fires again should reopen the given
+    /// occurrence, rather than create a new one.
+    ///
+    /// Occurrences that are still firing are always reused, and so are
+    /// occurrences that were only resolved from Slack, since Alertmanager
+    /// keeps notifying about those until it resolves them too. Occurrences
+    /// that Alertmanager resolved are only reopened within the quiet period.
+    pub fn reopens_occurrence(&self, latest: &Alert, now: OffsetDateTime) -> bool {
+        if !latest.resolved || is_resolved_from_slack_only(latest) {
+            return true;
+        }
+
+        let resolved_at = latest.ends_at.unwrap_or(latest.updated_at);
+        let quiet_period = time::Duration::seconds(
+            i64::try_from(self.config.alert_quiet_period_secs).unwrap_or(i64::MAX),
+        );
+        // A long enough quiet period never ends.
+        resolved_at
+            .checked_add(quiet_period)
+            .map_or(true, |quiet_period_end| now < quiet_period_end)
+    }
+
+    /// Returns whether silences can be created in Alertmanager.
+    pub fn silences_enabled(&self) -> bool {
+        self.config.alertmanager_url.is_some()
+    }
+
+    /// Creates a silence in Alertmanager matching the given labels, and
+    /// returns its ID.
+    pub async fn create_silence(
+        &self,
+        labels: &BTreeMap<String, String>,
+        starts_at: OffsetDateTime,
+        ends_at: OffsetDateTime,
+        created_by: String,
+        comment: String,
+    ) -> Result<String, AlertmanagerServiceError> {
+        let url = self.api_url(&["silences"])?;
+        let silence = PostableSilence::for_labels(labels, starts_at, ends_at, created_by, comment);
+
+        debug!(?silence, "Creating Alertmanager silence");
+
+        let response: PostSilenceResponse = self
+            .client
+            .post(url)
+            .json(&silence)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| AlertmanagerServiceError::Http(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| {
+                AlertmanagerServiceError::Deserialization(format!(
+                    "Could not deserialize Alertmanager response: {err}"
+                ))
+            })?;
+
+        Ok(response.silence_id)
+    }
+
+    /// Expires the Alertmanager silence with the given ID.
+    pub async fn expire_silence(&self, silence_id: &str) -> Result<(), AlertmanagerServiceError> {
+        let url = self.api_url(&["silence", silence_id])?;
+
+        debug!(?silence_id, "Expiring Alertmanager silence");
+
+        self.client
+            .delete(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| AlertmanagerServiceError::Http(err.to_string()))?;
+
+        Ok(())
+    }
+
+    fn api_url(&self, segments: &[&str]) -> Result<Url, AlertmanagerServiceError> {
+        let Some(base_url) = self.config.alertmanager_url.as_ref() else {
+            return Err(AlertmanagerServiceError::Config(
+                "No Alertmanager URL configured".to_owned(),
+            ));
+        };
+
+        let mut url = base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| {
+                AlertmanagerServiceError::Config(format!(
+                    "Cannot append to Alertmanager base URL: {base_url}"
+                ))
+            })?
+            .pop_if_empty()
+            .extend(["api", "v2"])
+            .extend(segments);
+
+        Ok(url)
+    }
+
+    /// Verifies the credentials sent along with an incoming webhook request.
+    ///
+    /// If no credentials are configured, all requests are accepted. If both a
+    /// bearer token and basic auth credentials are configured, a request
+    /// matching either of them is accepted.
+    pub fn authenticate_webhook(
+        &self,
+        headers: &HeaderMap,
+    ) -> Result<(), AlertmanagerWebhookHandlerError> {
+        let config = &self.config;
+        let basic_auth = config
+            .alertmanager_webhook_username
+            .as_ref()
+            .zip(config.alertmanager_webhook_password.as_ref());
+
+        if config.alertmanager_webhook_bearer_token.is_none() && basic_auth.is_none() {
+            return Ok(());
+        }
+
+        let bearer_matches = config
+            .alertmanager_webhook_bearer_token
+            .as_ref()
+            .map(|token| has_bearer_token(headers, token))
+            .unwrap_or(false);
+        let basic_auth_matches = basic_auth
+            .map(|(username, password)| has_basic_auth(headers, username, password))
+            .unwrap_or(false);
+
+        if bearer_matches || basic_auth_matches {
+            Ok(())
+        } else {
+            Err(AlertmanagerWebhookHandlerError::Unauthorized)
+        }
+    }
+}
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -35,6 +253,16 @@ pub struct AlertmanagerAlert {
     ends_at: OffsetDateTime,
 }
 
+impl AlertmanagerAlert {
+    /// Returns the time at which the alert was resolved, if it is resolved.
+    ///
+    /// Alertmanager also sends an `endsAt` for firing alerts, but it is either
+    /// zero or merely an estimate, so we ignore it.
+    fn resolved_at(&self) -> Option<OffsetDateTime> {
+        self.status.is_resolved().then_some(self.ends_at)
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AlertmanagerWebhookPayload {
@@ -138,3 +366,9 @@ fn get_label<'a>(
         .or_else(|| payload.common_labels.get(key))
         .map(String::as_str)
 }
+
+/// Returns whether the alert was resolved from Slack, but not (yet) by
+/// Alertmanager.
+fn is_resolved_from_slack_only(alert: &Alert) -> bool {
+    alert.resolved && alert.resolved_by.is_some() && alert.ends_at.is_none()
+}
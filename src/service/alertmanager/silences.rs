@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+
+/// A silence as accepted by the `POST /api/v2/silences` endpoint.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostableSilence {
+    pub matchers: Vec<Matcher>,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub starts_at: OffsetDateTime,
+
+    #[serde(with = "time::serde::rfc3339")]
+    pub ends_at: OffsetDateTime,
+
+    pub created_by: String,
+
+    pub comment: String,
+}
+
+impl PostableSilence {
+    /// Creates a silence that matches exactly the given label set.
+    pub fn for_labels(
+        labels: &BTreeMap<String, String>,
+        starts_at: OffsetDateTime,
+        ends_at: OffsetDateTime,
+        created_by: String,
+        comment: String,
+    ) -> Self {
+        let matchers = labels
+            .iter()
+            .map(|(name, value)| Matcher {
+                name: name.clone(),
+                value: value.clone(),
+                is_regex: false,
+                is_equal: true,
+            })
+            .collect();
+
+        Self {
+            matchers,
+            starts_at,
+            ends_at,
+            created_by,
+            comment,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Matcher {
+    pub name: String,
+    pub value: String,
+    pub is_regex: bool,
+    pub is_equal: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostSilenceResponse {
+    #[serde(rename = "silenceID")]
+    pub silence_id: String,
+}
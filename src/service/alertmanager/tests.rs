@@ -1,9 +1,62 @@
-use crate::db::models::Alert;
+use crate::db::models::{Alert, AlertEventKind};
+use crate::events::Event;
 use crate::service::alertmanager::*;
+use crate::service::charts::RECOVERY_WINDOW;
+use crate::service::Service;
 use crate::testutil::*;
 use axum::extract::State;
+use axum::headers::{Authorization, HeaderMapExt};
+use axum::http::HeaderMap;
 use axum::Json;
 use sqlx::types::time::OffsetDateTime;
+use std::sync::Arc;
+
+fn firing_payload(fingerprint: &str) -> AlertmanagerWebhookPayload {
+    let now = OffsetDateTime::now_utc();
+    AlertmanagerWebhookPayload {
+        alerts: vec![AlertmanagerAlert {
+            fingerprint: fingerprint.to_owned(),
+            generator_url: Default::default(),
+            annotations: Default::default(),
+            labels: Default::default(),
+            status: AlertStatus::Firing,
+            starts_at: now,
+            ends_at: now,
+        }],
+        status: AlertStatus::Firing,
+        version: "4".to_string(),
+        ..Default::default()
+    }
+}
+
+fn with_webhook_auth(service: Service, config: AlertmanagerServiceConfig) -> Service {
+    Service {
+        alertmanager: Arc::new(AlertmanagerService::new(config)),
+        ..service
+    }
+}
+
+fn bearer_token_config() -> AlertmanagerServiceConfig {
+    AlertmanagerServiceConfig {
+        alertmanager_webhook_bearer_token: Some("s3cr3t".to_owned().into()),
+        ..AlertmanagerServiceConfig::new_test_config()
+    }
+}
+
+fn quiet_period_config() -> AlertmanagerServiceConfig {
+    AlertmanagerServiceConfig {
+        alert_quiet_period_secs: 3600,
+        ..AlertmanagerServiceConfig::new_test_config()
+    }
+}
+
+fn basic_auth_config() -> AlertmanagerServiceConfig {
+    AlertmanagerServiceConfig {
+        alertmanager_webhook_username: Some("alertmanager".to_owned()),
+        alertmanager_webhook_password: Some("s3cr3t".to_owned().into()),
+        ..AlertmanagerServiceConfig::new_test_config()
+    }
+}
 
 #[tokio::test]
 async fn alerts_create_new_unresolved() {
@@ -29,12 +82,19 @@ async fn alerts_create_new_unresolved() {
             };
 
             // act
-            handlers::receive_alertmanager_webhook(State(service.clone()), Json(payload))
-                .await
-                .expect("Error receiving firing alert");
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .expect("Error receiving firing alert");
 
             let mut tx = db.start_transaction().await.unwrap();
-            let alert = db.alert_get_by_fingerprint(&mut tx, "12345").await.unwrap();
+            let alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "12345")
+                .await
+                .unwrap();
             tx.commit().await.unwrap();
 
             // assert
@@ -74,12 +134,19 @@ async fn alerts_create_new_resolved() {
             };
 
             // act
-            handlers::receive_alertmanager_webhook(State(service.clone()), Json(payload))
-                .await
-                .expect("Error receiving resolved alert");
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .expect("Error receiving resolved alert");
 
             let mut tx = db.start_transaction().await.unwrap();
-            let alert = db.alert_get_by_fingerprint(&mut tx, "23456").await.unwrap();
+            let alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "23456")
+                .await
+                .unwrap();
             tx.commit().await.unwrap();
 
             // assert
@@ -118,19 +185,30 @@ async fn alerts_create_and_update() {
                 ..Default::default()
             };
 
-            handlers::receive_alertmanager_webhook(State(service.clone()), Json(payload.clone()))
-                .await
-                .expect("Error receiving original alert");
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload.clone()),
+            )
+            .await
+            .expect("Error receiving original alert");
 
             payload.alerts[0].status = AlertStatus::Resolved;
 
             // act
-            handlers::receive_alertmanager_webhook(State(service.clone()), Json(payload))
-                .await
-                .expect("Error receiving updated alert");
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .expect("Error receiving updated alert");
 
             let mut tx = db.start_transaction().await.unwrap();
-            let alert = db.alert_get_by_fingerprint(&mut tx, "34567").await.unwrap();
+            let alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "34567")
+                .await
+                .unwrap();
             tx.commit().await.unwrap();
 
             // assert
@@ -146,6 +224,359 @@ async fn alerts_create_and_update() {
     .await;
 }
 
+#[tokio::test]
+async fn alerts_reject_missing_bearer_token() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let service = with_webhook_auth(service, bearer_token_config());
+
+            // act
+            let result = handlers::receive_alertmanager_webhook(
+                State(service),
+                HeaderMap::new(),
+                Json(firing_payload("45678")),
+            )
+            .await;
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "45678")
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_matches!(result, Err(AlertmanagerWebhookHandlerError::Unauthorized));
+            assert_matches!(alert, None);
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_reject_invalid_bearer_token() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { service, .. }| async move {
+            // arrange
+            let service = with_webhook_auth(service, bearer_token_config());
+            let mut headers = HeaderMap::new();
+            headers.typed_insert(Authorization::bearer("wrong").unwrap());
+
+            // act
+            let result = handlers::receive_alertmanager_webhook(
+                State(service),
+                headers,
+                Json(firing_payload("56789")),
+            )
+            .await;
+
+            // assert
+            assert_matches!(result, Err(AlertmanagerWebhookHandlerError::Unauthorized));
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_accept_valid_bearer_token() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let service = with_webhook_auth(service, bearer_token_config());
+            let mut headers = HeaderMap::new();
+            headers.typed_insert(Authorization::bearer("s3cr3t").unwrap());
+
+            // act
+            handlers::receive_alertmanager_webhook(
+                State(service),
+                headers,
+                Json(firing_payload("67890")),
+            )
+            .await
+            .expect("Error receiving authenticated alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "67890")
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_matches!(alert, Some(Alert { .. }));
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_reject_invalid_basic_auth() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { service, .. }| async move {
+            // arrange
+            let service = with_webhook_auth(service, basic_auth_config());
+            let mut headers = HeaderMap::new();
+            headers.typed_insert(Authorization::basic("alertmanager", "wrong"));
+
+            // act
+            let result = handlers::receive_alertmanager_webhook(
+                State(service),
+                headers,
+                Json(firing_payload("78901")),
+            )
+            .await;
+
+            // assert
+            assert_matches!(result, Err(AlertmanagerWebhookHandlerError::Unauthorized));
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_accept_valid_basic_auth() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let service = with_webhook_auth(service, basic_auth_config());
+            let mut headers = HeaderMap::new();
+            headers.typed_insert(Authorization::basic("alertmanager", "s3cr3t"));
+
+            // act
+            handlers::receive_alertmanager_webhook(
+                State(service),
+                headers,
+                Json(firing_payload("89012")),
+            )
+            .await
+            .expect("Error receiving authenticated alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "89012")
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_matches!(alert, Some(Alert { .. }));
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_persist_labels_and_annotations() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let starts_at = OffsetDateTime::UNIX_EPOCH;
+            let ends_at = starts_at + time::Duration::minutes(30);
+            let labels = BTreeMap::from([
+                ("alertname".to_owned(), "InstanceDown".to_owned()),
+                ("team".to_owned(), "payments".to_owned()),
+            ]);
+            let annotations =
+                BTreeMap::from([("runbook_url".to_owned(), "https://runbooks".to_owned())]);
+            let mut payload = AlertmanagerWebhookPayload {
+                alerts: vec![AlertmanagerAlert {
+                    fingerprint: "90123".to_owned(),
+                    generator_url: "http://prometheus/graph?g0.expr=up".to_owned(),
+                    annotations: annotations.clone(),
+                    labels: labels.clone(),
+                    status: AlertStatus::Firing,
+                    starts_at,
+                    ends_at,
+                }],
+                status: AlertStatus::Firing,
+                version: "4".to_string(),
+                ..Default::default()
+            };
+
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload.clone()),
+            )
+            .await
+            .expect("Error receiving firing alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let firing_alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "90123")
+                .await
+                .unwrap()
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            payload.alerts[0].status = AlertStatus::Resolved;
+
+            // act
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .expect("Error receiving resolved alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let resolved_alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "90123")
+                .await
+                .unwrap()
+                .unwrap();
+            let alerts_for_team = db
+                .alert_list_by_label(&mut tx, "team", "payments")
+                .await
+                .unwrap();
+            let alerts_for_other_team = db
+                .alert_list_by_label(&mut tx, "team", "search")
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(*firing_alert.labels, labels);
+            assert_eq!(*firing_alert.annotations, annotations);
+            assert_eq!(
+                firing_alert.generator_url.as_deref(),
+                Some("http://prometheus/graph?g0.expr=up")
+            );
+            assert_eq!(firing_alert.starts_at, Some(starts_at));
+            assert_eq!(firing_alert.ends_at, None);
+            assert_eq!(resolved_alert.ends_at, Some(ends_at));
+            assert_eq!(alerts_for_team.len(), 1);
+            assert_eq!(alerts_for_team[0].id, resolved_alert.id);
+            assert!(alerts_for_other_team.is_empty());
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_queue_events() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let mut payload = firing_payload("01234");
+
+            // act
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload.clone()),
+            )
+            .await
+            .expect("Error receiving firing alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "01234")
+                .await
+                .unwrap()
+                .unwrap();
+            let create_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            db.event_complete(&mut tx, create_event.id).await.unwrap();
+            tx.commit().await.unwrap();
+
+            payload.alerts[0].status = AlertStatus::Resolved;
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .expect("Error receiving resolved alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let update_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            let no_event = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(
+                create_event.payload.0,
+                Event::CreateChartAndPostToSlack { alert_id: alert.id }
+            );
+            assert_eq!(
+                update_event.payload.0,
+                Event::UpdateSlackAlert { alert_id: alert.id }
+            );
+            assert_matches!(no_event, None);
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_replay_interrupted_events() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(firing_payload("12340")),
+            )
+            .await
+            .expect("Error receiving firing alert");
+
+            // The claim expires right away, like that of a replica that
+            // stopped while processing the event.
+            let mut tx = db.start_transaction().await.unwrap();
+            let claimed_event = db
+                .event_claim_next(&mut tx, time::Duration::ZERO)
+                .await
+                .unwrap()
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // act
+            let mut tx = db.start_transaction().await.unwrap();
+            let replayed_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            let no_event = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(replayed_event.attempts, 2);
+            assert_matches!(no_event, None);
+            assert_eq!(replayed_event.id, claimed_event.id);
+            assert_eq!(replayed_event.payload.0, claimed_event.payload.0);
+        },
+    )
+    .await;
+}
+
 #[test]
 fn test_alert_text_instance_down() {
     use super::create_alert_text;
@@ -244,3 +675,409 @@ fn test_alert_text_unknown_slo() {
         "SLO \"other\" in danger for \"slack-app\" [environment=dev]"
     );
 }
+
+#[test]
+fn test_silence_matches_all_labels() {
+    use super::silences::PostableSilence;
+
+    // arrange
+    let starts_at = OffsetDateTime::UNIX_EPOCH;
+    let ends_at = starts_at + time::Duration::hours(1);
+    let labels = BTreeMap::from([
+        ("alertname".to_owned(), "InstanceDown".to_owned()),
+        ("instance".to_owned(), "api-1".to_owned()),
+    ]);
+
+    // act
+    let silence = PostableSilence::for_labels(
+        &labels,
+        starts_at,
+        ends_at,
+        "jane".to_owned(),
+        "Silenced from Slack".to_owned(),
+    );
+
+    // assert
+    assert_eq!(
+        serde_json::to_value(&silence).unwrap(),
+        serde_json::json!({
+            "matchers": [
+                { "name": "alertname", "value": "InstanceDown", "isRegex": false, "isEqual": true },
+                { "name": "instance", "value": "api-1", "isRegex": false, "isEqual": true },
+            ],
+            "startsAt": "1970-01-01T00:00:00Z",
+            "endsAt": "1970-01-01T01:00:00Z",
+            "createdBy": "jane",
+            "comment": "Silenced from Slack",
+        })
+    );
+}
+
+#[tokio::test]
+async fn alerts_create_new_occurrence_after_resolution() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let mut payload = firing_payload("67890");
+            for status in [AlertStatus::Firing, AlertStatus::Resolved] {
+                payload.alerts[0].status = status;
+                handlers::receive_alertmanager_webhook(
+                    State(service.clone()),
+                    HeaderMap::new(),
+                    Json(payload.clone()),
+                )
+                .await
+                .expect("Error receiving alert");
+            }
+
+            // act
+            payload.alerts[0].status = AlertStatus::Firing;
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .expect("Error receiving re-fired alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let occurrences = db
+                .alert_list_by_fingerprint(&mut tx, "67890")
+                .await
+                .unwrap();
+            let mut events = Vec::new();
+            while let Some(event) = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap() {
+                db.event_complete(&mut tx, event.id).await.unwrap();
+                events.push(event.payload.0);
+            }
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(occurrences.len(), 2);
+            assert!(!occurrences[0].resolved);
+            assert_eq!(occurrences[0].occurrence, 2);
+            assert!(occurrences[1].resolved);
+            assert_eq!(occurrences[1].occurrence, 1);
+            assert_eq!(
+                events.last(),
+                Some(&Event::CreateChartAndPostToSlack {
+                    alert_id: occurrences[0].id
+                })
+            );
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_resolved_from_slack_keep_their_occurrence_while_firing() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let mut payload = firing_payload("67891");
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload.clone()),
+            )
+            .await
+            .expect("Error receiving alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let mut alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "67891")
+                .await
+                .unwrap()
+                .unwrap();
+            alert.resolved = true;
+            alert.resolved_by = Some("U0123".to_owned());
+            db.alert_update(&mut tx, &alert).await.unwrap();
+            while let Some(event) = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap() {
+                db.event_complete(&mut tx, event.id).await.unwrap();
+            }
+            tx.commit().await.unwrap();
+
+            // act
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload.clone()),
+            )
+            .await
+            .expect("Error receiving repeated alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let repeated_occurrences = db
+                .alert_list_by_fingerprint(&mut tx, "67891")
+                .await
+                .unwrap();
+            let no_event = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap();
+            tx.commit().await.unwrap();
+
+            payload.alerts[0].status = AlertStatus::Resolved;
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload.clone()),
+            )
+            .await
+            .expect("Error receiving resolved alert");
+            payload.alerts[0].status = AlertStatus::Firing;
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .expect("Error receiving re-fired alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let refired_occurrences = db
+                .alert_list_by_fingerprint(&mut tx, "67891")
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(repeated_occurrences.len(), 1);
+            assert!(repeated_occurrences[0].resolved);
+            assert_eq!(
+                repeated_occurrences[0].resolved_by.as_deref(),
+                Some("U0123")
+            );
+            assert!(no_event.is_none());
+            assert_eq!(refired_occurrences.len(), 2);
+            assert!(refired_occurrences[1].ends_at.is_some());
+            assert!(!refired_occurrences[0].resolved);
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_reopen_occurrence_within_quiet_period() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let service = with_webhook_auth(service, quiet_period_config());
+            let mut payload = firing_payload("78901");
+            for status in [AlertStatus::Firing, AlertStatus::Resolved] {
+                payload.alerts[0].status = status;
+                handlers::receive_alertmanager_webhook(
+                    State(service.clone()),
+                    HeaderMap::new(),
+                    Json(payload.clone()),
+                )
+                .await
+                .expect("Error receiving alert");
+            }
+
+            // act
+            payload.alerts[0].status = AlertStatus::Firing;
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .expect("Error receiving re-fired alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let occurrences = db
+                .alert_list_by_fingerprint(&mut tx, "78901")
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(occurrences.len(), 1);
+            assert!(!occurrences[0].resolved);
+            assert_eq!(occurrences[0].ends_at, None);
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_reopen_occurrence_within_endless_quiet_period() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let config = AlertmanagerServiceConfig {
+                alert_quiet_period_secs: u64::MAX,
+                ..AlertmanagerServiceConfig::new_test_config()
+            };
+            let service = with_webhook_auth(service, config);
+            let mut payload = firing_payload("78902");
+            for status in [AlertStatus::Firing, AlertStatus::Resolved] {
+                payload.alerts[0].status = status;
+                handlers::receive_alertmanager_webhook(
+                    State(service.clone()),
+                    HeaderMap::new(),
+                    Json(payload.clone()),
+                )
+                .await
+                .expect("Error receiving alert");
+            }
+
+            // act
+            payload.alerts[0].status = AlertStatus::Firing;
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .expect("Error receiving re-fired alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let occurrences = db
+                .alert_list_by_fingerprint(&mut tx, "78902")
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(occurrences.len(), 1);
+            assert!(!occurrences[0].resolved);
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_record_history() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let mut payload = firing_payload("89012");
+
+            // act
+            for status in [
+                AlertStatus::Firing,
+                AlertStatus::Resolved,
+                AlertStatus::Firing,
+            ] {
+                payload.alerts[0].status = status;
+                handlers::receive_alertmanager_webhook(
+                    State(service.clone()),
+                    HeaderMap::new(),
+                    Json(payload.clone()),
+                )
+                .await
+                .expect("Error receiving alert");
+            }
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let occurrences = db
+                .alert_list_by_fingerprint(&mut tx, "89012")
+                .await
+                .unwrap();
+            let first_history = db
+                .alert_event_list(&mut tx, occurrences[1].id)
+                .await
+                .unwrap();
+            let first_stats = db
+                .alert_stats(&mut tx, &occurrences[1], OffsetDateTime::now_utc())
+                .await
+                .unwrap();
+            let second_stats = db
+                .alert_stats(&mut tx, &occurrences[0], OffsetDateTime::now_utc())
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            let kinds: Vec<_> = first_history.iter().map(|event| event.kind).collect();
+            assert_eq!(kinds, vec![AlertEventKind::Fired, AlertEventKind::Resolved]);
+            assert_eq!(first_stats.fired_last_24h, 2);
+            assert_matches!(first_stats.time_to_resolve_secs, Some(secs) if secs >= 0);
+            assert_eq!(second_stats.fired_last_24h, 2);
+            assert_eq!(second_stats.time_to_resolve_secs, None);
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_regenerate_chart_on_resolve() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let mut payload = firing_payload("56789");
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload.clone()),
+            )
+            .await
+            .expect("Error receiving firing alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let mut alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "56789")
+                .await
+                .unwrap()
+                .unwrap();
+            alert.chart_filename = Some("56789.png".to_owned());
+            db.alert_update(&mut tx, &alert).await.unwrap();
+            let create_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            db.event_complete(&mut tx, create_event.id).await.unwrap();
+            tx.commit().await.unwrap();
+
+            // act
+            payload.alerts[0].status = AlertStatus::Resolved;
+            handlers::receive_alertmanager_webhook(
+                State(service.clone()),
+                HeaderMap::new(),
+                Json(payload),
+            )
+            .await
+            .expect("Error receiving resolved alert");
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let update_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            db.event_complete(&mut tx, update_event.id).await.unwrap();
+            let no_event = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap();
+            let next_available_at = db.event_next_available_at(&mut tx).await.unwrap();
+            let resolved_alert = db
+                .alert_get_latest_by_fingerprint(&mut tx, "56789")
+                .await
+                .unwrap()
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(
+                update_event.payload.0,
+                Event::UpdateSlackAlert { alert_id: alert.id }
+            );
+            assert!(no_event.is_none());
+            assert_eq!(
+                next_available_at,
+                Some(resolved_alert.ends_at.unwrap() + RECOVERY_WINDOW)
+            );
+        },
+    )
+    .await;
+}
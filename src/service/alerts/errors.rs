@@ -0,0 +1,60 @@
+use crate::db::DbError;
+use crate::service::admin::AdminHandlerError;
+use axum::extract::Json;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error, Serialize)]
+#[serde(tag = "error", content = "details", rename_all = "snake_case")]
+pub enum AlertsHandlerError {
+    #[error("Database error: {0}")]
+    DatabaseError(DbError),
+
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("Alerts API is disabled")]
+    Disabled,
+
+    #[error("Entity not found")]
+    NotFound,
+
+    #[error("Missing or invalid credentials")]
+    Unauthorized,
+}
+
+impl From<AdminHandlerError> for AlertsHandlerError {
+    fn from(error: AdminHandlerError) -> Self {
+        match error {
+            AdminHandlerError::DatabaseError(error) => Self::DatabaseError(error),
+            AdminHandlerError::Disabled => Self::Disabled,
+            AdminHandlerError::NotFound => Self::NotFound,
+            AdminHandlerError::Unauthorized => Self::Unauthorized,
+        }
+    }
+}
+
+impl From<DbError> for AlertsHandlerError {
+    fn from(error: DbError) -> Self {
+        match error {
+            DbError::NotFound => AlertsHandlerError::NotFound,
+            error => AlertsHandlerError::DatabaseError(error),
+        }
+    }
+}
+
+impl IntoResponse for AlertsHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match self {
+            Self::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Disabled => StatusCode::NOT_FOUND,
+            Self::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+        };
+
+        (status_code, Json(self)).into_response()
+    }
+}
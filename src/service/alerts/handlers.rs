@@ -0,0 +1,146 @@
+use super::{AlertListQuery, AlertsHandlerError};
+use crate::db::models::{Alert, AlertEvent, AlertStats, SlackMessage};
+use crate::service::matchers::LabelMatcher;
+use crate::service::slack::message_permalink;
+use crate::service::{Service, SLACK_APP_SLO};
+use autometrics::autometrics;
+use axum::extract::{Json, Path, RawQuery, State};
+use axum::http::HeaderMap;
+use serde::Serialize;
+use time::OffsetDateTime;
+use tracing::instrument;
+
+/// Maximum number of batches of alerts that are fetched to fill a single page
+/// when filtering by labels. If the page isn't filled by then, it is returned
+/// as it is, with a cursor to continue from the last alert that was checked.
+const MAX_BATCHES_PER_PAGE: usize = 10;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertList {
+    /// Alerts matching the query, most recent first.
+    pub alerts: Vec<Alert>,
+
+    /// Cursor to pass along to fetch the next page, if there may be more
+    /// alerts.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertDetails {
+    #[serde(flatten)]
+    pub alert: Alert,
+
+    /// The state transitions of the alert, oldest first.
+    pub history: Vec<AlertEvent>,
+
+    pub stats: AlertStats,
+
+    /// The messages that were posted to Slack for the alert.
+    pub slack_messages: Vec<SlackMessageLink>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlackMessageLink {
+    /// The channel the message was posted to, as it appears in the routing
+    /// table.
+    pub channel: String,
+
+    pub channel_id: String,
+
+    pub ts: String,
+
+    pub permalink: String,
+}
+
+impl From<SlackMessage> for SlackMessageLink {
+    fn from(message: SlackMessage) -> Self {
+        Self {
+            permalink: message_permalink(&message),
+            channel: message.channel,
+            channel_id: message.channel_id,
+            ts: message.ts,
+        }
+    }
+}
+
+#[autometrics(objective = SLACK_APP_SLO)]
+#[instrument(err, skip(service, headers))]
+pub async fn alerts_list(
+    State(service): State<Service>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+) -> Result<Json<AlertList>, AlertsHandlerError> {
+    service.admin.authenticate(&headers)?;
+
+    let query = AlertListQuery::parse(query.as_deref().unwrap_or_default())?;
+
+    // Label matchers are applied after fetching the alerts, so we may need
+    // to fetch multiple batches to fill a page.
+    let mut alerts = Vec::new();
+    let mut before_id = query.cursor;
+    let mut batches = 0;
+    let mut tx = service.db.start_transaction().await?;
+    let next_cursor = loop {
+        batches += 1;
+        let batch = service
+            .db
+            .alert_list(&mut tx, &query.filter, before_id, query.limit)
+            .await?;
+        let exhausted = batch.len() < query.limit as usize;
+
+        for alert in batch {
+            before_id = Some(alert.id);
+            if LabelMatcher::all_match(&query.label_matchers, &alert.labels) {
+                alerts.push(alert);
+                if alerts.len() as i64 == query.limit {
+                    break;
+                }
+            }
+        }
+
+        if exhausted && (alerts.len() as i64) < query.limit {
+            break None;
+        } else if alerts.len() as i64 == query.limit || batches == MAX_BATCHES_PER_PAGE {
+            break before_id.map(|id| id.to_string());
+        }
+    };
+    service.db.commit(tx).await?;
+
+    Ok(Json(AlertList {
+        alerts,
+        next_cursor,
+    }))
+}
+
+#[autometrics(objective = SLACK_APP_SLO)]
+#[instrument(err, skip(service, headers))]
+pub async fn alert_get(
+    State(service): State<Service>,
+    headers: HeaderMap,
+    Path(alert_id): Path<i64>,
+) -> Result<Json<AlertDetails>, AlertsHandlerError> {
+    service.admin.authenticate(&headers)?;
+
+    let mut tx = service.db.start_transaction().await?;
+    let alert = service.db.alert_get(&mut tx, alert_id).await?;
+    let history = service.db.alert_event_list(&mut tx, alert_id).await?;
+    let stats = service
+        .db
+        .alert_stats(&mut tx, &alert, OffsetDateTime::now_utc())
+        .await?;
+    let slack_messages = service
+        .db
+        .slack_message_list_by_alert(&mut tx, alert_id)
+        .await?;
+    service.db.commit(tx).await?;
+
+    Ok(Json(AlertDetails {
+        alert,
+        history,
+        stats,
+        slack_messages: slack_messages.into_iter().map(Into::into).collect(),
+    }))
+}
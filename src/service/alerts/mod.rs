@@ -0,0 +1,86 @@
+mod errors;
+#[cfg(test)]
+mod tests;
+
+pub mod handlers;
+
+use super::matchers::LabelMatcher;
+use crate::db::models::AlertFilter;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+pub use errors::AlertsHandlerError;
+
+/// Number of alerts returned per page, if no limit is given.
+const DEFAULT_LIMIT: i64 = 50;
+
+/// Maximum number of alerts that can be requested per page.
+const MAX_LIMIT: i64 = 500;
+
+/// Query for listing alerts, parsed from the query string.
+///
+/// Supported parameters are `resolved`, `severity`, `slothService`,
+/// `objectiveName`, `from` and `to` (RFC 3339 timestamps, applied to the
+/// creation time), `label` (a label matcher, may be repeated), `cursor` and
+/// `limit`.
+#[derive(Debug, Default)]
+pub struct AlertListQuery {
+    pub filter: AlertFilter,
+    pub label_matchers: Vec<LabelMatcher>,
+    pub cursor: Option<i64>,
+    pub limit: i64,
+}
+
+impl AlertListQuery {
+    pub fn parse(query: &str) -> Result<Self, AlertsHandlerError> {
+        let mut list_query = Self {
+            limit: DEFAULT_LIMIT,
+            ..Default::default()
+        };
+
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            let filter = &mut list_query.filter;
+            match key.as_ref() {
+                "resolved" => filter.resolved = Some(parse_param(&key, &value)?),
+                "severity" => filter.severity = Some(value.into_owned()),
+                "slothService" => filter.sloth_service = Some(value.into_owned()),
+                "objectiveName" => filter.objective_name = Some(value.into_owned()),
+                "from" => filter.created_from = Some(parse_timestamp(&key, &value)?),
+                "to" => filter.created_to = Some(parse_timestamp(&key, &value)?),
+                "label" => list_query.label_matchers.push(parse_param(&key, &value)?),
+                "cursor" => list_query.cursor = Some(parse_param(&key, &value)?),
+                "limit" => {
+                    let limit: i64 = parse_param(&key, &value)?;
+                    if !(1..=MAX_LIMIT).contains(&limit) {
+                        return Err(AlertsHandlerError::InvalidQuery(format!(
+                            "limit must be between 1 and {MAX_LIMIT}"
+                        )));
+                    }
+                    list_query.limit = limit;
+                }
+                _ => {
+                    return Err(AlertsHandlerError::InvalidQuery(format!(
+                        "unknown parameter: {key}"
+                    )))
+                }
+            }
+        }
+
+        Ok(list_query)
+    }
+}
+
+fn parse_param<T>(key: &str, value: &str) -> Result<T, AlertsHandlerError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|err| AlertsHandlerError::InvalidQuery(format!("invalid {key}: {err}")))
+}
+
+fn parse_timestamp(key: &str, value: &str) -> Result<OffsetDateTime, AlertsHandlerError> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|err| AlertsHandlerError::InvalidQuery(format!("invalid {key}: {err}")))
+}
@@ -0,0 +1,250 @@
+use crate::db::models::{Alert, AlertEventKind, NewAlert};
+use crate::db::Db;
+use crate::service::alerts::*;
+use crate::testutil::*;
+use axum::extract::{Path, RawQuery, State};
+use axum::headers::{Authorization, HeaderMapExt};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use std::collections::BTreeMap;
+
+fn admin_headers(token: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.typed_insert(Authorization::bearer(token).unwrap());
+    headers
+}
+
+async fn create_alert(db: &Db, severity: &str, team: &str, resolved: bool) -> Alert {
+    let mut tx = db.start_transaction().await.unwrap();
+    let alert = db
+        .alert_create(
+            &mut tx,
+            NewAlert {
+                text: "Something is wrong".to_owned(),
+                resolved,
+                fingerprint: Some(format!("{:016x}", rand::random::<u64>())),
+                notebook_id: None,
+                chart_filename: None,
+                slack_channel: None,
+                slack_ts: None,
+                sloth_slo: None,
+                sloth_service: Some("payments".to_owned()),
+                objective_name: None,
+                severity: Some(severity.to_owned()),
+                labels: BTreeMap::from([("team".to_owned(), team.to_owned())]),
+                annotations: Default::default(),
+                generator_url: None,
+                starts_at: None,
+                ends_at: None,
+            },
+        )
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    alert
+}
+
+#[test]
+fn test_parse_alert_list_query() {
+    let query = AlertListQuery::parse(
+        "resolved=false&severity=critical&slothService=payments&from=2023-10-01T00%3A00%3A00Z&label=team%3D~%22pay.*%22&cursor=42&limit=10",
+    )
+    .expect("Error parsing query");
+
+    assert_eq!(query.filter.resolved, Some(false));
+    assert_eq!(query.filter.severity.as_deref(), Some("critical"));
+    assert_eq!(query.filter.sloth_service.as_deref(), Some("payments"));
+    assert_eq!(
+        query.filter.created_from.map(|from| from.unix_timestamp()),
+        Some(1696118400)
+    );
+    assert_eq!(query.label_matchers.len(), 1);
+    assert_eq!(query.cursor, Some(42));
+    assert_eq!(query.limit, 10);
+
+    assert_matches!(
+        AlertListQuery::parse("resolved=maybe"),
+        Err(AlertsHandlerError::InvalidQuery(_))
+    );
+    assert_matches!(
+        AlertListQuery::parse("limit=0"),
+        Err(AlertsHandlerError::InvalidQuery(_))
+    );
+    assert_matches!(
+        AlertListQuery::parse("colour=red"),
+        Err(AlertsHandlerError::InvalidQuery(_))
+    );
+}
+
+#[tokio::test]
+async fn alerts_list_filters_and_paginates() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let first_alert = create_alert(&db, "critical", "payments", false).await;
+            create_alert(&db, "warning", "payments", false).await;
+            create_alert(&db, "critical", "search", false).await;
+            let last_alert = create_alert(&db, "critical", "payments", false).await;
+            create_alert(&db, "critical", "payments", true).await;
+
+            let query = "resolved=false&severity=critical&label=team%3D%22payments%22&limit=1";
+
+            // act
+            let first_page = handlers::alerts_list(
+                State(service.clone()),
+                admin_headers("admin-s3cr3t"),
+                RawQuery(Some(query.to_owned())),
+            )
+            .await
+            .expect("Error listing alerts");
+
+            let cursor = first_page.next_cursor.clone().expect("Missing cursor");
+            let second_page = handlers::alerts_list(
+                State(service.clone()),
+                admin_headers("admin-s3cr3t"),
+                RawQuery(Some(format!("{query}&cursor={cursor}"))),
+            )
+            .await
+            .expect("Error listing alerts");
+
+            let cursor = second_page.next_cursor.clone().expect("Missing cursor");
+            let third_page = handlers::alerts_list(
+                State(service),
+                admin_headers("admin-s3cr3t"),
+                RawQuery(Some(format!("{query}&cursor={cursor}"))),
+            )
+            .await
+            .expect("Error listing alerts");
+
+            // assert
+            assert_eq!(first_page.alerts, vec![last_alert]);
+            assert_eq!(second_page.alerts, vec![first_alert]);
+            assert!(third_page.alerts.is_empty());
+            assert_eq!(third_page.next_cursor, None);
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alert_get_returns_history_and_permalinks() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let alert = create_alert(&db, "critical", "payments", false).await;
+
+            let mut tx = db.start_transaction().await.unwrap();
+            db.alert_event_create(&mut tx, alert.id, AlertEventKind::Fired, None)
+                .await
+                .unwrap();
+            db.slack_message_create(&mut tx, alert.id, "#payments", "C123", "1697040000.123456")
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // act
+            let details = handlers::alert_get(
+                State(service.clone()),
+                admin_headers("admin-s3cr3t"),
+                Path(alert.id),
+            )
+            .await
+            .expect("Error getting alert");
+            let missing_result = handlers::alert_get(
+                State(service),
+                admin_headers("admin-s3cr3t"),
+                Path(alert.id + 1),
+            )
+            .await;
+
+            // assert
+            assert_eq!(details.alert, alert);
+            assert_eq!(details.history.len(), 1);
+            assert_eq!(details.history[0].kind, AlertEventKind::Fired);
+            assert_eq!(details.stats.fired_last_24h, 1);
+            assert_eq!(details.slack_messages.len(), 1);
+            assert_eq!(
+                details.slack_messages[0].permalink,
+                "https://slack.com/archives/C123/p1697040000123456"
+            );
+            assert_matches!(missing_result, Err(AlertsHandlerError::NotFound));
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_list_stops_scanning_after_a_few_batches() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let payments_alert = create_alert(&db, "critical", "payments", false).await;
+            for _ in 0..12 {
+                create_alert(&db, "critical", "search", false).await;
+            }
+
+            let query = "label=team%3D%22payments%22&limit=1";
+
+            // act
+            let first_page = handlers::alerts_list(
+                State(service.clone()),
+                admin_headers("admin-s3cr3t"),
+                RawQuery(Some(query.to_owned())),
+            )
+            .await
+            .expect("Error listing alerts");
+
+            let cursor = first_page.next_cursor.clone().expect("Missing cursor");
+            let second_page = handlers::alerts_list(
+                State(service),
+                admin_headers("admin-s3cr3t"),
+                RawQuery(Some(format!("{query}&cursor={cursor}"))),
+            )
+            .await
+            .expect("Error listing alerts");
+
+            // assert
+            assert!(first_page.alerts.is_empty());
+            assert_eq!(second_page.alerts, vec![payments_alert]);
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn alerts_api_rejects_invalid_token() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let alert = create_alert(&db, "critical", "payments", false).await;
+
+            // act
+            let missing_token_result =
+                handlers::alerts_list(State(service.clone()), HeaderMap::new(), RawQuery(None))
+                    .await;
+            let invalid_token_result = handlers::alert_get(
+                State(service.clone()),
+                admin_headers("wrong"),
+                Path(alert.id),
+            )
+            .await;
+            let response = handlers::alerts_list(State(service), HeaderMap::new(), RawQuery(None))
+                .await
+                .into_response();
+
+            // assert
+            assert_matches!(missing_token_result, Err(AlertsHandlerError::Unauthorized));
+            assert_matches!(invalid_token_result, Err(AlertsHandlerError::Unauthorized));
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        },
+    )
+    .await;
+}
@@ -0,0 +1,46 @@
+use axum::headers::authorization::{Basic, Bearer};
+use axum::headers::{Authorization, HeaderMapExt};
+use axum::http::HeaderMap;
+use secrecy::{ExposeSecret, SecretString};
+
+/// Returns whether the request carries a bearer token matching `expected`.
+pub fn has_bearer_token(headers: &HeaderMap, expected: &SecretString) -> bool {
+    headers
+        .typed_get::<Authorization<Bearer>>()
+        .map(|auth| secret_matches(auth.token(), expected))
+        .unwrap_or(false)
+}
+
+/// Returns whether the request carries HTTP basic credentials matching the
+/// expected username and password.
+pub fn has_basic_auth(
+    headers: &HeaderMap,
+    expected_username: &str,
+    expected_password: &SecretString,
+) -> bool {
+    headers
+        .typed_get::<Authorization<Basic>>()
+        .map(|auth| {
+            // Evaluate both comparisons, so the timing does not reveal which
+            // one failed.
+            let username_matches = constant_time_eq(auth.username(), expected_username);
+            let password_matches = secret_matches(auth.password(), expected_password);
+            username_matches & password_matches
+        })
+        .unwrap_or(false)
+}
+
+fn secret_matches(given: &str, expected: &SecretString) -> bool {
+    constant_time_eq(given, expected.expose_secret())
+}
+
+/// Compares two strings without short-circuiting on the first mismatching
+/// byte.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
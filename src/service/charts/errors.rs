@@ -16,6 +16,9 @@ pub enum ChartHandlerError {
 
     #[error("Entity not found")]
     NotFound,
+
+    #[error("Missing, invalid or expired chart URL signature")]
+    Forbidden,
 }
 
 impl From<DbError> for ChartHandlerError {
@@ -33,12 +36,22 @@ impl From<std::io::Error> for ChartHandlerError {
     }
 }
 
+impl From<ChartServiceError> for ChartHandlerError {
+    fn from(error: ChartServiceError) -> Self {
+        match error {
+            ChartServiceError::NotFound => Self::NotFound,
+            error => Self::FileError(error.to_string()),
+        }
+    }
+}
+
 impl IntoResponse for ChartHandlerError {
     fn into_response(self) -> axum::response::Response {
         let status_code = match self {
             Self::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::FileError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Forbidden => StatusCode::FORBIDDEN,
         };
 
         (status_code, Json(self)).into_response()
@@ -51,15 +64,24 @@ pub enum ChartServiceError {
     #[error("Cannot generate chart")]
     Generation,
 
+    #[error("Chart not found")]
+    NotFound,
+
     #[error("Cannot render chart: {0}")]
     Render(String),
 
     #[error("Cannot store chart found")]
     Storage(String),
+
+    #[error("Rendering the chart took too long")]
+    Timeout,
 }
 
 impl From<std::io::Error> for ChartServiceError {
     fn from(error: std::io::Error) -> Self {
-        Self::Storage(error.to_string())
+        match error.kind() {
+            std::io::ErrorKind::NotFound => Self::NotFound,
+            _ => Self::Storage(error.to_string()),
+        }
     }
 }
@@ -0,0 +1,69 @@
+use serde::Deserialize;
+
+/// Formats in which charts are rendered and stored.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartFormat {
+    /// Raster image, which is what Slack displays.
+    #[default]
+    Png,
+
+    /// Vector image, for embedding in web UIs and notebooks.
+    Svg,
+}
+
+impl ChartFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Svg => "image/svg+xml",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Svg => "svg",
+        }
+    }
+
+    /// Returns the filename under which the chart is stored in this format,
+    /// given the filename stored for the alert.
+    pub fn chart_filename(&self, chart_filename: &str) -> String {
+        let stem = chart_filename
+            .rsplit_once('.')
+            .map_or(chart_filename, |(stem, _)| stem);
+        format!("{stem}.{}", self.extension())
+    }
+
+    /// Picks the format preferred by the given `Accept` header, if it accepts
+    /// any. Wildcards result in the default format, and the first of equally
+    /// preferred formats is picked.
+    pub fn from_accept_header(accept: &str) -> Option<Self> {
+        let mut preferred: Option<(Self, f32)> = None;
+
+        for media_range in accept.split(',') {
+            let mut params = media_range.split(';');
+            let format = match params.next().unwrap_or_default().trim() {
+                "image/png" | "image/*" | "*/*" => Self::Png,
+                "image/svg+xml" => Self::Svg,
+                _ => continue,
+            };
+            let quality = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(1.0, |quality| quality.parse().unwrap_or(0.0));
+
+            if quality > 0.0 && preferred.map_or(true, |(_, preferred)| quality > preferred) {
+                preferred = Some((format, quality));
+            }
+        }
+
+        preferred.map(|(format, _)| format)
+    }
+}
+
+/// Query parameters for picking the format of a chart explicitly.
+#[derive(Debug, Default, Deserialize)]
+pub struct ChartFormatQuery {
+    pub format: Option<ChartFormat>,
+}
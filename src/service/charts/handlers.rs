@@ -1,78 +1,104 @@
-use crate::db::DbError;
+use super::{ChartFormat, ChartFormatQuery, ChartHandlerError, ChartUrlSignature};
+use crate::db::models::Alert;
 use crate::service::{Service, SLACK_APP_SLO};
 use autometrics::autometrics;
-use axum::body::StreamBody;
-use axum::extract::{Json, Path, State};
-use axum::http::header::CONTENT_TYPE;
-use axum::http::{HeaderMap, StatusCode};
-use axum::response::IntoResponse;
-use serde::Serialize;
-use thiserror::Error;
-use tokio::fs::File;
-use tokio_util::io::ReaderStream;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{ACCEPT, CONTENT_TYPE};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Redirect, Response};
+use time::OffsetDateTime;
 use tracing::instrument;
 
-#[derive(Debug, Error, Serialize)]
-#[serde(tag = "error", content = "details", rename_all = "snake_case")]
-pub enum ChartHandlerError {
-    #[error("Database error: {0}")]
-    Database(DbError),
-
-    #[error("Cannot read file: {0}")]
-    FileError(String),
-
-    #[error("Entity not found")]
-    NotFound,
-}
-
-impl From<DbError> for ChartHandlerError {
-    fn from(error: DbError) -> Self {
-        match error {
-            DbError::NotFound => ChartHandlerError::NotFound,
-            error => ChartHandlerError::Database(error),
-        }
-    }
+/// Serves the chart of an alert.
+///
+/// The format is picked with the `format` query parameter if given, or the
+/// `Accept` header otherwise, and defaults to PNG.
+#[autometrics(objective = SLACK_APP_SLO)]
+#[instrument(err, skip(service, headers))]
+pub async fn charts_get(
+    State(service): State<Service>,
+    Path(alert_id): Path<i64>,
+    Query(signature): Query<ChartUrlSignature>,
+    Query(format_query): Query<ChartFormatQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ChartHandlerError> {
+    serve_chart(
+        &service,
+        alert_id,
+        &signature,
+        format_query,
+        &headers,
+        |alert| alert.chart_filename,
+    )
+    .await
 }
 
-impl From<std::io::Error> for ChartHandlerError {
-    fn from(error: std::io::Error) -> Self {
-        Self::FileError(error.to_string())
-    }
+/// Serves the chart of the error budget consumption of an alert, if one was
+/// created. The format is picked like for [`charts_get`].
+#[autometrics(objective = SLACK_APP_SLO)]
+#[instrument(err, skip(service, headers))]
+pub async fn error_budget_charts_get(
+    State(service): State<Service>,
+    Path(alert_id): Path<i64>,
+    Query(signature): Query<ChartUrlSignature>,
+    Query(format_query): Query<ChartFormatQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ChartHandlerError> {
+    serve_chart(
+        &service,
+        alert_id,
+        &signature,
+        format_query,
+        &headers,
+        |alert| alert.error_budget_chart_filename,
+    )
+    .await
 }
 
-impl IntoResponse for ChartHandlerError {
-    fn into_response(self) -> axum::response::Response {
-        let status_code = match self {
-            Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::FileError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::NotFound => StatusCode::NOT_FOUND,
-        };
-
-        (status_code, Json(self)).into_response()
+/// Serves one of the charts of an alert. Both charts are covered by the same
+/// signature, since they belong to the same alert.
+async fn serve_chart(
+    service: &Service,
+    alert_id: i64,
+    signature: &ChartUrlSignature,
+    format_query: ChartFormatQuery,
+    headers: &HeaderMap,
+    chart_filename: impl FnOnce(Alert) -> Option<String>,
+) -> Result<Response, ChartHandlerError> {
+    let now = OffsetDateTime::now_utc();
+    if !service.charts.verify_chart_url(alert_id, signature, now) {
+        return Err(ChartHandlerError::Forbidden);
     }
-}
 
-#[autometrics(objective = SLACK_APP_SLO)]
-#[instrument(err, skip(service))]
-pub async fn charts_get(
-    State(service): State<Service>,
-    Path(alert_id): Path<i64>,
-) -> Result<impl IntoResponse, ChartHandlerError> {
     let mut tx = service.db.start_transaction().await?;
 
     let alert = service.db.alert_get(&mut tx, alert_id).await?;
 
     service.db.commit(tx).await?;
 
-    let Some(filename) = alert.chart_filename.as_ref() else {
+    let Some(chart_filename) = chart_filename(alert) else {
         return Err(ChartHandlerError::NotFound);
     };
 
-    let headers = HeaderMap::from_iter([(CONTENT_TYPE, "image/png".parse().unwrap())]);
+    let format = format_query
+        .format
+        .or_else(|| {
+            headers
+                .get(ACCEPT)
+                .and_then(|accept| accept.to_str().ok())
+                .and_then(ChartFormat::from_accept_header)
+        })
+        .unwrap_or_default();
+    let filename = format.chart_filename(&chart_filename);
+
+    // Let clients fetch the chart from the storage directly, if it supports
+    // that.
+    if let Some(url) = service.charts.presigned_chart_url(&filename, now) {
+        return Ok(Redirect::temporary(url.as_str()).into_response());
+    }
 
-    let file: File = File::open(service.charts.config.storage_dir.join(filename)).await?;
-    let stream = ReaderStream::new(file);
-    let body = StreamBody::new(stream);
+    let headers = HeaderMap::from_iter([(CONTENT_TYPE, format.content_type().parse().unwrap())]);
+    let image = service.charts.read_chart(&filename).await?;
 
-    Ok((headers, body))
+    Ok((headers, image).into_response())
 }
@@ -1,14 +1,32 @@
 mod errors;
+mod format;
+mod signing;
+mod storage;
+#[cfg(test)]
+mod tests;
+mod timeline;
 
 pub mod handlers;
 
 use autometrics::autometrics;
+use fiberplane::models::providers::ProviderEvent;
 use mondrian_charts::*;
 use once_cell::sync::Lazy;
+use secrecy::SecretString;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use storage::sigv4::Credentials;
+use storage::{ChartStorage, LocalChartStorage, S3ChartStorage};
+use time::OffsetDateTime;
+use tokio::sync::Semaphore;
 use tracing::{debug, instrument};
+use url::Url;
 
 pub use errors::{ChartHandlerError, ChartServiceError};
+pub use format::{ChartFormat, ChartFormatQuery};
+pub use signing::{ChartUrlSignature, ChartUrlSigner};
+pub use timeline::{AlertTimeline, RECOVERY_WINDOW};
 
 const INTER_FONT: &[u8] = include_bytes!("../../../assets/Inter-Regular.ttf");
 
@@ -32,53 +50,248 @@ fn get_shape_list_color<S>(_shape: &S, index: usize) -> &str {
     SHAPE_COLORS[index % SHAPE_COLORS.len()]
 }
 
+/// Prefix of the names of error budget charts, which are formatted as
+/// percentages regardless of the kind of SLO.
+const ERROR_BUDGET_CHART_PREFIX: &str = "error-budget-";
+
+/// Longest time for which S3 accepts presigned URLs, in seconds.
+const MAX_PRESIGNED_URL_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Longest time for which signed chart URLs can be valid, in seconds.
+const MAX_CHART_URL_TTL_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Kinds of storage charts can be kept in.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChartStorageKind {
+    /// Store charts in a local directory.
+    Local,
+    /// Store charts in a bucket of an S3-compatible object storage.
+    S3,
+}
+
 #[derive(clap::Args, Debug)]
 pub struct ChartServiceConfig {
-    /// Directory where charts will be stored.
-    #[clap(long, env, required = true, help_heading = "Chart storage")]
-    storage_dir: PathBuf,
+    /// Where charts will be stored.
+    #[clap(
+        long,
+        env,
+        value_enum,
+        default_value = "local",
+        help_heading = "Chart storage"
+    )]
+    chart_storage: ChartStorageKind,
+
+    /// Directory where charts will be stored, when using local storage.
+    #[clap(
+        long,
+        env,
+        required_if_eq("chart_storage", "local"),
+        help_heading = "Chart storage"
+    )]
+    storage_dir: Option<PathBuf>,
+
+    /// Endpoint of the S3 API, when using S3 storage. Defaults to the AWS S3
+    /// endpoint of the configured region.
+    #[clap(long, env, help_heading = "Chart storage")]
+    chart_s3_endpoint: Option<Url>,
+
+    /// Bucket in which charts will be stored, when using S3 storage.
+    #[clap(
+        long,
+        env,
+        required_if_eq("chart_storage", "s3"),
+        help_heading = "Chart storage"
+    )]
+    chart_s3_bucket: Option<String>,
+
+    /// Region of the bucket, when using S3 storage.
+    #[clap(long, env, default_value = "us-east-1", help_heading = "Chart storage")]
+    chart_s3_region: String,
+
+    /// Use path-style URLs for objects, which include the bucket in the path
+    /// instead of the host name. This is usually needed for MinIO.
+    #[clap(long, env, help_heading = "Chart storage")]
+    chart_s3_path_style: bool,
+
+    /// Access key ID for the S3 API, when using S3 storage.
+    #[clap(
+        long,
+        env,
+        required_if_eq("chart_storage", "s3"),
+        help_heading = "Chart storage"
+    )]
+    chart_s3_access_key_id: Option<String>,
+
+    /// Secret access key for the S3 API, when using S3 storage.
+    #[clap(
+        long,
+        env,
+        required_if_eq("chart_storage", "s3"),
+        help_heading = "Chart storage"
+    )]
+    chart_s3_secret_access_key: Option<SecretString>,
+
+    /// Session token for the S3 API, when using temporary credentials.
+    #[clap(long, env, help_heading = "Chart storage")]
+    chart_s3_session_token: Option<SecretString>,
+
+    /// How long the presigned URLs that chart requests are redirected to
+    /// remain valid, in seconds, when using S3 storage.
+    ///
+    /// S3 doesn't accept presigned URLs that are valid for more than 7 days.
+    #[clap(
+        long,
+        env,
+        default_value = "300",
+        value_parser = clap::value_parser!(u64).range(1..=MAX_PRESIGNED_URL_TTL_SECS),
+        help_heading = "Chart storage"
+    )]
+    chart_s3_presigned_url_ttl_secs: u64,
+
+    /// Maximum number of charts that are rendered concurrently.
+    #[clap(long, env, default_value = "2", help_heading = "Chart rendering")]
+    chart_render_workers: usize,
+
+    /// Maximum time rendering a single chart may take, in seconds.
+    #[clap(long, env, default_value = "30", help_heading = "Chart rendering")]
+    chart_render_timeout_secs: u64,
+
+    /// Also chart how the error budget of SLOs was consumed, in addition to
+    /// the chart of the SLO itself.
+    #[clap(long, env, help_heading = "Chart rendering")]
+    chart_error_budget: bool,
+
+    /// Key for signing chart URLs.
+    ///
+    /// If set, charts can only be fetched through the signed URLs included in
+    /// Slack messages, until they expire.
+    #[clap(long, env, help_heading = "Chart links")]
+    chart_url_signing_key: Option<SecretString>,
+
+    /// Comma-separated list of previous signing keys, that are still accepted
+    /// when verifying chart URLs. Used for rotating the signing key.
+    #[clap(long, env, value_delimiter = ',', help_heading = "Chart links")]
+    chart_url_verification_keys: Vec<SecretString>,
+
+    /// How long signed chart URLs remain valid, in seconds. At most a year.
+    #[clap(
+        long,
+        env,
+        default_value = "2592000",
+        value_parser = clap::value_parser!(u64).range(1..=MAX_CHART_URL_TTL_SECS),
+        help_heading = "Chart links"
+    )]
+    chart_url_ttl_secs: u64,
 }
 
 #[cfg(test)]
 impl ChartServiceConfig {
     pub fn new_test_config() -> Self {
         Self {
-            storage_dir: PathBuf::from("/tmp"),
+            chart_storage: ChartStorageKind::Local,
+            storage_dir: Some(PathBuf::from("/tmp")),
+            chart_s3_endpoint: None,
+            chart_s3_bucket: None,
+            chart_s3_region: "us-east-1".to_owned(),
+            chart_s3_path_style: false,
+            chart_s3_access_key_id: None,
+            chart_s3_secret_access_key: None,
+            chart_s3_session_token: None,
+            chart_s3_presigned_url_ttl_secs: 300,
+            chart_render_workers: 1,
+            chart_render_timeout_secs: 10,
+            chart_error_budget: false,
+            chart_url_signing_key: None,
+            chart_url_verification_keys: Vec::new(),
+            chart_url_ttl_secs: 3600,
         }
     }
 }
+
+/// A chart, rendered in all supported formats.
+pub struct RenderedChart {
+    pub png: Vec<u8>,
+    pub svg: Vec<u8>,
+}
+
 pub struct ChartService {
     config: ChartServiceConfig,
+
+    /// Limits the number of blocking threads used for rendering charts.
+    render_permits: Arc<Semaphore>,
+
+    /// Signs and verifies chart URLs, if a signing key is configured.
+    url_signer: Option<ChartUrlSigner>,
+
+    storage: Box<dyn ChartStorage>,
 }
 
 impl ChartService {
     pub fn new(config: ChartServiceConfig) -> Self {
-        Self { config }
+        let render_permits = Arc::new(Semaphore::new(config.chart_render_workers.max(1)));
+        let url_signer = config.chart_url_signing_key.clone().map(|signing_key| {
+            ChartUrlSigner::new(
+                signing_key,
+                config.chart_url_verification_keys.clone(),
+                time::Duration::seconds(
+                    config.chart_url_ttl_secs.min(MAX_CHART_URL_TTL_SECS) as i64
+                ),
+            )
+        });
+        let storage = storage_for_config(&config);
+        Self {
+            config,
+            render_permits,
+            url_signer,
+            storage,
+        }
     }
 
-    /// FIXME: Generating charts is a relatively heavy task that can potentially
-    ///        take a few seconds including rendering. This could block the
-    ///        Tokio event loop if multiple charts are being generated
-    ///        concurrently. I don't *think* this is an issue currently, since
-    ///        the event handler processes events sequentially, and this
-    ///        function is only called from the event handler, meaning only one
-    ///        chart will be generated at a time.
-    ///        However, this still generates a bit of a bottleneck in case
-    ///        multiple SLOs are triggering alerts and we would ideally
-    ///        parallize chart generation. But if we want to fix that, we should
-    ///        still be careful to *only* parallize requests for different
-    ///        alerts, and handle everything for the same alert serially, or we
-    ///        risk dropping updates to Slack (for instance, when Alertmanager
-    ///        quickly resolves an alert we're still generating the chart for).
-    pub fn create_chart(
+    /// Returns whether error budget charts should be created for SLO alerts.
+    pub fn charts_error_budget(&self) -> bool {
+        self.config.chart_error_budget
+    }
+
+    pub fn url_signer(&self) -> Option<&ChartUrlSigner> {
+        self.url_signer.as_ref()
+    }
+
+    /// Returns whether the chart for the given alert may be served for a
+    /// request with the given signature. If chart URLs aren't signed, all
+    /// requests are allowed.
+    pub fn verify_chart_url(
         &self,
+        alert_id: i64,
+        signature: &ChartUrlSignature,
+        now: OffsetDateTime,
+    ) -> bool {
+        match self.url_signer.as_ref() {
+            Some(url_signer) => url_signer.verify(alert_id, signature, now),
+            None => true,
+        }
+    }
+
+    /// Generates and renders a chart to PNG and SVG images.
+    ///
+    /// If a threshold is given, it is drawn as a reference line, such as the
+    /// latency threshold or target success rate of the SLO.
+    ///
+    /// The SLO determines how values are formatted. Error budget charts use
+    /// the name of the SLO with an `error-budget-` prefix.
+    ///
+    /// This is CPU-heavy and can take a few seconds, so it should not be
+    /// called from async code directly. Use [`Self::render_chart`] instead.
+    #[autometrics]
+    pub fn create_chart(
         slo: &str,
         time_range: TimeRange,
         timeseries_data: Vec<Timeseries>,
-    ) -> Result<Vec<u8>, ChartServiceError> {
+        events: Vec<ProviderEvent>,
+        threshold: Option<f64>,
+    ) -> Result<RenderedChart, ChartServiceError> {
         let y_formatter = if slo.starts_with("latency-") {
             FormatterKind::Duration
-        } else if slo.starts_with("success-rate-") {
+        } else if slo.starts_with("success-rate-") || slo.starts_with(ERROR_BUDGET_CHART_PREFIX) {
             FormatterKind::Percentage
         } else {
             FormatterKind::Exponent
@@ -88,8 +301,9 @@ impl ChartService {
             graph_type: GraphType::Line,
             stacking_type: StackingType::None,
             timeseries_data: &timeseries_data.iter().collect::<Vec<_>>(),
-            events: &[],
-            target_latency: None, // Do we want to include the target for latency SLOs?
+            events: &events.iter().collect::<Vec<_>>(),
+            // Drawn as a reference line, which works for any kind of SLO.
+            target_latency: threshold,
             time_range,
         })
         .ok_or(ChartServiceError::Generation)?;
@@ -116,10 +330,11 @@ impl ChartService {
             background_color: "white".to_owned(),
         };
 
-        let image_data = chart_to_image(&chart, &chart_options, &image_options)
+        let png = chart_to_image(&chart, &chart_options, &image_options)
             .map_err(|err| ChartServiceError::Render(err.to_string()))?;
+        let svg = chart_to_svg(&chart, &chart_options).into_bytes();
 
-        Ok(image_data)
+        Ok(RenderedChart { png, svg })
     }
 
     #[autometrics]
@@ -129,15 +344,154 @@ impl ChartService {
         slo: &str,
         time_range: TimeRange,
         timeseries_data: Vec<Timeseries>,
+        events: Vec<ProviderEvent>,
+        threshold: Option<f64>,
     ) -> Result<String, ChartServiceError> {
         let chart_filename = format!("{ts}-{slo}.png", ts = time_range.to);
 
         debug!(?chart_filename, "Creating chart");
 
-        let image = Self::create_chart(self, slo, time_range, timeseries_data)?;
+        let chart = self
+            .render_chart(slo, time_range, timeseries_data, events, threshold)
+            .await?;
 
-        tokio::fs::write(&self.config.storage_dir.join(&chart_filename), image).await?;
+        // The PNG is stored last, so its filename only ends up in the DB once
+        // all formats are stored.
+        self.storage
+            .put(&ChartFormat::Svg.chart_filename(&chart_filename), chart.svg)
+            .await?;
+        self.storage.put(&chart_filename, chart.png).await?;
 
         Ok(chart_filename)
     }
+
+    /// Creates a chart of the remaining error budget of an SLO over time, and
+    /// stores it next to the chart of the SLO itself. The point at which the
+    /// budget is exhausted is drawn as a reference line.
+    pub async fn create_and_store_error_budget_chart(
+        &self,
+        slo: &str,
+        time_range: TimeRange,
+        timeseries_data: Vec<Timeseries>,
+        events: Vec<ProviderEvent>,
+    ) -> Result<String, ChartServiceError> {
+        self.create_and_store_chart(
+            &format!("{ERROR_BUDGET_CHART_PREFIX}{slo}"),
+            time_range,
+            timeseries_data,
+            events,
+            Some(0.0),
+        )
+        .await
+    }
+
+    /// Reads a chart that was stored before. Use
+    /// [`ChartFormat::chart_filename`] to read it in another format than PNG.
+    #[instrument(err, skip(self))]
+    pub async fn read_chart(&self, chart_filename: &str) -> Result<Vec<u8>, ChartServiceError> {
+        self.storage.get(chart_filename).await
+    }
+
+    /// Deletes a chart that was stored before, in all formats. Returns
+    /// whether the chart still existed.
+    #[autometrics]
+    #[instrument(err, skip(self))]
+    pub async fn delete_chart(&self, chart_filename: &str) -> Result<bool, ChartServiceError> {
+        // Charts created before SVG support was added only exist as PNG.
+        self.storage
+            .delete(&ChartFormat::Svg.chart_filename(chart_filename))
+            .await?;
+        self.storage.delete(chart_filename).await
+    }
+
+    /// Returns a URL from which the chart can be fetched directly, if the
+    /// storage supports it.
+    pub fn presigned_chart_url(&self, chart_filename: &str, now: OffsetDateTime) -> Option<Url> {
+        self.storage.presigned_url(chart_filename, now)
+    }
+
+    /// Renders a chart on the blocking thread pool, so it doesn't block the
+    /// async runtime.
+    ///
+    /// At most `chart_render_workers` charts are rendered at the same time.
+    /// If rendering takes longer than `chart_render_timeout_secs`, a timeout
+    /// error is returned. The rendering itself cannot be cancelled, so it
+    /// keeps its worker until it completes.
+    async fn render_chart(
+        &self,
+        slo: &str,
+        time_range: TimeRange,
+        timeseries_data: Vec<Timeseries>,
+        events: Vec<ProviderEvent>,
+        threshold: Option<f64>,
+    ) -> Result<RenderedChart, ChartServiceError> {
+        let permit = self
+            .render_permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|err| ChartServiceError::Render(err.to_string()))?;
+
+        let slo = slo.to_owned();
+        let render_task = tokio::task::spawn_blocking(move || {
+            let result = Self::create_chart(&slo, time_range, timeseries_data, events, threshold);
+            drop(permit);
+            result
+        });
+
+        let timeout = Duration::from_secs(self.config.chart_render_timeout_secs);
+        match tokio::time::timeout(timeout, render_task).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(err)) => Err(ChartServiceError::Render(err.to_string())),
+            Err(_) => Err(ChartServiceError::Timeout),
+        }
+    }
+}
+
+fn storage_for_config(config: &ChartServiceConfig) -> Box<dyn ChartStorage> {
+    // The options that are required for each kind of storage are enforced
+    // when parsing the config.
+    match config.chart_storage {
+        ChartStorageKind::Local => Box::new(LocalChartStorage::new(
+            config
+                .storage_dir
+                .clone()
+                .expect("storage_dir is required for local chart storage"),
+        )),
+        ChartStorageKind::S3 => {
+            let endpoint = config.chart_s3_endpoint.clone().unwrap_or_else(|| {
+                Url::parse(&format!(
+                    "https://s3.{}.amazonaws.com",
+                    config.chart_s3_region
+                ))
+                .expect("S3 endpoint should be a valid URL")
+            });
+            let credentials = Credentials {
+                access_key_id: config
+                    .chart_s3_access_key_id
+                    .clone()
+                    .expect("chart_s3_access_key_id is required for S3 chart storage"),
+                secret_access_key: config
+                    .chart_s3_secret_access_key
+                    .clone()
+                    .expect("chart_s3_secret_access_key is required for S3 chart storage"),
+                session_token: config.chart_s3_session_token.clone(),
+                region: config.chart_s3_region.clone(),
+            };
+            Box::new(S3ChartStorage::new(
+                endpoint,
+                config
+                    .chart_s3_bucket
+                    .clone()
+                    .expect("chart_s3_bucket is required for S3 chart storage"),
+                config.chart_s3_path_style,
+                credentials,
+                time::Duration::seconds(
+                    config
+                        .chart_s3_presigned_url_ttl_secs
+                        .min(MAX_PRESIGNED_URL_TTL_SECS) as i64,
+                ),
+            ))
+        }
+    }
 }
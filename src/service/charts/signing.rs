@@ -0,0 +1,95 @@
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use sha2::Sha256;
+use time::{Duration, OffsetDateTime};
+use url::Url;
+
+/// Query parameters that carry the signature of a chart URL.
+#[derive(Debug, Default, Deserialize)]
+pub struct ChartUrlSignature {
+    /// Unix timestamp after which the URL is no longer valid.
+    pub expires: Option<i64>,
+
+    /// Hex-encoded HMAC-SHA256 of the alert ID and the expiry.
+    pub signature: Option<String>,
+}
+
+/// Signs chart URLs, so charts can only be fetched through the links that
+/// were posted to Slack, and only until they expire.
+#[derive(Clone)]
+pub struct ChartUrlSigner {
+    /// Key used to sign new URLs.
+    signing_key: SecretString,
+
+    /// Additional keys that are accepted when verifying URLs, so URLs signed
+    /// with a previous key remain valid while keys are rotated.
+    verification_keys: Vec<SecretString>,
+
+    /// How long signed URLs remain valid.
+    ttl: Duration,
+}
+
+impl ChartUrlSigner {
+    pub fn new(
+        signing_key: SecretString,
+        verification_keys: Vec<SecretString>,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            signing_key,
+            verification_keys,
+            ttl,
+        }
+    }
+
+    /// Adds an expiry and signature to the URL of the chart for the given
+    /// alert.
+    pub fn sign(&self, url: &mut Url, alert_id: i64, now: OffsetDateTime) {
+        let expires = (now + self.ttl).unix_timestamp();
+        let signature = hex::encode(
+            mac_for(&self.signing_key, alert_id, expires)
+                .finalize()
+                .into_bytes(),
+        );
+
+        url.query_pairs_mut()
+            .append_pair("expires", &expires.to_string())
+            .append_pair("signature", &signature);
+    }
+
+    /// Returns whether the signature is valid for the chart of the given
+    /// alert, and has not expired yet.
+    pub fn verify(
+        &self,
+        alert_id: i64,
+        signature: &ChartUrlSignature,
+        now: OffsetDateTime,
+    ) -> bool {
+        let (Some(expires), Some(signature)) = (signature.expires, signature.signature.as_ref())
+        else {
+            return false;
+        };
+        if expires < now.unix_timestamp() {
+            return false;
+        }
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+
+        std::iter::once(&self.signing_key)
+            .chain(&self.verification_keys)
+            .any(|key| {
+                mac_for(key, alert_id, expires)
+                    .verify_slice(&signature)
+                    .is_ok()
+            })
+    }
+}
+
+fn mac_for(key: &SecretString, alert_id: i64, expires: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.expose_secret().as_bytes())
+        .expect("HMAC can take keys of any size");
+    mac.update(format!("{alert_id}:{expires}").as_bytes());
+    mac
+}
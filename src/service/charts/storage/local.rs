@@ -0,0 +1,36 @@
+use super::ChartStorage;
+use crate::service::charts::ChartServiceError;
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+/// Stores charts as files in a local directory.
+pub struct LocalChartStorage {
+    storage_dir: PathBuf,
+}
+
+impl LocalChartStorage {
+    pub fn new(storage_dir: PathBuf) -> Self {
+        Self { storage_dir }
+    }
+}
+
+#[async_trait]
+impl ChartStorage for LocalChartStorage {
+    async fn put(&self, chart_filename: &str, image: Vec<u8>) -> Result<(), ChartServiceError> {
+        tokio::fs::write(self.storage_dir.join(chart_filename), image).await?;
+        Ok(())
+    }
+
+    async fn get(&self, chart_filename: &str) -> Result<Vec<u8>, ChartServiceError> {
+        let image = tokio::fs::read(self.storage_dir.join(chart_filename)).await?;
+        Ok(image)
+    }
+
+    async fn delete(&self, chart_filename: &str) -> Result<bool, ChartServiceError> {
+        match tokio::fs::remove_file(self.storage_dir.join(chart_filename)).await {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+mod local;
+mod s3;
+pub mod sigv4;
+#[cfg(test)]
+mod tests;
+
+pub use local::LocalChartStorage;
+pub use s3::S3ChartStorage;
+
+use super::ChartServiceError;
+use async_trait::async_trait;
+use time::OffsetDateTime;
+use url::Url;
+
+/// Where rendered charts are kept, so they can be served and uploaded after
+/// they were rendered.
+#[async_trait]
+pub trait ChartStorage: Send + Sync {
+    /// Stores a chart under the given filename, replacing any chart that was
+    /// stored under it before.
+    async fn put(&self, chart_filename: &str, image: Vec<u8>) -> Result<(), ChartServiceError>;
+
+    /// Reads a chart that was stored before.
+    async fn get(&self, chart_filename: &str) -> Result<Vec<u8>, ChartServiceError>;
+
+    /// Deletes a chart that was stored before. Returns whether the chart
+    /// still existed, as far as the storage can tell.
+    async fn delete(&self, chart_filename: &str) -> Result<bool, ChartServiceError>;
+
+    /// Returns a URL from which the chart can be fetched directly for a
+    /// limited time, if the storage supports it.
+    fn presigned_url(&self, _chart_filename: &str, _now: OffsetDateTime) -> Option<Url> {
+        None
+    }
+}
+
+/// Returns the content type to store and serve a chart with.
+pub fn content_type(chart_filename: &str) -> &'static str {
+    if chart_filename.ends_with(".svg") {
+        "image/svg+xml"
+    } else {
+        "image/png"
+    }
+}
@@ -0,0 +1,149 @@
+use super::sigv4::{authorization_headers, presign_url, uri_encode, Credentials};
+use super::{content_type, ChartStorage};
+use crate::service::charts::ChartServiceError;
+use async_trait::async_trait;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use time::{Duration, OffsetDateTime};
+use url::Url;
+
+/// Stores charts as objects in a bucket of an S3-compatible object storage,
+/// such as AWS S3 or MinIO.
+pub struct S3ChartStorage {
+    client: Client,
+
+    /// Endpoint of the S3 API, for example `https://s3.eu-west-1.amazonaws.com`.
+    endpoint: Url,
+
+    bucket: String,
+
+    /// Whether the bucket is part of the path of object URLs, instead of their
+    /// host name. MinIO needs this, unless it is configured with a domain.
+    path_style: bool,
+
+    credentials: Credentials,
+
+    /// How long presigned URLs for charts remain valid.
+    presigned_url_ttl: Duration,
+}
+
+impl S3ChartStorage {
+    pub fn new(
+        endpoint: Url,
+        bucket: String,
+        path_style: bool,
+        credentials: Credentials,
+        presigned_url_ttl: Duration,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint,
+            bucket,
+            path_style,
+            credentials,
+            presigned_url_ttl,
+        }
+    }
+
+    /// Returns the URL of the object for the given chart.
+    fn object_url(&self, chart_filename: &str) -> Url {
+        let mut url = self.endpoint.clone();
+        let mut path = url.path().trim_end_matches('/').to_owned();
+
+        if self.path_style {
+            path.push('/');
+            path.push_str(&uri_encode(&self.bucket));
+        } else if let Some(host) = url.host_str() {
+            let host = format!("{}.{host}", self.bucket);
+            // The bucket name is validated by S3, so this can't fail for
+            // buckets that exist.
+            let _ = url.set_host(Some(&host));
+        }
+
+        path.push('/');
+        path.push_str(&uri_encode(chart_filename));
+        url.set_path(&path);
+        url
+    }
+
+    /// Builds a request for the object of the given chart, authenticated
+    /// with the configured credentials.
+    fn request(&self, method: Method, chart_filename: &str, payload: &[u8]) -> RequestBuilder {
+        let url = self.object_url(chart_filename);
+        let headers = authorization_headers(
+            &self.credentials,
+            method.as_str(),
+            &url,
+            payload,
+            OffsetDateTime::now_utc(),
+        );
+
+        headers.into_iter().fold(
+            self.client.request(method, url),
+            |request, (name, value)| request.header(name, value),
+        )
+    }
+}
+
+#[async_trait]
+impl ChartStorage for S3ChartStorage {
+    async fn put(&self, chart_filename: &str, image: Vec<u8>) -> Result<(), ChartServiceError> {
+        self.request(Method::PUT, chart_filename, &image)
+            .header(CONTENT_TYPE, content_type(chart_filename))
+            .body(image)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| ChartServiceError::Storage(err.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, chart_filename: &str) -> Result<Vec<u8>, ChartServiceError> {
+        let response = self
+            .request(Method::GET, chart_filename, &[])
+            .send()
+            .await
+            .map_err(|err| ChartServiceError::Storage(err.to_string()))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ChartServiceError::NotFound);
+        }
+
+        let image = response
+            .error_for_status()
+            .map_err(|err| ChartServiceError::Storage(err.to_string()))?
+            .bytes()
+            .await
+            .map_err(|err| ChartServiceError::Storage(err.to_string()))?;
+
+        Ok(image.to_vec())
+    }
+
+    /// S3 doesn't report whether a deleted object existed, so this only
+    /// returns `false` if the storage responds with a 404.
+    async fn delete(&self, chart_filename: &str) -> Result<bool, ChartServiceError> {
+        let response = self
+            .request(Method::DELETE, chart_filename, &[])
+            .send()
+            .await
+            .map_err(|err| ChartServiceError::Storage(err.to_string()))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+
+        response
+            .error_for_status()
+            .map_err(|err| ChartServiceError::Storage(err.to_string()))?;
+        Ok(true)
+    }
+
+    fn presigned_url(&self, chart_filename: &str, now: OffsetDateTime) -> Option<Url> {
+        Some(presign_url(
+            &self.credentials,
+            Method::GET.as_str(),
+            &self.object_url(chart_filename),
+            self.presigned_url_ttl,
+            now,
+        ))
+    }
+}
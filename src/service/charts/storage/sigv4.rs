@@ -0,0 +1,195 @@
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use time::{Duration, OffsetDateTime, UtcOffset};
+use url::Url;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const SERVICE: &str = "s3";
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Credentials for signing requests to an S3-compatible API with AWS
+/// Signature Version 4.
+///
+/// See: https://docs.aws.amazon.com/AmazonS3/latest/API/sig-v4-authenticating-requests.html
+#[derive(Clone)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: SecretString,
+
+    /// Session token that comes with temporary credentials, such as those of
+    /// an assumed IAM role.
+    pub session_token: Option<SecretString>,
+
+    pub region: String,
+}
+
+/// Returns the headers that authenticate a request with the given method and
+/// URL. The URL's path needs to be URI-encoded with [`uri_encode`] already,
+/// and the URL should not have a query.
+pub fn authorization_headers(
+    credentials: &Credentials,
+    method: &str,
+    url: &Url,
+    payload: &[u8],
+    now: OffsetDateTime,
+) -> Vec<(&'static str, String)> {
+    let (date, timestamp) = format_timestamp(now);
+    let payload_hash = hex::encode(Sha256::digest(payload));
+
+    let session_token = credentials
+        .session_token
+        .as_ref()
+        .map(|token| token.expose_secret().to_owned());
+
+    let mut canonical_headers = format!(
+        "host:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{timestamp}\n",
+        host = host(url)
+    );
+    let mut signed_headers = "host;x-amz-content-sha256;x-amz-date".to_owned();
+    if let Some(session_token) = &session_token {
+        canonical_headers.push_str(&format!("x-amz-security-token:{session_token}\n"));
+        signed_headers.push_str(";x-amz-security-token");
+    }
+    let canonical_request = format!(
+        "{method}\n{path}\n\n{canonical_headers}\n{signed_headers}\n{payload_hash}",
+        path = url.path(),
+    );
+
+    let scope = credential_scope(credentials, &date);
+    let signature = sign(credentials, &date, &timestamp, &scope, &canonical_request);
+    let authorization = format!(
+        "{ALGORITHM} Credential={access_key_id}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        access_key_id = credentials.access_key_id
+    );
+
+    let mut headers = vec![
+        ("authorization", authorization),
+        ("x-amz-content-sha256", payload_hash),
+        ("x-amz-date", timestamp),
+    ];
+    if let Some(session_token) = session_token {
+        headers.push(("x-amz-security-token", session_token));
+    }
+    headers
+}
+
+/// Returns a presigned version of the URL, which can be requested with the
+/// given method without further authentication until it expires.
+pub fn presign_url(
+    credentials: &Credentials,
+    method: &str,
+    url: &Url,
+    expires_in: Duration,
+    now: OffsetDateTime,
+) -> Url {
+    let (date, timestamp) = format_timestamp(now);
+    let scope = credential_scope(credentials, &date);
+
+    // The query parameters have to be sorted by name, which they are.
+    let session_token = credentials
+        .session_token
+        .as_ref()
+        .map(|token| ("X-Amz-Security-Token", token.expose_secret().to_owned()));
+    let query = [
+        Some(("X-Amz-Algorithm", ALGORITHM.to_owned())),
+        Some((
+            "X-Amz-Credential",
+            format!("{}/{scope}", credentials.access_key_id),
+        )),
+        Some(("X-Amz-Date", timestamp.clone())),
+        Some(("X-Amz-Expires", expires_in.whole_seconds().to_string())),
+        session_token,
+        Some(("X-Amz-SignedHeaders", "host".to_owned())),
+    ]
+    .iter()
+    .flatten()
+    .map(|(key, value)| format!("{key}={}", uri_encode(value)))
+    .collect::<Vec<_>>()
+    .join("&");
+
+    let canonical_request = format!(
+        "{method}\n{path}\n{query}\nhost:{host}\n\nhost\n{UNSIGNED_PAYLOAD}",
+        path = url.path(),
+        host = host(url)
+    );
+    let signature = sign(credentials, &date, &timestamp, &scope, &canonical_request);
+
+    let mut url = url.clone();
+    url.set_query(Some(&format!("{query}&X-Amz-Signature={signature}")));
+    url
+}
+
+/// URI-encodes a value the way Signature Version 4 expects it, which is
+/// stricter than the encoding applied by [`Url`].
+pub fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+fn sign(
+    credentials: &Credentials,
+    date: &str,
+    timestamp: &str,
+    scope: &str,
+    canonical_request: &str,
+) -> String {
+    let string_to_sign = format!(
+        "{ALGORITHM}\n{timestamp}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let secret = format!("AWS4{}", credentials.secret_access_key.expose_secret());
+    let key = hmac(secret.as_bytes(), date.as_bytes());
+    let key = hmac(&key, credentials.region.as_bytes());
+    let key = hmac(&key, SERVICE.as_bytes());
+    let key = hmac(&key, b"aws4_request");
+
+    hex::encode(hmac(&key, string_to_sign.as_bytes()))
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn credential_scope(credentials: &Credentials, date: &str) -> String {
+    format!("{date}/{}/{SERVICE}/aws4_request", credentials.region)
+}
+
+/// Returns the value of the `Host` header for the URL, which only includes
+/// the port if it isn't the default one.
+fn host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_owned(),
+    }
+}
+
+/// Returns the date and the timestamp of the request, in the formats used by
+/// Signature Version 4.
+fn format_timestamp(now: OffsetDateTime) -> (String, String) {
+    let now = now.to_offset(UtcOffset::UTC);
+    let date = format!(
+        "{:04}{:02}{:02}",
+        now.year(),
+        u8::from(now.month()),
+        now.day()
+    );
+    let timestamp = format!(
+        "{date}T{:02}{:02}{:02}Z",
+        now.hour(),
+        now.minute(),
+        now.second()
+    );
+    (date, timestamp)
+}
@@ -0,0 +1,272 @@
+use super::{AlertTimeline, ChartFormat, ChartUrlSignature, ChartUrlSigner};
+use crate::db::models::{Alert, AlertEvent, AlertEventKind};
+use fiberplane::models::timestamps::Timestamp;
+use secrecy::SecretString;
+use time::{Duration, OffsetDateTime};
+use url::Url;
+
+fn signer(signing_key: &str, verification_keys: &[&str]) -> ChartUrlSigner {
+    ChartUrlSigner::new(
+        SecretString::new(signing_key.to_owned()),
+        verification_keys
+            .iter()
+            .map(|key| SecretString::new((*key).to_owned()))
+            .collect(),
+        Duration::hours(1),
+    )
+}
+
+fn signed_url(signer: &ChartUrlSigner, alert_id: i64, now: OffsetDateTime) -> ChartUrlSignature {
+    let mut url = Url::parse(&format!("http://localhost:3031/api/chart/{alert_id}")).unwrap();
+    signer.sign(&mut url, alert_id, now);
+
+    let mut signature = ChartUrlSignature::default();
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "expires" => signature.expires = Some(value.parse().unwrap()),
+            "signature" => signature.signature = Some(value.into_owned()),
+            _ => panic!("unexpected query parameter: {key}"),
+        }
+    }
+    signature
+}
+
+#[test]
+fn test_signed_chart_url() {
+    let now = OffsetDateTime::UNIX_EPOCH;
+    let signer = signer("secret", &[]);
+
+    let signature = signed_url(&signer, 1234, now);
+
+    assert_eq!(signature.expires, Some(3600));
+    assert!(signer.verify(1234, &signature, now));
+    assert!(signer.verify(1234, &signature, now + Duration::minutes(59)));
+    assert!(!signer.verify(1234, &signature, now + Duration::minutes(61)));
+    assert!(!signer.verify(1235, &signature, now));
+}
+
+#[test]
+fn test_invalid_chart_url_signatures() {
+    let now = OffsetDateTime::UNIX_EPOCH;
+    let signer = signer("secret", &[]);
+    let signature = signed_url(&signer, 1234, now);
+
+    assert!(!signer.verify(1234, &ChartUrlSignature::default(), now));
+    assert!(!signer.verify(
+        1234,
+        &ChartUrlSignature {
+            expires: None,
+            ..signature
+        },
+        now
+    ));
+
+    let signature = signed_url(&signer, 1234, now);
+    assert!(!signer.verify(
+        1234,
+        &ChartUrlSignature {
+            expires: signature.expires.map(|expires| expires + 3600),
+            ..signature
+        },
+        now
+    ));
+    assert!(!signer.verify(
+        1234,
+        &ChartUrlSignature {
+            expires: Some(3600),
+            signature: Some("not hex".to_owned()),
+        },
+        now
+    ));
+}
+
+#[test]
+fn test_chart_url_key_rotation() {
+    let now = OffsetDateTime::UNIX_EPOCH;
+    let old_signer = signer("old-secret", &[]);
+    let new_signer = signer("new-secret", &["old-secret"]);
+    let unrelated_signer = signer("other-secret", &[]);
+
+    let old_signature = signed_url(&old_signer, 1234, now);
+    let new_signature = signed_url(&new_signer, 1234, now);
+
+    assert!(new_signer.verify(1234, &old_signature, now));
+    assert!(new_signer.verify(1234, &new_signature, now));
+    assert!(!old_signer.verify(1234, &new_signature, now));
+    assert!(!unrelated_signer.verify(1234, &old_signature, now));
+}
+
+#[test]
+fn test_chart_format_from_accept_header() {
+    let cases = [
+        ("image/png", Some(ChartFormat::Png)),
+        ("image/svg+xml", Some(ChartFormat::Svg)),
+        ("*/*", Some(ChartFormat::Png)),
+        ("image/svg+xml, image/png", Some(ChartFormat::Svg)),
+        ("image/png;q=0.5, image/svg+xml", Some(ChartFormat::Svg)),
+        ("image/svg+xml;q=0.5, image/*;q=0.8", Some(ChartFormat::Png)),
+        (
+            "image/avif,image/webp,image/apng,image/svg+xml,image/*,*/*;q=0.8",
+            Some(ChartFormat::Svg),
+        ),
+        ("image/svg+xml;q=0", None),
+        ("application/json", None),
+        ("", None),
+    ];
+
+    for (accept, expected) in cases {
+        assert_eq!(
+            ChartFormat::from_accept_header(accept),
+            expected,
+            "Accept: {accept}"
+        );
+    }
+}
+
+#[test]
+fn test_chart_format_filename() {
+    assert_eq!(
+        ChartFormat::Png.chart_filename("1698652800-latency-api.png"),
+        "1698652800-latency-api.png"
+    );
+    assert_eq!(
+        ChartFormat::Svg.chart_filename("1698652800-latency-api.png"),
+        "1698652800-latency-api.svg"
+    );
+}
+
+fn alert(created_at: OffsetDateTime) -> Alert {
+    Alert {
+        id: 1234,
+        text: "High Error Rate for \"api\" [environment=production]".to_owned(),
+        resolved: false,
+        fingerprint: None,
+        occurrence: 1,
+        notebook_id: None,
+        chart_filename: Some("1234.png".to_owned()),
+        error_budget: None,
+        error_budget_chart_filename: None,
+        sloth_service: Some("api".to_owned()),
+        sloth_slo: Some("success-rate-api".to_owned()),
+        objective_name: Some("api".to_owned()),
+        severity: None,
+        slack_channel: None,
+        slack_ts: None,
+        acknowledged_by: None,
+        acknowledged_at: None,
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: Some(created_at - Duration::minutes(2)),
+        ends_at: None,
+        created_at,
+        updated_at: created_at,
+    }
+}
+
+fn event_times(timeline: &AlertTimeline) -> Vec<(Timestamp, String)> {
+    timeline
+        .events()
+        .into_iter()
+        .map(|event| (event.time, event.title))
+        .collect()
+}
+
+#[test]
+fn test_firing_alert_timeline() {
+    let created_at = OffsetDateTime::from_unix_timestamp(1698652800).unwrap();
+    let alert = alert(created_at);
+
+    let timeline = AlertTimeline::for_alert(&alert, &[]);
+    let time_range = timeline.time_range(created_at + Duration::minutes(5));
+
+    assert_eq!(
+        time_range.from,
+        Timestamp::from(created_at - Duration::hours(6))
+    );
+    assert_eq!(time_range.to, Timestamp::from(created_at));
+    assert_eq!(
+        event_times(&timeline),
+        vec![(
+            Timestamp::from(created_at - Duration::minutes(2)),
+            "Alert fired".to_owned()
+        )]
+    );
+}
+
+#[test]
+fn test_resolved_alert_timeline() {
+    let created_at = OffsetDateTime::from_unix_timestamp(1698652800).unwrap();
+    let ends_at = created_at + Duration::hours(1);
+    let alert = Alert {
+        resolved: true,
+        ends_at: Some(ends_at),
+        ..alert(created_at)
+    };
+
+    let timeline = AlertTimeline::for_alert(&alert, &[]);
+    let early_time_range = timeline.time_range(ends_at + Duration::minutes(5));
+    let late_time_range = timeline.time_range(ends_at + Duration::hours(2));
+
+    assert_eq!(
+        early_time_range.from,
+        Timestamp::from(created_at - Duration::hours(6))
+    );
+    assert_eq!(
+        early_time_range.to,
+        Timestamp::from(ends_at + Duration::minutes(5))
+    );
+    assert_eq!(
+        late_time_range.to,
+        Timestamp::from(ends_at + Duration::minutes(30))
+    );
+    assert_eq!(
+        event_times(&timeline),
+        vec![
+            (
+                Timestamp::from(created_at - Duration::minutes(2)),
+                "Alert fired".to_owned()
+            ),
+            (Timestamp::from(ends_at), "Alert resolved".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn test_manually_resolved_alert_timeline() {
+    let created_at = OffsetDateTime::from_unix_timestamp(1698652800).unwrap();
+    let resolved_at = created_at + Duration::minutes(20);
+    let alert = Alert {
+        resolved: true,
+        resolved_by: Some("U1234".to_owned()),
+        starts_at: None,
+        updated_at: created_at + Duration::minutes(40),
+        ..alert(created_at)
+    };
+    let history = [
+        AlertEvent {
+            id: 1,
+            alert_id: alert.id,
+            kind: AlertEventKind::Fired,
+            actor: None,
+            created_at,
+        },
+        AlertEvent {
+            id: 2,
+            alert_id: alert.id,
+            kind: AlertEventKind::Resolved,
+            actor: Some("U1234".to_owned()),
+            created_at: resolved_at,
+        },
+    ];
+
+    let timeline = AlertTimeline::for_alert(&alert, &history);
+
+    assert_eq!(timeline.fired_at, created_at);
+    assert_eq!(timeline.resolved_at, Some(resolved_at));
+}
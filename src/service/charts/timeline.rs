@@ -0,0 +1,90 @@
+use crate::db::models::{Alert, AlertEvent, AlertEventKind};
+use fiberplane::models::providers::{OtelMetadata, ProviderEvent};
+use fiberplane::models::timestamps::{TimeRange, Timestamp};
+use time::{Duration, OffsetDateTime};
+
+/// How much history the chart shows before the alert was created.
+const LOOKBACK: Duration = Duration::hours(6);
+
+/// How much history the chart shows at least before the alert fired.
+const LEAD_TIME: Duration = Duration::minutes(30);
+
+/// How far the chart extends past the resolution of the alert, so the
+/// recovery is visible.
+pub const RECOVERY_WINDOW: Duration = Duration::minutes(30);
+
+/// The moments in the life of an alert that are marked on its chart.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AlertTimeline {
+    /// When the alert started firing, according to Alertmanager, or when it
+    /// was created otherwise.
+    pub fired_at: OffsetDateTime,
+
+    /// When the alert was created.
+    pub created_at: OffsetDateTime,
+
+    /// When the alert was resolved, if it is.
+    pub resolved_at: Option<OffsetDateTime>,
+}
+
+impl AlertTimeline {
+    /// Returns the timeline of the given alert, using its history to find out
+    /// when it was resolved manually.
+    pub fn for_alert(alert: &Alert, history: &[AlertEvent]) -> Self {
+        let resolved_at = if alert.resolved {
+            alert
+                .ends_at
+                .or_else(|| {
+                    history
+                        .iter()
+                        .rev()
+                        .find(|event| event.kind == AlertEventKind::Resolved)
+                        .map(|event| event.created_at)
+                })
+                .or(Some(alert.updated_at))
+        } else {
+            None
+        };
+
+        Self {
+            fired_at: alert.starts_at.unwrap_or(alert.created_at),
+            created_at: alert.created_at,
+            resolved_at,
+        }
+    }
+
+    /// Returns the time range the chart should cover.
+    ///
+    /// For resolved alerts, the range extends past the resolution, up to the
+    /// current time.
+    pub fn time_range(&self, now: OffsetDateTime) -> TimeRange {
+        let to = match self.resolved_at {
+            Some(resolved_at) => (resolved_at + RECOVERY_WINDOW).min(now).max(resolved_at),
+            None => self.created_at,
+        };
+        let from = (self.created_at - LOOKBACK).min(self.fired_at - LEAD_TIME);
+
+        TimeRange {
+            from: Timestamp::from(from),
+            to: Timestamp::from(to),
+        }
+    }
+
+    /// Returns the events to mark on the chart.
+    pub fn events(&self) -> Vec<ProviderEvent> {
+        let fired = Some(event(self.fired_at, "Alert fired"));
+        let resolved = self
+            .resolved_at
+            .map(|resolved_at| event(resolved_at, "Alert resolved"));
+
+        fired.into_iter().chain(resolved).collect()
+    }
+}
+
+fn event(time: OffsetDateTime, title: &str) -> ProviderEvent {
+    ProviderEvent::builder()
+        .time(Timestamp::from(time))
+        .title(title.to_owned())
+        .otel(OtelMetadata::default())
+        .build()
+}
@@ -1,9 +1,8 @@
 use crate::db::DbError;
-use crate::events::Event;
 use crate::service::{ChartServiceError, SlackServiceError};
 use serde::Serialize;
+use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::mpsc::error::SendError;
 
 #[derive(Debug, Error, Serialize)]
 #[serde(tag = "error", content = "details", rename_all = "snake_case")]
@@ -24,6 +23,36 @@ pub enum EventLoopError {
     SlackError(SlackServiceError),
 }
 
+impl EventLoopError {
+    /// Returns whether processing the event may succeed if it is retried.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::ChannelClosed => false,
+            Self::ChartError(ChartServiceError::Storage(_)) => true,
+            // Rendering may be faster once the other render workers are less
+            // busy.
+            Self::ChartError(ChartServiceError::Timeout) => true,
+            Self::ChartError(_) => false,
+            Self::DatabaseError(_) => true,
+            Self::NotFound => false,
+            // A missing timestamp means the alert hasn't been posted yet, so
+            // the update may succeed once it has.
+            Self::SlackError(error) => !error.is_permanent(),
+        }
+    }
+
+    /// Returns how long the remote service asked us to wait before retrying,
+    /// if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::SlackError(SlackServiceError::RateLimited { retry_after_secs }) => {
+                retry_after_secs.map(Duration::from_secs)
+            }
+            _ => None,
+        }
+    }
+}
+
 impl From<ChartServiceError> for EventLoopError {
     fn from(error: ChartServiceError) -> Self {
         Self::ChartError(error)
@@ -39,12 +68,6 @@ impl From<DbError> for EventLoopError {
     }
 }
 
-impl From<SendError<Event>> for EventLoopError {
-    fn from(_error: SendError<Event>) -> Self {
-        Self::ChannelClosed
-    }
-}
-
 impl From<SlackServiceError> for EventLoopError {
     fn from(error: SlackServiceError) -> Self {
         Self::SlackError(error)
@@ -1,152 +1,788 @@
 mod errors;
+#[cfg(test)]
+mod tests;
 
 use super::Service;
-use crate::db::models::Alert;
-use crate::events::Event;
-use crate::service::prometheus::PrometheusServiceError;
-use crate::service::SlackServiceError;
+use crate::db::models::{
+    Alert, AlertEvent, AlertEventKind, AlertStats, AlertTransition, ErrorBudget, QueuedEvent,
+    SlackMessage,
+};
+use crate::events::{Event, EventLoopSignal};
+use crate::service::charts::AlertTimeline;
+use crate::service::prometheus::{
+    expression_from_generator_url, split_threshold, PrometheusServiceError,
+};
 use autometrics::autometrics;
 use errors::EventLoopError;
 use fiberplane::models::timestamps::{TimeRange, Timestamp};
+use rand::Rng;
+use sqlx::types::Json;
 use std::sync::atomic::Ordering;
-use time::ext::NumericalDuration;
+use std::sync::Arc;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::select;
 use tokio::sync::mpsc::Receiver;
-use tracing::{error, instrument, warn};
+use tokio::task::{JoinError, JoinSet};
+use tracing::{debug, error, instrument, warn};
 
 type EventResult = Result<(), EventLoopError>;
 
-/// Handle all received messages from the `event_reader` until a shutdown
-/// event is received.
+/// Interval at which the event queue is checked for events, even if the event
+/// loop isn't notified of new events.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(clap::Args, Debug)]
+pub struct EventLoopConfig {
+    /// Maximum number of attempts to process an event, before it is moved to
+    /// the dead-letter events.
+    #[clap(long, env, default_value = "8", help_heading = "Event processing")]
+    event_max_attempts: u32,
+
+    /// Delay before retrying a failed event for the first time, in
+    /// milliseconds. The delay doubles with every attempt.
+    #[clap(long, env, default_value = "1000", help_heading = "Event processing")]
+    event_retry_base_delay_ms: u64,
+
+    /// Maximum delay before retrying a failed event, in milliseconds.
+    #[clap(long, env, default_value = "300000", help_heading = "Event processing")]
+    event_retry_max_delay_ms: u64,
+
+    /// Maximum number of events that are processed concurrently.
+    ///
+    /// Events for the same alert are always processed in order, regardless of
+    /// this setting. Values below 1 are treated as 1.
+    #[clap(long, env, default_value = "8", help_heading = "Event processing")]
+    event_concurrency: usize,
+
+    /// How long an event stays claimed by the replica processing it, in
+    /// seconds. Once the claim expires, the event is processed again, so this
+    /// should be longer than processing any event may take.
+    #[clap(long, env, default_value = "900", help_heading = "Event processing")]
+    event_claim_lease_secs: u64,
+}
+
+#[cfg(test)]
+impl EventLoopConfig {
+    pub fn new_test_config() -> Self {
+        Self {
+            event_max_attempts: 3,
+            event_retry_base_delay_ms: 1000,
+            event_retry_max_delay_ms: 60_000,
+            event_concurrency: 4,
+            event_claim_lease_secs: 900,
+        }
+    }
+}
+
+impl EventLoopConfig {
+    fn claim_lease(&self) -> time::Duration {
+        time::Duration::seconds(i64::try_from(self.event_claim_lease_secs).unwrap_or(i64::MAX))
+    }
+
+    /// Returns how long to wait before retrying an event that failed for the
+    /// given number of attempts.
+    ///
+    /// Uses exponential backoff with jitter, so retries of events that failed
+    /// at the same time get spread out. If the remote service told us how long
+    /// to wait, we wait at least that long.
+    fn retry_delay(&self, attempts: u32, retry_after: Option<Duration>) -> Duration {
+        let base_delay = Duration::from_millis(self.event_retry_base_delay_ms);
+        let max_delay = Duration::from_millis(self.event_retry_max_delay_ms);
+
+        let exponent = attempts.saturating_sub(1).min(31);
+        let delay = base_delay.saturating_mul(1 << exponent).min(max_delay);
+        let jittered_delay = delay / 2 + delay.mul_f64(rand::thread_rng().gen_range(0.0..0.5));
+
+        match retry_after {
+            Some(retry_after) => jittered_delay.max(retry_after),
+            None => jittered_delay,
+        }
+    }
+}
+
+/// Handle all events in the event queue until a shutdown signal is received.
+///
+/// Events that were left over from a previous run are processed again once
+/// their claim expires.
+///
+/// Events for different alerts are processed concurrently, up to the
+/// configured limit. Events for the same alert are always processed one at a
+/// time, in the order in which they were queued.
 pub async fn handle_events(
     service: &mut Service,
-    mut event_reader: Receiver<Event>,
+    config: EventLoopConfig,
+    mut signal_receiver: Receiver<EventLoopSignal>,
 ) -> EventResult {
+    let concurrency = config.event_concurrency.max(1);
+    let config = Arc::new(config);
+    let mut tasks = JoinSet::new();
+
     loop {
-        match event_reader.recv().await {
-            Some(event) => {
-                use Event::*;
-                let result = match event {
-                    CreateChartAndPostToSlack { alert } => {
-                        handle_create_chart(service, alert).await
-                    }
-                    PostSlackAlert { alert } => handle_post_slack_alert(service, alert).await,
-                    UpdateSlackAlert { alert_id } => {
-                        handle_update_slack_alert(service, alert_id).await
-                    }
-                    Shutdown => {
-                        handle_shutdown(service);
-                        return Ok(());
+        while tasks.len() < concurrency {
+            match claim_next_event(service, &config).await {
+                Ok(Some(queued_event)) => {
+                    tasks.spawn(process_event(service.clone(), config.clone(), queued_event));
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    // The database may be back by the time we poll again.
+                    error!(?err, "Unable to claim next event");
+                    break;
+                }
+            }
+        }
+
+        // There's no point in waking up for a retry while all slots are busy,
+        // so in that case we only wait for a slot to free up.
+        let slot_available = tasks.len() < concurrency;
+        let sleep_duration = if slot_available {
+            time_until_next_retry(service)
+                .await
+                .unwrap_or_else(|err| {
+                    error!(?err, "Unable to determine when the next event is due");
+                    POLL_INTERVAL
+                })
+                .min(POLL_INTERVAL)
+        } else {
+            POLL_INTERVAL
+        };
+
+        select! {
+            signal = signal_receiver.recv() => match signal {
+                Some(EventLoopSignal::EventsQueued) => {}
+                Some(EventLoopSignal::Shutdown) => {
+                    handle_shutdown(service);
+
+                    // Let events that are already being processed finish, so
+                    // they don't need to be replayed on the next run.
+                    while let Some(result) = tasks.join_next().await {
+                        handle_task_result(result);
                     }
-                };
 
-                if let Err(err) = result {
-                    error!(?err, "Unable to process event message");
+                    return Ok(());
                 }
-            }
-            None => return Err(EventLoopError::ChannelClosed),
+                None => return Err(EventLoopError::ChannelClosed),
+            },
+            Some(result) = tasks.join_next() => handle_task_result(result),
+            _ = tokio::time::sleep(sleep_duration), if slot_available => {}
         }
     }
 }
 
+/// Logs the outcome of an event processing task. Failures only affect the
+/// event that was being processed, so the event loop keeps running.
+fn handle_task_result(result: Result<EventResult, JoinError>) {
+    match result {
+        Ok(Ok(())) => {}
+        // In both cases the event stays claimed, so it will be processed again
+        // once the claim expires. Events for other alerts can still be
+        // processed.
+        Ok(Err(err)) => error!(?err, "Unable to record the outcome of an event"),
+        Err(err) => error!(?err, "Event processing task failed"),
+    }
+}
+
+/// Processes a claimed event, and either completes it, schedules it for a
+/// retry or moves it to the dead-letter events, depending on the outcome.
+async fn process_event(
+    mut service: Service,
+    config: Arc<EventLoopConfig>,
+    queued_event: QueuedEvent,
+) -> EventResult {
+    debug!(event_id = queued_event.id, event = ?queued_event.payload, "Processing event");
+
+    let result = handle_event(
+        &mut service,
+        queued_event.id,
+        queued_event.payload.0.clone(),
+    )
+    .await;
+
+    let mut tx = service.db.start_transaction().await?;
+    match result {
+        Ok(()) => {
+            service.db.event_complete(&mut tx, queued_event.id).await?;
+        }
+        Err(err)
+            if err.is_retryable()
+                && queued_event.attempts < i64::from(config.event_max_attempts) =>
+        {
+            let attempts = u32::try_from(queued_event.attempts).unwrap_or(u32::MAX);
+            let delay = config.retry_delay(attempts, err.retry_after());
+            warn!(
+                ?err,
+                attempts,
+                ?delay,
+                "Unable to process event, retrying later"
+            );
+
+            let available_at = OffsetDateTime::now_utc() + delay;
+            service
+                .db
+                .event_retry_later(&mut tx, queued_event.id, available_at, &err.to_string())
+                .await?;
+        }
+        Err(err) => {
+            error!(
+                ?err,
+                attempts = queued_event.attempts,
+                "Unable to process event, moving it to the dead-letter events"
+            );
+            service
+                .db
+                .event_move_to_dead_letter(&mut tx, queued_event.id, &err.to_string())
+                .await?;
+        }
+    }
+    service.db.commit(tx).await?;
+
+    Ok(())
+}
+
+/// Returns how long it takes until the next event that is waiting to be
+/// retried becomes available.
+async fn time_until_next_retry(service: &Service) -> Result<Duration, EventLoopError> {
+    let mut tx = service.db.start_transaction().await?;
+    let next_available_at = service.db.event_next_available_at(&mut tx).await?;
+    service.db.commit(tx).await?;
+
+    let duration = match next_available_at {
+        Some(available_at) => (available_at - OffsetDateTime::now_utc())
+            .try_into()
+            .unwrap_or(Duration::ZERO),
+        None => POLL_INTERVAL,
+    };
+
+    Ok(duration)
+}
+
+async fn claim_next_event(
+    service: &Service,
+    config: &EventLoopConfig,
+) -> Result<Option<QueuedEvent>, EventLoopError> {
+    let mut tx = service.db.start_transaction().await?;
+    let queued_event = service
+        .db
+        .event_claim_next(&mut tx, config.claim_lease())
+        .await?;
+    service.db.commit(tx).await?;
+
+    Ok(queued_event)
+}
+
+async fn handle_event(service: &mut Service, event_id: i64, event: Event) -> EventResult {
+    use Event::*;
+    match event {
+        CreateChartAndPostToSlack { alert_id } => handle_create_chart(service, alert_id).await,
+        PostSlackAlert { alert_id } => handle_post_slack_alert(service, alert_id).await,
+        UpdateSlackAlert { alert_id } => handle_update_slack_alert(service, alert_id).await,
+        RegenerateChart { alert_id } => handle_regenerate_chart(service, alert_id).await,
+        PostSlackThreadReply {
+            alert_id,
+            transition,
+        } => handle_post_slack_thread_reply(service, event_id, alert_id, transition).await,
+    }
+}
+
 #[autometrics]
 #[instrument(err, skip(service))]
-async fn handle_create_chart(service: &mut Service, mut alert: Alert) -> EventResult {
-    match (alert.sloth_slo.as_ref(), alert.objective_name.as_ref()) {
-        (Some(slo), Some(objective_name)) => {
-            let created_at = Timestamp::from(alert.created_at);
-            let time_range = TimeRange {
-                from: created_at - 6.hours(),
-                to: created_at,
-            };
+async fn handle_create_chart(service: &mut Service, alert_id: i64) -> EventResult {
+    let mut tx = service.db.start_transaction().await?;
+    let alert = service.db.alert_get(&mut tx, alert_id).await?;
+    let history = service.db.alert_event_list(&mut tx, alert_id).await?;
+    service.db.commit(tx).await?;
+
+    let charts = create_charts(service, &alert, &history).await?;
+
+    let mut tx = service.db.start_transaction().await?;
+
+    // Fetch the alert again, since it may have changed while we were
+    // generating the chart.
+    if let Some(charts) = charts {
+        let mut alert = service.db.alert_get(&mut tx, alert_id).await?;
+        charts.apply_to(&mut alert);
+        service.db.alert_update(&mut tx, &alert).await?;
+    }
+
+    service
+        .queue_event(&mut tx, Event::PostSlackAlert { alert_id })
+        .await?;
+
+    service.db.commit(tx).await?;
+
+    Ok(())
+}
+
+#[autometrics]
+#[instrument(err, skip(service))]
+async fn handle_regenerate_chart(service: &mut Service, alert_id: i64) -> EventResult {
+    let mut tx = service.db.start_transaction().await?;
+    let alert = service.db.alert_get(&mut tx, alert_id).await?;
+    let history = service.db.alert_event_list(&mut tx, alert_id).await?;
+    service.db.commit(tx).await?;
+
+    let Some(previous_chart_filename) = alert.chart_filename.clone() else {
+        return Ok(());
+    };
+    let previous_error_budget_chart_filename = alert.error_budget_chart_filename.clone();
+    let Some(charts) = create_charts(service, &alert, &history).await? else {
+        // Keep the charts we have.
+        return Ok(());
+    };
+
+    let mut tx = service.db.start_transaction().await?;
+
+    // Fetch the alert again, since it may have changed while we were
+    // generating the chart.
+    let mut alert = service.db.alert_get(&mut tx, alert_id).await?;
+    charts.apply_to(&mut alert);
+    service.db.alert_update(&mut tx, &alert).await?;
+
+    // The new charts are uploaded when the messages are updated, to the
+    // threads they haven't been uploaded to yet.
+    service
+        .db
+        .slack_message_clear_file_ids_by_alert(&mut tx, alert_id)
+        .await?;
+    service
+        .queue_event(&mut tx, Event::UpdateSlackAlert { alert_id })
+        .await?;
+
+    service.db.commit(tx).await?;
+
+    let previous_chart_filenames = [
+        Some(previous_chart_filename),
+        previous_error_budget_chart_filename,
+    ];
+    for previous_chart_filename in previous_chart_filenames.into_iter().flatten() {
+        if alert.chart_filename.as_ref() == Some(&previous_chart_filename)
+            || alert.error_budget_chart_filename.as_ref() == Some(&previous_chart_filename)
+        {
+            continue;
+        }
 
-            match service
+        // The previous chart is no longer referenced, so failing to delete it
+        // only wastes some storage.
+        if let Err(err) = service.charts.delete_chart(&previous_chart_filename).await {
+            warn!(?err, "Could not delete previous chart");
+        }
+    }
+
+    Ok(())
+}
+
+/// The charts and numbers that were created for an SLO alert.
+struct AlertCharts {
+    chart_filename: String,
+    error_budget: Option<ErrorBudget>,
+    error_budget_chart_filename: Option<String>,
+}
+
+impl AlertCharts {
+    fn apply_to(self, alert: &mut Alert) {
+        alert.chart_filename = Some(self.chart_filename);
+        alert.error_budget = self.error_budget.map(Json);
+        alert.error_budget_chart_filename = self.error_budget_chart_filename;
+    }
+}
+
+/// Queries Prometheus and creates the charts for the alert. Returns what was
+/// created, if the main chart was.
+///
+/// For SLO alerts, the main chart shows the SLO, and the error budget is
+/// determined as of the end of the chart and charted too if enabled. Other
+/// alerts get a chart of the expression of their alerting rule. The charts
+/// mark when the alert fired and, if it is resolved already, when it was
+/// resolved.
+async fn create_charts(
+    service: &Service,
+    alert: &Alert,
+    history: &[AlertEvent],
+) -> Result<Option<AlertCharts>, EventLoopError> {
+    let (Some(slo), Some(objective_name)) =
+        (alert.sloth_slo.as_ref(), alert.objective_name.as_ref())
+    else {
+        return create_expression_chart(service, alert, history).await;
+    };
+
+    let timeline = AlertTimeline::for_alert(alert, history);
+    let time_range = timeline.time_range(OffsetDateTime::now_utc());
+
+    match service
+        .prometheus
+        .query_slo_timeseries(slo, objective_name, time_range.clone())
+        .await
+    {
+        Ok(timeseries_data) => {
+            // The chart is still useful without the threshold.
+            let threshold = service
                 .prometheus
-                .query_slo_timeseries(slo, objective_name, time_range.clone())
+                .query_slo_threshold(slo, objective_name, &alert.labels, time_range.clone())
                 .await
-            {
-                Ok(timeseries_data) => {
-                    // Not bothering to be too graceful about error handling for this one
-                    // because it's not dependent on external services. If something goes wrong inside this function
-                    // it's most likely a filesystem issue, which would be serious enough
-                    // that we might want to escalate it anyway.
-                    let filename = service
-                        .charts
-                        .create_and_store_chart(slo, time_range, timeseries_data)
-                        .await?;
-
-                    alert.chart_filename = Some(filename);
-
-                    update_alert(service, alert.clone()).await?;
-                }
-                Err(PrometheusServiceError::UnknownSlo(_)) => {
-                    // Continue without chart.
-                }
-                Err(err) => {
-                    // TODO: Should we include information in the Slack alert,
-                    //       to tell the user we couldn't query Prometheus?
-                    // NOTE: The function has a tracing::instrument attribute,
-                    //        so the spans attached to the error! call will already
-                    //        have the Alert in its attributes in theory
-                    error!(?err, "Could not query Prometheus");
-                }
+                .unwrap_or_else(|err| {
+                    warn!(?err, "Could not determine SLO threshold");
+                    None
+                });
+
+            // Not bothering to be too graceful about error handling for this one
+            // because it's not dependent on external services. If something goes wrong inside this function
+            // it's most likely a filesystem issue, which would be serious enough
+            // that we might want to escalate it anyway.
+            let chart_filename = service
+                .charts
+                .create_and_store_chart(
+                    slo,
+                    time_range.clone(),
+                    timeseries_data,
+                    timeline.events(),
+                    threshold,
+                )
+                .await?;
+
+            let error_budget = service
+                .prometheus
+                .query_error_budget(slo, objective_name, threshold, time_range.to)
+                .await
+                .unwrap_or_else(|err| {
+                    warn!(?err, "Could not determine error budget");
+                    None
+                });
+
+            let error_budget_chart_filename = if service.charts.charts_error_budget() {
+                create_error_budget_chart(
+                    service,
+                    slo,
+                    objective_name,
+                    threshold,
+                    &timeline,
+                    &time_range,
+                )
+                .await?
+            } else {
+                None
             };
+
+            Ok(Some(AlertCharts {
+                chart_filename,
+                error_budget,
+                error_budget_chart_filename,
+            }))
         }
-        _ => {
+        Err(PrometheusServiceError::UnknownSlo(_)) => {
             // Continue without chart.
+            Ok(None)
+        }
+        Err(err) => {
+            // TODO: Should we include information in the Slack alert,
+            //       to tell the user we couldn't query Prometheus?
+            // NOTE: The function has a tracing::instrument attribute,
+            //        so the spans attached to the error! call will already
+            //        have the Alert in its attributes in theory
+            error!(?err, "Could not query Prometheus");
+            Ok(None)
         }
     }
+}
 
-    service
-        .event_sender
-        .send(Event::PostSlackAlert { alert })
+/// Queries Prometheus and creates a chart of the expression of the alerting
+/// rule that fired the alert, for alerts that aren't about an SLO. Returns
+/// what was created, if the alert has an expression that returned any data.
+async fn create_expression_chart(
+    service: &Service,
+    alert: &Alert,
+    history: &[AlertEvent],
+) -> Result<Option<AlertCharts>, EventLoopError> {
+    let Some(expression) = alert
+        .generator_url
+        .as_deref()
+        .and_then(expression_from_generator_url)
+    else {
+        return Ok(None);
+    };
+    let (expression, threshold) = split_threshold(&expression);
+
+    let timeline = AlertTimeline::for_alert(alert, history);
+    let time_range = timeline.time_range(OffsetDateTime::now_utc());
+
+    let timeseries_data = match service
+        .prometheus
+        .query_expression_timeseries(expression, time_range.clone())
+        .await
+    {
+        Ok(timeseries_data) if !timeseries_data.is_empty() => timeseries_data,
+        Ok(_) => return Ok(None),
+        Err(err) => {
+            // The expression may not be valid anymore, or only work on the
+            // Prometheus that evaluated the rule.
+            warn!(?err, "Could not query alert expression");
+            return Ok(None);
+        }
+    };
+
+    let chart_filename = service
+        .charts
+        .create_and_store_chart(
+            &format!("alert-{}", alert.id),
+            time_range,
+            timeseries_data,
+            timeline.events(),
+            threshold,
+        )
         .await?;
 
-    Ok(())
+    Ok(Some(AlertCharts {
+        chart_filename,
+        error_budget: None,
+        error_budget_chart_filename: None,
+    }))
 }
 
-#[autometrics]
-#[instrument(err, skip(service))]
-async fn handle_post_slack_alert(service: &mut Service, mut alert: Alert) -> EventResult {
-    let (slack_channel, slack_ts) = service.slack.send_alert(&alert).await?;
+/// Creates a chart of the remaining error budget over the SLO period, up to
+/// the end of the chart of the SLO itself. Returns the filename of the chart,
+/// if there was anything to chart.
+async fn create_error_budget_chart(
+    service: &Service,
+    slo: &str,
+    objective_name: &str,
+    threshold: Option<f64>,
+    timeline: &AlertTimeline,
+    slo_time_range: &TimeRange,
+) -> Result<Option<String>, EventLoopError> {
+    let time_range = TimeRange {
+        from: Timestamp::from(*slo_time_range.to - service.prometheus.slo_period()),
+        to: slo_time_range.to,
+    };
+
+    let timeseries_data = match service
+        .prometheus
+        .query_error_budget_timeseries(slo, objective_name, threshold, time_range.clone())
+        .await
+    {
+        Ok(timeseries_data) if !timeseries_data.is_empty() => timeseries_data,
+        Ok(_) => return Ok(None),
+        Err(err) => {
+            // The alert is still useful without this chart.
+            warn!(?err, "Could not query error budget");
+            return Ok(None);
+        }
+    };
 
-    alert.slack_channel = Some(slack_channel.to_string());
-    alert.slack_ts = Some(slack_ts.to_string());
+    let filename = service
+        .charts
+        .create_and_store_error_budget_chart(slo, time_range, timeseries_data, timeline.events())
+        .await?;
 
-    update_alert(service, alert).await
+    Ok(Some(filename))
 }
 
 #[autometrics]
 #[instrument(err, skip(service))]
-async fn handle_update_slack_alert(service: &mut Service, alert_id: i64) -> EventResult {
+async fn handle_post_slack_alert(service: &mut Service, alert_id: i64) -> EventResult {
     let mut tx = service.db.start_transaction().await?;
-
     let alert = service.db.alert_get(&mut tx, alert_id).await?;
+    let messages = service
+        .db
+        .slack_message_list_by_alert(&mut tx, alert_id)
+        .await?;
+    let stats = service
+        .db
+        .alert_stats(&mut tx, &alert, OffsetDateTime::now_utc())
+        .await?;
+    service.db.commit(tx).await?;
 
-    if alert.slack_ts.is_some() {
-        service.slack.update_alert(&alert).await?;
-    } else {
-        Err(SlackServiceError::MissingTimestamp)?;
+    for channel in service.slack.channels_for_alert(&alert) {
+        // The alert may have been posted to this channel already before the
+        // event loop got interrupted, in which case we should not post it
+        // again.
+        let message = match messages.iter().find(|message| message.channel == channel) {
+            Some(message) => message.clone(),
+            None => post_slack_message(service, &alert, &stats, &channel).await?,
+        };
+
+        if service.slack.uploads_charts() {
+            upload_charts(service, &alert, &message).await?;
+        }
     }
 
-    service.db.commit(tx).await?;
+    Ok(())
+}
+
+/// Uploads the charts of the alert to the thread of the given message, unless
+/// they were uploaded to it already.
+///
+/// The ID of every uploaded chart is stored right away, so a retry only
+/// uploads the charts that are still missing. The error budget chart is
+/// uploaded first, so it appears above the chart of the SLO.
+async fn upload_charts(service: &Service, alert: &Alert, message: &SlackMessage) -> EventResult {
+    let Some(chart_filename) = alert.chart_filename.as_ref() else {
+        return Ok(());
+    };
+
+    if let (Some(error_budget_chart_filename), None) = (
+        alert.error_budget_chart_filename.as_ref(),
+        message.error_budget_file_id.as_ref(),
+    ) {
+        let chart = service
+            .charts
+            .read_chart(error_budget_chart_filename)
+            .await?;
+        let file_id = service
+            .slack
+            .upload_error_budget_chart(alert, message, chart)
+            .await?;
+
+        let mut tx = service.db.start_transaction().await?;
+        service
+            .db
+            .slack_message_set_error_budget_file_id(&mut tx, message.id, &file_id)
+            .await?;
+        service.db.commit(tx).await?;
+    }
+
+    if message.chart_file_id.is_none() {
+        let chart = service.charts.read_chart(chart_filename).await?;
+        let file_id = service.slack.upload_chart(alert, message, chart).await?;
+
+        let mut tx = service.db.start_transaction().await?;
+        service
+            .db
+            .slack_message_set_chart_file_id(&mut tx, message.id, &file_id)
+            .await?;
+        service.db.commit(tx).await?;
+    }
 
     Ok(())
 }
 
-#[autometrics]
-#[instrument(skip_all)]
-fn handle_shutdown(service: &mut Service) {
-    service.shutdown.store(true, Ordering::Release);
+/// Posts the alert to the given channel, and stores the message.
+async fn post_slack_message(
+    service: &Service,
+    alert: &Alert,
+    stats: &AlertStats,
+    channel: &str,
+) -> Result<SlackMessage, EventLoopError> {
+    let alert_id = alert.id;
+    let (channel_id, ts) = service.slack.send_alert(alert, stats, channel).await?;
+
+    // Store every message right away, so a failure to post to one of the
+    // other channels doesn't cause this one to be posted again.
+    let mut tx = service.db.start_transaction().await?;
+    let message = service
+        .db
+        .slack_message_create(
+            &mut tx,
+            alert_id,
+            channel,
+            &channel_id.to_string(),
+            &ts.to_string(),
+        )
+        .await?;
+
+    // Fetch the alert again, since it may have changed while we were posting
+    // it. The first message is also stored on the alert itself.
+    let mut alert = service.db.alert_get(&mut tx, alert_id).await?;
+    if alert.slack_ts.is_none() {
+        alert.slack_channel = Some(channel_id.to_string());
+        alert.slack_ts = Some(ts.to_string());
+        service.db.alert_update(&mut tx, &alert).await?;
+        service
+            .db
+            .alert_event_create(&mut tx, alert_id, AlertEventKind::Posted, None)
+            .await?;
+    }
+
+    service.db.commit(tx).await?;
+
+    Ok(message)
 }
 
 #[autometrics]
 #[instrument(err, skip(service))]
-async fn update_alert(service: &mut Service, alert: Alert) -> EventResult {
+async fn handle_update_slack_alert(service: &mut Service, alert_id: i64) -> EventResult {
     let mut tx = service.db.start_transaction().await?;
+    let alert = service.db.alert_get(&mut tx, alert_id).await?;
+    let messages = service
+        .db
+        .slack_message_list_by_alert(&mut tx, alert_id)
+        .await?;
+    let stats = service
+        .db
+        .alert_stats(&mut tx, &alert, OffsetDateTime::now_utc())
+        .await?;
+    service.db.commit(tx).await?;
 
-    service.db.alert_update(&mut tx, &alert).await?;
+    // The alert may have changed before it was posted, for example while its
+    // chart was being created. There's nothing to update yet then, and the
+    // message will show the current state of the alert once it is posted.
+    if messages.is_empty() {
+        return Ok(());
+    }
+
+    service
+        .slack
+        .update_alert(&alert, &stats, &messages)
+        .await?;
 
+    if service.slack.uploads_charts() {
+        for message in &messages {
+            upload_charts(service, &alert, message).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[autometrics]
+#[instrument(err, skip(service))]
+async fn handle_post_slack_thread_reply(
+    service: &mut Service,
+    event_id: i64,
+    alert_id: i64,
+    transition: AlertTransition,
+) -> EventResult {
+    let mut tx = service.db.start_transaction().await?;
+    let alert = service.db.alert_get(&mut tx, alert_id).await?;
+    let messages = service
+        .db
+        .slack_message_list_by_alert(&mut tx, alert_id)
+        .await?;
+    let replied_message_ids = service
+        .db
+        .slack_thread_reply_list_by_event(&mut tx, event_id)
+        .await?;
     service.db.commit(tx).await?;
 
+    // Like updates, replies about transitions before the alert was posted
+    // are skipped, since the message shows the current state already.
+    if messages.is_empty() {
+        return Ok(());
+    }
+
+    // The reply is recorded for every message it was posted to, so a retry
+    // after a failure only posts it to the remaining ones.
+    for message in messages
+        .iter()
+        .filter(|message| !replied_message_ids.contains(&message.id))
+    {
+        service
+            .slack
+            .post_thread_reply(&alert, message, transition)
+            .await?;
+
+        let mut tx = service.db.start_transaction().await?;
+        service
+            .db
+            .slack_thread_reply_create(&mut tx, event_id, message.id)
+            .await?;
+        service.db.commit(tx).await?;
+    }
+
     Ok(())
 }
+
+#[autometrics]
+#[instrument(skip_all)]
+fn handle_shutdown(service: &mut Service) {
+    service.shutdown.store(true, Ordering::Release);
+}
@@ -0,0 +1,505 @@
+use super::errors::EventLoopError;
+use super::{handle_event, EventLoopConfig};
+use crate::db::models::{Alert, AlertTransition, NewAlert};
+use crate::db::{Db, DbError};
+use crate::events::Event;
+use crate::service::{ChartServiceError, SlackServiceError};
+use crate::testutil::*;
+use std::time::Duration;
+use time::OffsetDateTime;
+
+async fn create_alert(db: &Db, fingerprint: &str) -> Alert {
+    let mut tx = db.start_transaction().await.unwrap();
+    let alert = db
+        .alert_create(
+            &mut tx,
+            NewAlert {
+                text: "Something is wrong".to_owned(),
+                resolved: false,
+                fingerprint: Some(fingerprint.to_owned()),
+                notebook_id: None,
+                chart_filename: None,
+                slack_channel: None,
+                slack_ts: None,
+                sloth_slo: None,
+                sloth_service: None,
+                objective_name: None,
+                severity: None,
+                labels: Default::default(),
+                annotations: Default::default(),
+                generator_url: None,
+                starts_at: None,
+                ends_at: None,
+            },
+        )
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    alert
+}
+
+#[test]
+fn test_retry_delay_grows_exponentially() {
+    let config = EventLoopConfig::new_test_config();
+
+    for (attempts, delay_ms) in [(1, 1000), (2, 2000), (3, 4000), (4, 8000)] {
+        let delay = config.retry_delay(attempts, None);
+        assert!(delay >= Duration::from_millis(delay_ms / 2), "{delay:?}");
+        assert!(delay < Duration::from_millis(delay_ms), "{delay:?}");
+    }
+}
+
+#[test]
+fn test_retry_delay_is_capped() {
+    let config = EventLoopConfig::new_test_config();
+
+    let delay = config.retry_delay(u32::MAX, None);
+
+    assert!(delay < Duration::from_millis(config.event_retry_max_delay_ms));
+}
+
+#[test]
+fn test_retry_delay_respects_retry_after() {
+    let config = EventLoopConfig::new_test_config();
+
+    let delay = config.retry_delay(1, Some(Duration::from_secs(30)));
+
+    assert_eq!(delay, Duration::from_secs(30));
+}
+
+#[test]
+fn test_error_classification() {
+    let rate_limited = EventLoopError::from(SlackServiceError::RateLimited {
+        retry_after_secs: Some(5),
+    });
+    assert!(rate_limited.is_retryable());
+    assert_eq!(rate_limited.retry_after(), Some(Duration::from_secs(5)));
+
+    let storage = EventLoopError::from(ChartServiceError::Storage("disk full".to_owned()));
+    assert!(storage.is_retryable());
+    assert_eq!(storage.retry_after(), None);
+
+    assert!(EventLoopError::from(ChartServiceError::Timeout).is_retryable());
+    assert!(!EventLoopError::from(ChartServiceError::Generation).is_retryable());
+    assert!(!EventLoopError::from(DbError::NotFound).is_retryable());
+
+    let channel_not_found = SlackServiceError::Api {
+        code: "channel_not_found".to_owned(),
+    };
+    assert!(!EventLoopError::from(channel_not_found).is_retryable());
+    let internal_error = SlackServiceError::Api {
+        code: "internal_error".to_owned(),
+    };
+    assert!(EventLoopError::from(internal_error).is_retryable());
+    assert!(EventLoopError::from(SlackServiceError::MissingTimestamp).is_retryable());
+}
+
+#[tokio::test]
+async fn events_wait_until_available() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, .. }| async move {
+            // arrange
+            let alert = create_alert(&db, "01234").await;
+            let event = Event::PostSlackAlert { alert_id: alert.id };
+            let available_at = OffsetDateTime::now_utc() + time::Duration::minutes(5);
+
+            let mut tx = db.start_transaction().await.unwrap();
+            db.event_enqueue(&mut tx, &event).await.unwrap();
+            let claimed_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+
+            // act
+            db.event_retry_later(&mut tx, claimed_event.id, available_at, "Slack is down")
+                .await
+                .unwrap();
+            let no_event = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap();
+            let next_available_at = db.event_next_available_at(&mut tx).await.unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(claimed_event.attempts, 1);
+            assert!(no_event.is_none());
+            assert_eq!(next_available_at, Some(available_at));
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn events_move_to_dead_letter_and_replay() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, .. }| async move {
+            // arrange
+            let alert = create_alert(&db, "01234").await;
+            let event = Event::PostSlackAlert { alert_id: alert.id };
+
+            let mut tx = db.start_transaction().await.unwrap();
+            db.event_enqueue(&mut tx, &event).await.unwrap();
+            let claimed_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+
+            // act
+            let dead_letter_event = db
+                .event_move_to_dead_letter(&mut tx, claimed_event.id, "Slack is down")
+                .await
+                .unwrap();
+            let no_event = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap();
+
+            db.dead_letter_event_replay(&mut tx, dead_letter_event.id)
+                .await
+                .unwrap();
+            let dead_letter_events = db.dead_letter_event_list(&mut tx).await.unwrap();
+            let replayed_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert!(no_event.is_none());
+            assert_eq!(dead_letter_event.payload.0, event);
+            assert_eq!(dead_letter_event.attempts, 1);
+            assert_eq!(
+                dead_letter_event.last_error.as_deref(),
+                Some("Slack is down")
+            );
+            assert!(dead_letter_events.is_empty());
+            assert_eq!(replayed_event.payload.0, event);
+            assert_eq!(replayed_event.attempts, 1);
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn events_for_the_same_alert_are_claimed_in_order() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, .. }| async move {
+            // arrange
+            let first_alert = create_alert(&db, "01234").await;
+            let second_alert = create_alert(&db, "56789").await;
+
+            let mut tx = db.start_transaction().await.unwrap();
+            for event in [
+                Event::PostSlackAlert {
+                    alert_id: first_alert.id,
+                },
+                Event::UpdateSlackAlert {
+                    alert_id: first_alert.id,
+                },
+                Event::PostSlackAlert {
+                    alert_id: second_alert.id,
+                },
+            ] {
+                db.event_enqueue(&mut tx, &event).await.unwrap();
+            }
+            tx.commit().await.unwrap();
+
+            // act
+            let mut tx = db.start_transaction().await.unwrap();
+            let first_post = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            let second_post = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            let blocked_update = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap();
+
+            db.event_complete(&mut tx, first_post.id).await.unwrap();
+            let first_update = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(first_post.alert_id, first_alert.id);
+            assert_eq!(second_post.alert_id, second_alert.id);
+            assert!(blocked_update.is_none());
+            assert_eq!(
+                first_update.payload.0,
+                Event::UpdateSlackAlert {
+                    alert_id: first_alert.id
+                }
+            );
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn updates_before_the_alert_is_posted_do_not_hold_up_the_post() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, mut service }| async move {
+            // arrange
+            let alert = create_alert(&db, "01234").await;
+
+            // The alert was resolved while its chart was being created, so
+            // the post is queued after the update and the reply.
+            let mut tx = db.start_transaction().await.unwrap();
+            for event in [
+                Event::UpdateSlackAlert { alert_id: alert.id },
+                Event::PostSlackThreadReply {
+                    alert_id: alert.id,
+                    transition: AlertTransition::Resolved,
+                },
+                Event::PostSlackAlert { alert_id: alert.id },
+            ] {
+                db.event_enqueue(&mut tx, &event).await.unwrap();
+            }
+            tx.commit().await.unwrap();
+
+            // act
+            let mut results = Vec::new();
+            for _ in 0..2 {
+                let mut tx = db.start_transaction().await.unwrap();
+                let queued_event = db
+                    .event_claim_next(&mut tx, CLAIM_LEASE)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                tx.commit().await.unwrap();
+
+                let result =
+                    handle_event(&mut service, queued_event.id, queued_event.payload.0).await;
+                results.push(result);
+
+                let mut tx = db.start_transaction().await.unwrap();
+                db.event_complete(&mut tx, queued_event.id).await.unwrap();
+                tx.commit().await.unwrap();
+            }
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let post_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_matches!(results.as_slice(), [Ok(()), Ok(())]);
+            assert_eq!(
+                post_event.payload.0,
+                Event::PostSlackAlert { alert_id: alert.id }
+            );
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn events_wait_for_earlier_retries() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, .. }| async move {
+            // arrange
+            let alert = create_alert(&db, "01234").await;
+            let available_at = OffsetDateTime::now_utc() + time::Duration::minutes(5);
+
+            let mut tx = db.start_transaction().await.unwrap();
+            db.event_enqueue(&mut tx, &Event::PostSlackAlert { alert_id: alert.id })
+                .await
+                .unwrap();
+            db.event_enqueue(&mut tx, &Event::UpdateSlackAlert { alert_id: alert.id })
+                .await
+                .unwrap();
+            let post_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+
+            // act
+            db.event_retry_later(&mut tx, post_event.id, available_at, "Slack is down")
+                .await
+                .unwrap();
+            let no_event = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert!(no_event.is_none());
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn scheduled_events_do_not_hold_up_later_events() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, .. }| async move {
+            // arrange
+            let alert = create_alert(&db, "01234").await;
+            let available_at = OffsetDateTime::now_utc() + time::Duration::minutes(30);
+
+            let mut tx = db.start_transaction().await.unwrap();
+            db.event_schedule(
+                &mut tx,
+                &Event::RegenerateChart { alert_id: alert.id },
+                available_at,
+            )
+            .await
+            .unwrap();
+            db.event_enqueue(&mut tx, &Event::UpdateSlackAlert { alert_id: alert.id })
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // act
+            let mut tx = db.start_transaction().await.unwrap();
+            let update_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+            db.event_complete(&mut tx, update_event.id).await.unwrap();
+            let no_event = db.event_claim_next(&mut tx, CLAIM_LEASE).await.unwrap();
+            let next_available_at = db.event_next_available_at(&mut tx).await.unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(
+                update_event.payload.0,
+                Event::UpdateSlackAlert { alert_id: alert.id }
+            );
+            assert!(no_event.is_none());
+            assert_eq!(next_available_at, Some(available_at));
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn thread_replies_are_recorded_until_the_event_completes() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, .. }| async move {
+            // arrange
+            let alert = create_alert(&db, "01234").await;
+            let event = Event::PostSlackThreadReply {
+                alert_id: alert.id,
+                transition: AlertTransition::Resolved,
+            };
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let message = db
+                .slack_message_create(&mut tx, alert.id, "#alerts", "C0123", "1698400000.000100")
+                .await
+                .unwrap();
+            db.event_enqueue(&mut tx, &event).await.unwrap();
+            let claimed_event = db
+                .event_claim_next(&mut tx, CLAIM_LEASE)
+                .await
+                .unwrap()
+                .unwrap();
+
+            // act
+            db.slack_thread_reply_create(&mut tx, claimed_event.id, message.id)
+                .await
+                .unwrap();
+            let replied_message_ids = db
+                .slack_thread_reply_list_by_event(&mut tx, claimed_event.id)
+                .await
+                .unwrap();
+            db.event_complete(&mut tx, claimed_event.id).await.unwrap();
+            let remaining_message_ids = db
+                .slack_thread_reply_list_by_event(&mut tx, claimed_event.id)
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            assert_eq!(replied_message_ids, vec![message.id]);
+            assert!(remaining_message_ids.is_empty());
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn uploaded_charts_are_recorded_per_message_until_regenerated() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, .. }| async move {
+            // arrange
+            let alert = create_alert(&db, "01234").await;
+
+            let mut tx = db.start_transaction().await.unwrap();
+            let first_message = db
+                .slack_message_create(&mut tx, alert.id, "#alerts", "C0123", "1698400000.000100")
+                .await
+                .unwrap();
+            let second_message = db
+                .slack_message_create(&mut tx, alert.id, "#slo", "C4567", "1698400000.000200")
+                .await
+                .unwrap();
+
+            // act
+            db.slack_message_set_error_budget_file_id(&mut tx, first_message.id, "F0001")
+                .await
+                .unwrap();
+            db.slack_message_set_chart_file_id(&mut tx, first_message.id, "F0002")
+                .await
+                .unwrap();
+            db.slack_message_set_error_budget_file_id(&mut tx, second_message.id, "F0003")
+                .await
+                .unwrap();
+            let uploaded_messages = db
+                .slack_message_list_by_alert(&mut tx, alert.id)
+                .await
+                .unwrap();
+            db.slack_message_clear_file_ids_by_alert(&mut tx, alert.id)
+                .await
+                .unwrap();
+            let regenerated_messages = db
+                .slack_message_list_by_alert(&mut tx, alert.id)
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // assert
+            let file_ids: Vec<_> = uploaded_messages
+                .iter()
+                .map(|message| {
+                    (
+                        message.error_budget_file_id.as_deref(),
+                        message.chart_file_id.as_deref(),
+                    )
+                })
+                .collect();
+            assert_eq!(
+                file_ids,
+                vec![(Some("F0001"), Some("F0002")), (Some("F0003"), None)]
+            );
+            assert!(regenerated_messages.iter().all(|message| {
+                message.error_budget_file_id.is_none() && message.chart_file_id.is_none()
+            }));
+        },
+    )
+    .await;
+}
@@ -0,0 +1,108 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A label matcher, using the same syntax as Alertmanager and Prometheus
+/// selectors: `name=value`, `name!=value`, `name=~regex` or `name!~regex`.
+///
+/// Regular expressions are anchored, so they need to match the entire label
+/// value. A label that is not present matches as if its value were empty.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct LabelMatcher {
+    pub name: String,
+    pub kind: LabelMatcherKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum LabelMatcherKind {
+    Equal(String),
+    NotEqual(String),
+    Regex(Regex),
+    NotRegex(Regex),
+}
+
+#[derive(Debug, Error)]
+pub enum LabelMatcherError {
+    #[error("Missing operator in label matcher: {0}")]
+    MissingOperator(String),
+
+    #[error("Missing label name in label matcher: {0}")]
+    MissingName(String),
+
+    #[error("Invalid regular expression in label matcher: {0}")]
+    InvalidRegex(#[from] regex::Error),
+}
+
+impl LabelMatcher {
+    /// Returns whether the given labels are matched.
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        let value = labels.get(&self.name).map(String::as_str).unwrap_or("");
+        match &self.kind {
+            LabelMatcherKind::Equal(expected) => value == expected,
+            LabelMatcherKind::NotEqual(expected) => value != expected,
+            LabelMatcherKind::Regex(regex) => regex.is_match(value),
+            LabelMatcherKind::NotRegex(regex) => !regex.is_match(value),
+        }
+    }
+
+    /// Returns whether the given labels are matched by all of the matchers.
+    pub fn all_match(matchers: &[LabelMatcher], labels: &BTreeMap<String, String>) -> bool {
+        matchers.iter().all(|matcher| matcher.matches(labels))
+    }
+}
+
+impl FromStr for LabelMatcher {
+    type Err = LabelMatcherError;
+
+    fn from_str(matcher: &str) -> Result<Self, Self::Err> {
+        let operator_index = matcher
+            .find(['=', '!'])
+            .ok_or_else(|| LabelMatcherError::MissingOperator(matcher.to_owned()))?;
+
+        let name = matcher[..operator_index].trim();
+        if name.is_empty() {
+            return Err(LabelMatcherError::MissingName(matcher.to_owned()));
+        }
+
+        let rest = &matcher[operator_index..];
+        let (operator, value) = ["=~", "!~", "!=", "="]
+            .into_iter()
+            .find_map(|operator| rest.strip_prefix(operator).map(|value| (operator, value)))
+            .ok_or_else(|| LabelMatcherError::MissingOperator(matcher.to_owned()))?;
+        let value = unquote(value.trim());
+
+        let kind = match operator {
+            "=~" => LabelMatcherKind::Regex(anchored_regex(value)?),
+            "!~" => LabelMatcherKind::NotRegex(anchored_regex(value)?),
+            "!=" => LabelMatcherKind::NotEqual(value.to_owned()),
+            _ => LabelMatcherKind::Equal(value.to_owned()),
+        };
+
+        Ok(Self {
+            name: name.to_owned(),
+            kind,
+        })
+    }
+}
+
+impl TryFrom<String> for LabelMatcher {
+    type Error = LabelMatcherError;
+
+    fn try_from(matcher: String) -> Result<Self, Self::Error> {
+        matcher.parse()
+    }
+}
+
+fn anchored_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{pattern})$"))
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
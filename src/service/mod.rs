@@ -1,23 +1,36 @@
+mod admin;
 mod alertmanager;
+mod alerts;
+mod auth;
 mod charts;
+mod matchers;
 mod metrics;
 mod prometheus;
 mod slack;
 
 pub mod event_loop;
+pub mod retention;
 pub mod router;
 
-use crate::db::Db;
-use crate::events::Event;
+use crate::db::models::{Alert, AlertTransition};
+use crate::db::{Db, DbError};
+use crate::events::{Event, EventLoopSignal};
+use admin::AdminService;
+use alertmanager::AlertmanagerService;
 use autometrics::objectives::{Objective, ObjectiveLatency, ObjectivePercentile};
 use axum::extract::FromRef;
-use charts::ChartService;
+use charts::{ChartService, RECOVERY_WINDOW};
 use prometheus::PrometheusService;
 use slack::SlackService;
 use std::sync::{atomic::AtomicBool, Arc};
+use time::OffsetDateTime;
+use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::mpsc::Sender;
+use tracing::warn;
 use url::Url;
 
+pub use admin::AdminServiceConfig;
+pub use alertmanager::{AlertmanagerServiceConfig, AlertmanagerServiceError};
 pub use charts::{ChartServiceConfig, ChartServiceError};
 pub use prometheus::{PrometheusServiceConfig, PrometheusServiceError};
 pub use slack::{SlackServiceConfig, SlackServiceError};
@@ -34,27 +47,36 @@ pub struct GlobalState {
 
 #[derive(Clone)]
 pub struct Service {
+    admin: Arc<AdminService>,
+    alertmanager: Arc<AlertmanagerService>,
     charts: Arc<ChartService>,
     db: Db,
-    event_sender: Sender<Event>,
+    event_sender: Sender<EventLoopSignal>,
     prometheus: Arc<PrometheusService>,
     shutdown: Arc<AtomicBool>,
     slack: Arc<SlackService>,
 }
 
 impl Service {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         service_base_url: Url,
+        admin_config: AdminServiceConfig,
+        alertmanager_config: AlertmanagerServiceConfig,
         chart_config: ChartServiceConfig,
         db: Db,
-        event_sender: Sender<Event>,
+        event_sender: Sender<EventLoopSignal>,
         explorer_base_url: Option<Url>,
         prometheus_config: PrometheusServiceConfig,
         slack_config: SlackServiceConfig,
     ) -> Self {
         let prometheus_url = prometheus_config.prometheus_url.clone();
+        let charts = ChartService::new(chart_config);
+        let chart_url_signer = charts.url_signer().cloned();
         Self {
-            charts: Arc::new(ChartService::new(chart_config)),
+            admin: Arc::new(AdminService::new(admin_config)),
+            alertmanager: Arc::new(AlertmanagerService::new(alertmanager_config)),
+            charts: Arc::new(charts),
             db,
             event_sender,
             prometheus: Arc::new(PrometheusService::new(prometheus_config)),
@@ -64,7 +86,75 @@ impl Service {
                 slack_config,
                 prometheus_url,
                 explorer_base_url,
+                chart_url_signer,
             )),
         }
     }
+
+    /// Adds an event to the event queue as part of the given transaction.
+    ///
+    /// Call `notify_event_loop()` once the transaction is committed, so the
+    /// event gets processed right away.
+    pub async fn queue_event(
+        &self,
+        tx: &mut crate::db::Transaction<'_>,
+        event: Event,
+    ) -> Result<(), DbError> {
+        self.db.event_enqueue(tx, &event).await?;
+        Ok(())
+    }
+
+    /// Queues an update of the Slack messages for the given alert.
+    ///
+    /// If the update is caused by a transition and thread replies are
+    /// enabled, a reply about the transition is queued as well. If the alert
+    /// was resolved and has a chart, the chart is regenerated to show the
+    /// resolution and recovery, once the recovery window has passed.
+    pub async fn queue_alert_update(
+        &self,
+        tx: &mut crate::db::Transaction<'_>,
+        alert: &Alert,
+        transition: Option<AlertTransition>,
+    ) -> Result<(), DbError> {
+        let alert_id = alert.id;
+        self.queue_event(tx, Event::UpdateSlackAlert { alert_id })
+            .await?;
+
+        if let Some(transition) = transition {
+            if self.slack.thread_replies_enabled() {
+                self.queue_event(
+                    tx,
+                    Event::PostSlackThreadReply {
+                        alert_id,
+                        transition,
+                    },
+                )
+                .await?;
+            }
+        }
+
+        // The chart is regenerated once it can show the recovery after the
+        // resolution. Until then, later events for the alert go ahead.
+        if transition == Some(AlertTransition::Resolved) && alert.chart_filename.is_some() {
+            let now = OffsetDateTime::now_utc();
+            let resolved_at = alert.ends_at.unwrap_or(now);
+            let regenerate_at = resolved_at.checked_add(RECOVERY_WINDOW).unwrap_or(now);
+            self.db
+                .event_schedule(tx, &Event::RegenerateChart { alert_id }, regenerate_at)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Wakes up the event loop to process newly queued events.
+    pub fn notify_event_loop(&self) {
+        match self.event_sender.try_send(EventLoopSignal::EventsQueued) {
+            // If the channel is full, the event loop has pending wake-ups
+            // already.
+            Ok(()) | Err(TrySendError::Full(_)) => {}
+            // The queued events will be picked up after a restart.
+            Err(TrySendError::Closed(_)) => warn!("Event loop is not running"),
+        }
+    }
 }
@@ -0,0 +1,105 @@
+use super::PrometheusServiceError;
+
+/// Windows over which burn rates are reported, shortest first. These match
+/// the windows of the multi-window, multi-burn-rate alerts generated by
+/// Sloth, so the numbers can be compared with the alerting rules.
+pub(crate) const BURN_RATE_WINDOWS: [&str; 4] = ["5m", "1h", "6h", "3d"];
+
+/// Returns the error budget of the given SLO, as the ratio of requests that
+/// may fail the objective.
+pub(crate) fn error_budget_for_slo(slo: &str) -> Result<f64, PrometheusServiceError> {
+    let percentile = objective_percentile(slo)?;
+    let percentile: f64 = percentile
+        .parse()
+        .map_err(|_| PrometheusServiceError::InvalidPercentile(percentile.to_owned()))?;
+
+    Ok(1.0 - percentile / 100.0)
+}
+
+/// Returns a query for the ratio of requests that failed the objective over
+/// the given window.
+///
+/// For latency SLOs, requests fail the objective if they take longer than the
+/// latency threshold, so `None` is returned if the threshold isn't known.
+pub(crate) fn error_ratio_query(
+    slo: &str,
+    objective_name: &str,
+    threshold: Option<f64>,
+    window: &str,
+) -> Result<Option<String>, PrometheusServiceError> {
+    let percentile = objective_percentile(slo)?;
+
+    if slo.starts_with("success-rate-") {
+        Ok(Some(format!(
+            r#"
+1 - (
+    sum(
+        rate(
+            {{
+                __name__=~"function_calls(_count)?(_total)?",
+                result="ok",
+                objective_name="{objective_name}",
+                objective_percentile="{percentile}"
+            }}[{window}]
+        )
+    ) / sum(
+        rate(
+            {{
+                __name__=~"function_calls(_count)?(_total)?",
+                objective_name="{objective_name}",
+                objective_percentile="{percentile}"
+            }}[{window}]
+        )
+    )
+)
+            "#
+        )))
+    } else if let Some(threshold) = threshold {
+        // Requests that were fast enough end up in the bucket of which the
+        // upper bound is the threshold.
+        let threshold = bucket_bound_pattern(threshold);
+        Ok(Some(format!(
+            r#"
+1 - (
+    sum(
+        rate(
+            {{
+                __name__=~"function_calls_duration(_seconds)?_bucket",
+                le=~"{threshold}",
+                objective_name="{objective_name}",
+                objective_percentile="{percentile}"
+            }}[{window}]
+        )
+    ) / sum(
+        rate(
+            {{
+                __name__=~"function_calls_duration(_seconds)?_count",
+                objective_name="{objective_name}",
+                objective_percentile="{percentile}"
+            }}[{window}]
+        )
+    )
+)
+            "#
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns a regex matching the `le` label of the histogram bucket with the
+/// given upper bound. Exporters format bounds differently, e.g. `1` or `1.0`,
+/// so any number of trailing zeros is matched.
+fn bucket_bound_pattern(bound: f64) -> String {
+    let bound = bound.to_string();
+    match bound.split_once('.') {
+        Some((integer, fraction)) => format!(r"{integer}\\.{fraction}0*"),
+        None => format!(r"{bound}(\\.0*)?"),
+    }
+}
+
+fn objective_percentile(slo: &str) -> Result<&str, PrometheusServiceError> {
+    slo.strip_prefix("success-rate-")
+        .or_else(|| slo.strip_prefix("latency-"))
+        .ok_or_else(|| PrometheusServiceError::UnknownSlo(slo.to_owned()))
+}
@@ -0,0 +1,154 @@
+use super::{PrometheusServiceConfig, PrometheusServiceError};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, Identity, RequestBuilder};
+use secrecy::{ExposeSecret, SecretString};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use url::Url;
+
+/// A header that is sent along with every request to Prometheus, given as
+/// `Name: value`.
+#[derive(Clone, Debug)]
+pub struct PrometheusHeader {
+    name: HeaderName,
+
+    /// The value may contain credentials, such as an API key.
+    value: SecretString,
+}
+
+impl FromStr for PrometheusHeader {
+    type Err = String;
+
+    fn from_str(header: &str) -> Result<Self, Self::Err> {
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| format!("Header should be formatted as `Name: value`: {header}"))?;
+        let name = HeaderName::from_str(name.trim())
+            .map_err(|err| format!("Invalid header name {name:?}: {err}"))?;
+        let value = value.trim();
+        HeaderValue::from_str(value)
+            .map_err(|err| format!("Invalid value for header {name}: {err}"))?;
+
+        Ok(Self {
+            name,
+            value: SecretString::new(value.to_owned()),
+        })
+    }
+}
+
+enum Authentication {
+    None,
+    Bearer(SecretString),
+    Basic {
+        username: String,
+        password: Option<SecretString>,
+    },
+}
+
+/// Client for the Prometheus API, which applies the configured
+/// authentication, headers and TLS settings to every request.
+pub(crate) struct PrometheusClient {
+    client: Client,
+    base_url: Url,
+    authentication: Authentication,
+}
+
+impl PrometheusClient {
+    pub fn new(config: &PrometheusServiceConfig) -> Result<Self, PrometheusServiceError> {
+        let mut headers = HeaderMap::new();
+        for header in &config.prometheus_headers {
+            let mut value = HeaderValue::from_str(header.value.expose_secret())
+                .map_err(|err| PrometheusServiceError::Config(err.to_string()))?;
+            value.set_sensitive(true);
+            headers.append(header.name.clone(), value);
+        }
+
+        let mut builder = Client::builder()
+            .timeout(Duration::from_secs(15))
+            .default_headers(headers);
+
+        if let Some(ca_cert) = config.prometheus_ca_cert.as_deref() {
+            let ca_cert = Certificate::from_pem(&read_file(ca_cert)?)
+                .map_err(|err| PrometheusServiceError::Config(err.to_string()))?;
+            builder = builder.add_root_certificate(ca_cert);
+        }
+
+        // Both are required if either is given, which is enforced when
+        // parsing the config.
+        if let (Some(client_cert), Some(client_key)) = (
+            config.prometheus_client_cert.as_deref(),
+            config.prometheus_client_key.as_deref(),
+        ) {
+            let mut pem = read_file(client_cert)?;
+            pem.push(b'\n');
+            pem.extend(read_file(client_key)?);
+            let identity = Identity::from_pem(&pem)
+                .map_err(|err| PrometheusServiceError::Config(err.to_string()))?;
+            builder = builder.identity(identity);
+        }
+
+        let client = builder
+            .build()
+            .map_err(|err| PrometheusServiceError::Config(err.to_string()))?;
+
+        let authentication = match (
+            config.prometheus_bearer_token.clone(),
+            config.prometheus_username.clone(),
+        ) {
+            (Some(token), _) => Authentication::Bearer(token),
+            (None, Some(username)) => Authentication::Basic {
+                username,
+                password: config.prometheus_password.clone(),
+            },
+            (None, None) => Authentication::None,
+        };
+
+        Ok(Self {
+            client,
+            base_url: config.prometheus_url.clone(),
+            authentication,
+        })
+    }
+
+    /// Returns the URL of the API endpoint with the given path segments,
+    /// relative to the base URL of Prometheus.
+    pub fn api_url(&self, segments: &[&str]) -> Result<Url, PrometheusServiceError> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| {
+                PrometheusServiceError::Config(format!(
+                    "Cannot append to prometheus base URL: {}",
+                    self.base_url
+                ))
+            })?
+            .extend(segments);
+
+        Ok(url)
+    }
+
+    pub fn get(&self, url: Url) -> RequestBuilder {
+        self.authenticate(self.client.get(url))
+    }
+
+    pub fn post(&self, url: Url) -> RequestBuilder {
+        self.authenticate(self.client.post(url))
+    }
+
+    fn authenticate(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.authentication {
+            Authentication::None => request,
+            Authentication::Bearer(token) => request.bearer_auth(token.expose_secret()),
+            Authentication::Basic { username, password } => request.basic_auth(
+                username,
+                password.as_ref().map(|password| password.expose_secret()),
+            ),
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, PrometheusServiceError> {
+    std::fs::read(path).map_err(|err| {
+        PrometheusServiceError::Config(format!("Cannot read {}: {err}", path.display()))
+    })
+}
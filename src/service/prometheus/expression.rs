@@ -0,0 +1,81 @@
+use url::Url;
+
+/// Returns the expression of the alerting rule that fired an alert, from the
+/// generator URL Alertmanager reports for it. Prometheus links to its graph
+/// page, with the expression in the `g0.expr` query parameter.
+pub fn expression_from_generator_url(generator_url: &str) -> Option<String> {
+    let url = Url::parse(generator_url).ok()?;
+
+    url.query_pairs()
+        .find(|(key, _)| key == "g0.expr")
+        .map(|(_, expression)| expression.trim().to_owned())
+        .filter(|expression| !expression.is_empty())
+}
+
+/// Splits a comparison against a number off an expression, such as the
+/// `> 0.5` in `rate(errors[5m]) > 0.5`. Returns the expression to chart, and
+/// the number to draw as its threshold.
+///
+/// Alerting rules usually filter their series with such a comparison, which
+/// would leave nothing to chart but the points at which the alert fired.
+/// Expressions with more than one comparison, or comparisons using the `bool`
+/// modifier, are returned as they are.
+pub fn split_threshold(expression: &str) -> (&str, Option<f64>) {
+    let mut comparisons = top_level_comparisons(expression);
+    let (Some((start, end)), None) = (comparisons.next(), comparisons.next()) else {
+        return (expression, None);
+    };
+
+    let left = expression[..start].trim();
+    let right = expression[end..].trim();
+    if right.starts_with("bool") {
+        return (expression, None);
+    }
+
+    let parse = |operand: &str| operand.parse::<f64>().ok().filter(|n| n.is_finite());
+    match (parse(left), parse(right)) {
+        (None, Some(threshold)) => (left, Some(threshold)),
+        (Some(threshold), None) => (right, Some(threshold)),
+        _ => (expression, None),
+    }
+}
+
+/// Returns the byte ranges of the comparison operators in the expression that
+/// aren't nested in parentheses, label matchers, range selectors or strings.
+fn top_level_comparisons(expression: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let bytes = expression.as_bytes();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut index = 0;
+
+    std::iter::from_fn(move || {
+        while index < bytes.len() {
+            let byte = bytes[index];
+            let next = bytes.get(index + 1).copied();
+            index += 1;
+
+            if let Some(quote_byte) = quote {
+                if byte == b'\\' {
+                    index += 1;
+                } else if byte == quote_byte {
+                    quote = None;
+                }
+                continue;
+            }
+
+            match (byte, next) {
+                (b'"' | b'\'' | b'`', _) => quote = Some(byte),
+                (b'(' | b'{' | b'[', _) => depth += 1,
+                (b')' | b'}' | b']', _) => depth -= 1,
+                (b'=' | b'!' | b'>' | b'<', Some(b'=')) if depth == 0 => {
+                    index += 1;
+                    return Some((index - 2, index));
+                }
+                (b'>' | b'<', _) if depth == 0 => return Some((index - 1, index)),
+                _ => {}
+            }
+        }
+
+        None
+    })
+}
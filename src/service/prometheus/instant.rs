@@ -0,0 +1,61 @@
+use super::{client::PrometheusClient, types::*, PrometheusServiceError};
+use fiberplane::models::timestamps::Timestamp;
+use reqwest::header::CONTENT_TYPE;
+use tracing::debug;
+
+pub(crate) struct InstantQuery {
+    pub query: String,
+    pub time: Timestamp,
+}
+
+/// Evaluates a query that results in a single value at the given time.
+/// Returns `None` if the result is empty or not a number, such as when there
+/// was no traffic to compute a ratio from.
+///
+/// See: https://prometheus.io/docs/prometheus/latest/querying/api/#instant-queries
+pub(crate) async fn query_value(
+    query: InstantQuery,
+    client: &PrometheusClient,
+) -> Result<Option<f64>, PrometheusServiceError> {
+    let query_string = {
+        let mut form_data = form_urlencoded::Serializer::new(String::new());
+        form_data.append_pair("query", &query.query);
+        form_data.append_pair("time", &query.time.to_string());
+        form_data.finish()
+    };
+
+    let url = client.api_url(&["api", "v1", "query"])?;
+
+    let url_str = url.as_str();
+    debug!(?url_str, query = %query.query, "Querying prometheus query api");
+
+    let response = client
+        .post(url)
+        .body(query_string)
+        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .send()
+        .await
+        .map_err(|err| PrometheusServiceError::Http(err.to_string()))?;
+
+    let response: PrometheusResponse = response.json().await.map_err(|err| {
+        PrometheusServiceError::Deserialization(format!(
+            "Could not deserialize Prometheus response: {err}"
+        ))
+    })?;
+
+    value_from_data(response.data)
+}
+
+/// Returns the value of the first sample in an instant vector.
+pub(crate) fn value_from_data(data: PrometheusData) -> Result<Option<f64>, PrometheusServiceError> {
+    let PrometheusData::Vector(vector) = data else {
+        return Err(PrometheusServiceError::Deserialization(
+            "Expected a vector from the query api".to_owned(),
+        ));
+    };
+
+    match vector.first() {
+        Some(sample) => Ok(Some(sample.value()?).filter(|value| value.is_finite())),
+        None => Ok(None),
+    }
+}
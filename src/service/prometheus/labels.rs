@@ -0,0 +1,50 @@
+use super::{client::PrometheusClient, types::*, PrometheusServiceError};
+use fiberplane::models::timestamps::TimeRange;
+use tracing::debug;
+
+pub(crate) struct LabelValuesQuery<'a> {
+    /// Name of the label to return the values of.
+    pub label: &'a str,
+
+    /// Series selector that limits the series to look at.
+    pub selector: String,
+
+    pub time_range: TimeRange,
+}
+
+/// Returns the values of a label among the series matching the selector.
+///
+/// See: https://prometheus.io/docs/prometheus/latest/querying/api/#querying-label-values
+pub(crate) async fn query_label_values(
+    query: LabelValuesQuery<'_>,
+    client: &PrometheusClient,
+) -> Result<Vec<String>, PrometheusServiceError> {
+    let query_string = {
+        let mut form_data = form_urlencoded::Serializer::new(String::new());
+        form_data.append_pair("match[]", &query.selector);
+        form_data.append_pair("start", &query.time_range.from.to_string());
+        form_data.append_pair("end", &query.time_range.to.to_string());
+        form_data.finish()
+    };
+
+    let mut url = client.api_url(&["api", "v1", "label", query.label, "values"])?;
+
+    url.set_query(Some(&query_string));
+
+    let url_str = url.as_str();
+    debug!(?url_str, "Querying prometheus label values api");
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|err| PrometheusServiceError::Http(err.to_string()))?;
+
+    let response: PrometheusLabelValuesResponse = response.json().await.map_err(|err| {
+        PrometheusServiceError::Deserialization(format!(
+            "Could not deserialize Prometheus response: {err}"
+        ))
+    })?;
+
+    Ok(response.data)
+}
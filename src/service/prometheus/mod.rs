@@ -1,21 +1,106 @@
+mod budget;
+mod client;
 mod errors;
+mod expression;
+mod instant;
+mod labels;
 #[cfg(test)]
 mod tests;
 mod timeseries;
 mod types;
 
+pub use client::PrometheusHeader;
 pub use errors::PrometheusServiceError;
+pub use expression::{expression_from_generator_url, split_threshold};
 
+use crate::db::models::{BurnRate, ErrorBudget};
+use budget::{error_budget_for_slo, error_ratio_query, BURN_RATE_WINDOWS};
+use client::PrometheusClient;
 use fiberplane::models::providers::Timeseries;
-use fiberplane::models::timestamps::TimeRange;
+use fiberplane::models::timestamps::{TimeRange, Timestamp};
+use futures::future::try_join_all;
+use instant::{query_value, InstantQuery};
+use labels::{query_label_values, LabelValuesQuery};
+use secrecy::SecretString;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use timeseries::{query_series, TimeseriesQuery};
 use url::Url;
 
+/// Label autometrics adds to the metrics of functions with a latency
+/// objective, containing the latency threshold in seconds.
+const LATENCY_THRESHOLD_LABEL: &str = "objective_latency_threshold";
+
 #[derive(clap::Args, Debug)]
 pub struct PrometheusServiceConfig {
     /// Base URL on which Prometheus can be reached.
-    #[clap(long, env, default_value = "http://localhost:9090/prometheus")]
+    #[clap(
+        long,
+        env,
+        default_value = "http://localhost:9090/prometheus",
+        help_heading = "Prometheus"
+    )]
     pub prometheus_url: Url,
+
+    /// Length of the period over which the error budget of SLOs is computed,
+    /// in days.
+    #[clap(long, env, default_value = "30", help_heading = "Prometheus")]
+    pub slo_period_days: u32,
+
+    /// Bearer token to authenticate to Prometheus with.
+    #[clap(
+        long,
+        env,
+        conflicts_with = "prometheus_username",
+        help_heading = "Prometheus"
+    )]
+    pub prometheus_bearer_token: Option<SecretString>,
+
+    /// Username to authenticate to Prometheus with, using basic
+    /// authentication.
+    #[clap(long, env, help_heading = "Prometheus")]
+    pub prometheus_username: Option<String>,
+
+    /// Password to authenticate to Prometheus with, using basic
+    /// authentication.
+    #[clap(
+        long,
+        env,
+        requires = "prometheus_username",
+        help_heading = "Prometheus"
+    )]
+    pub prometheus_password: Option<SecretString>,
+
+    /// Header to send along with every request to Prometheus, formatted as
+    /// `Name: value`. For example, `X-Scope-OrgID: tenant-1` to select the
+    /// tenant of a Mimir or Cortex cluster. May be given more than once, or
+    /// as one header per line in the environment variable.
+    #[clap(long, env, value_delimiter = '\n', help_heading = "Prometheus")]
+    pub prometheus_headers: Vec<PrometheusHeader>,
+
+    /// PEM file with the certificate of the authority that signed the
+    /// certificate of Prometheus, if it isn't trusted by default.
+    #[clap(long, env, help_heading = "Prometheus")]
+    pub prometheus_ca_cert: Option<PathBuf>,
+
+    /// PEM file with the client certificate to present to Prometheus, for
+    /// mutual TLS.
+    #[clap(
+        long,
+        env,
+        requires = "prometheus_client_key",
+        help_heading = "Prometheus"
+    )]
+    pub prometheus_client_cert: Option<PathBuf>,
+
+    /// PEM file with the private key of the client certificate.
+    #[clap(
+        long,
+        env,
+        requires = "prometheus_client_cert",
+        help_heading = "Prometheus"
+    )]
+    pub prometheus_client_key: Option<PathBuf>,
 }
 
 #[cfg(test)]
@@ -23,17 +108,30 @@ impl PrometheusServiceConfig {
     pub fn new_test_config() -> Self {
         Self {
             prometheus_url: Url::parse("http://localhost:9090/prometheus").unwrap(),
+            slo_period_days: 30,
+            prometheus_bearer_token: None,
+            prometheus_username: None,
+            prometheus_password: None,
+            prometheus_headers: Vec::new(),
+            prometheus_ca_cert: None,
+            prometheus_client_cert: None,
+            prometheus_client_key: None,
         }
     }
 }
 
 pub struct PrometheusService {
     config: PrometheusServiceConfig,
+
+    /// Shared by all queries, so connections are reused.
+    client: PrometheusClient,
 }
 
 impl PrometheusService {
     pub fn new(config: PrometheusServiceConfig) -> Self {
-        Self { config }
+        let client = PrometheusClient::new(&config).expect("Error building Prometheus client");
+
+        Self { config, client }
     }
 
     pub async fn query_slo_timeseries(
@@ -46,7 +144,158 @@ impl PrometheusService {
 
         let timeseries_query = TimeseriesQuery { query, time_range };
 
-        query_series(timeseries_query, &self.config).await
+        query_series(timeseries_query, &self.client).await
+    }
+
+    /// Returns the series of an arbitrary expression, such as the expression
+    /// of the alerting rule that fired an alert.
+    pub async fn query_expression_timeseries(
+        &self,
+        expression: &str,
+        time_range: TimeRange,
+    ) -> Result<Vec<Timeseries>, PrometheusServiceError> {
+        let timeseries_query = TimeseriesQuery {
+            query: expression.to_owned(),
+            time_range,
+        };
+
+        query_series(timeseries_query, &self.client).await
+    }
+
+    /// Returns the threshold of the given SLO, in the unit of its chart: the
+    /// latency threshold in seconds for latency SLOs, or the target
+    /// percentile as a ratio for success-rate SLOs.
+    ///
+    /// The threshold is taken from the alert labels if possible. Otherwise,
+    /// the latency threshold is looked up in the metadata autometrics adds to
+    /// the metrics of the objective.
+    pub async fn query_slo_threshold(
+        &self,
+        slo: &str,
+        objective_name: &str,
+        labels: &BTreeMap<String, String>,
+        time_range: TimeRange,
+    ) -> Result<Option<f64>, PrometheusServiceError> {
+        if let Some(threshold) = slo_threshold_from_labels(slo, labels)? {
+            return Ok(Some(threshold));
+        }
+
+        let Some(percentile) = slo.strip_prefix("latency-") else {
+            return Ok(None);
+        };
+        let query = LabelValuesQuery {
+            label: LATENCY_THRESHOLD_LABEL,
+            selector: format!(
+                r#"{{__name__=~"function_calls_duration(_seconds)?_bucket",objective_name="{objective_name}",objective_percentile="{percentile}"}}"#
+            ),
+            time_range,
+        };
+
+        let thresholds = query_label_values(query, &self.client).await?;
+
+        // There should be only one threshold per objective, but if it was
+        // changed, the strictest one is the safest to show.
+        Ok(thresholds
+            .iter()
+            .filter_map(|threshold| threshold.parse::<f64>().ok())
+            .min_by(f64::total_cmp))
+    }
+
+    /// Returns the period over which the error budget of SLOs is computed.
+    pub fn slo_period(&self) -> time::Duration {
+        time::Duration::days(self.config.slo_period_days.into())
+    }
+
+    /// Returns the remaining error budget of the given SLO at the given time,
+    /// and the rates at which it was burning over several windows.
+    ///
+    /// The threshold is the one returned by [`Self::query_slo_threshold`].
+    /// Without it, the budget of latency SLOs can't be determined and `None`
+    /// is returned.
+    pub async fn query_error_budget(
+        &self,
+        slo: &str,
+        objective_name: &str,
+        threshold: Option<f64>,
+        time: Timestamp,
+    ) -> Result<Option<ErrorBudget>, PrometheusServiceError> {
+        let budget = error_budget_for_slo(slo)?;
+        let period = format!("{}d", self.config.slo_period_days);
+
+        // The ratio over the whole period comes last.
+        let mut queries = Vec::new();
+        for window in BURN_RATE_WINDOWS.iter().copied().chain([period.as_str()]) {
+            let Some(query) = error_ratio_query(slo, objective_name, threshold, window)? else {
+                return Ok(None);
+            };
+            queries.push(query_value(InstantQuery { query, time }, &self.client));
+        }
+        let mut error_ratios = try_join_all(queries).await?;
+
+        let remaining = error_ratios
+            .pop()
+            .flatten()
+            .map(|error_ratio| 1.0 - error_ratio / budget);
+        let burn_rates = BURN_RATE_WINDOWS
+            .iter()
+            .zip(error_ratios)
+            .map(|(window, error_ratio)| BurnRate {
+                window: (*window).to_owned(),
+                rate: error_ratio.map(|error_ratio| error_ratio / budget),
+            })
+            .collect();
+
+        Ok(Some(ErrorBudget {
+            period_days: self.config.slo_period_days,
+            remaining,
+            burn_rates,
+        }))
+    }
+
+    /// Returns the series of the remaining error budget of the given SLO over
+    /// time, where every point covers the SLO period up to that point.
+    ///
+    /// Like [`Self::query_error_budget`], nothing is returned for latency SLOs
+    /// of which the threshold isn't known.
+    pub async fn query_error_budget_timeseries(
+        &self,
+        slo: &str,
+        objective_name: &str,
+        threshold: Option<f64>,
+        time_range: TimeRange,
+    ) -> Result<Vec<Timeseries>, PrometheusServiceError> {
+        let budget = error_budget_for_slo(slo)?;
+        let period = format!("{}d", self.config.slo_period_days);
+        let Some(error_ratio) = error_ratio_query(slo, objective_name, threshold, &period)? else {
+            return Ok(Vec::new());
+        };
+
+        let timeseries_query = TimeseriesQuery {
+            query: format!("1 - ({error_ratio}) / {budget}"),
+            time_range,
+        };
+
+        query_series(timeseries_query, &self.client).await
+    }
+}
+
+/// Returns the threshold of the given SLO, if it can be determined from the
+/// SLO name and the labels of the alert.
+fn slo_threshold_from_labels(
+    slo: &str,
+    labels: &BTreeMap<String, String>,
+) -> Result<Option<f64>, PrometheusServiceError> {
+    if let Some(percentile) = slo.strip_prefix("success-rate-") {
+        let percentile: f64 = percentile
+            .parse()
+            .map_err(|_| PrometheusServiceError::InvalidPercentile(percentile.to_owned()))?;
+        Ok(Some(percentile / 100.0))
+    } else if slo.starts_with("latency-") {
+        Ok(labels
+            .get(LATENCY_THRESHOLD_LABEL)
+            .and_then(|threshold| threshold.parse().ok()))
+    } else {
+        Err(PrometheusServiceError::UnknownSlo(slo.to_owned()))
     }
 }
 
@@ -1,5 +1,25 @@
-use crate::service::prometheus::types::PrometheusResponse;
-use serde_json::from_str;
+use crate::service::prometheus::budget::{error_budget_for_slo, error_ratio_query};
+use crate::service::prometheus::client::{PrometheusClient, PrometheusHeader};
+use crate::service::prometheus::expression::{expression_from_generator_url, split_threshold};
+use crate::service::prometheus::instant::value_from_data;
+use crate::service::prometheus::labels::{query_label_values, LabelValuesQuery};
+use crate::service::prometheus::types::{PrometheusLabelValuesResponse, PrometheusResponse};
+use crate::service::prometheus::{
+    slo_threshold_from_labels, PrometheusServiceConfig, PrometheusServiceError,
+};
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::routing::get;
+use axum::{Json, Router, Server};
+use clap::Parser;
+use fiberplane::models::timestamps::{TimeRange, Timestamp};
+use secrecy::SecretString;
+use serde_json::{from_str, json, Value};
+use std::collections::BTreeMap;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+use url::Url;
 
 #[test]
 fn test_decode_prometheus_response() {
@@ -37,3 +57,299 @@ fn test_decode_prometheus_response() {
         }
     }
 }
+
+#[test]
+fn test_decode_prometheus_label_values_response() {
+    let mock_response = r#"{"status": "success", "data": ["0.25", "0.5"]}"#;
+
+    let response: PrometheusLabelValuesResponse = from_str(mock_response).unwrap();
+
+    assert_eq!(response.data, vec!["0.25", "0.5"]);
+}
+
+#[test]
+fn test_slo_threshold_from_labels() {
+    let no_labels = BTreeMap::new();
+    let labels = BTreeMap::from([("objective_latency_threshold".to_owned(), "0.25".to_owned())]);
+
+    assert_eq!(
+        slo_threshold_from_labels("success-rate-99", &no_labels).unwrap(),
+        Some(0.99)
+    );
+    assert_eq!(
+        slo_threshold_from_labels("latency-99", &labels).unwrap(),
+        Some(0.25)
+    );
+    assert_eq!(
+        slo_threshold_from_labels("latency-99", &no_labels).unwrap(),
+        None
+    );
+    assert_matches!(
+        slo_threshold_from_labels("success-rate-high", &no_labels),
+        Err(PrometheusServiceError::InvalidPercentile(_))
+    );
+    assert_matches!(
+        slo_threshold_from_labels("availability", &no_labels),
+        Err(PrometheusServiceError::UnknownSlo(_))
+    );
+}
+
+#[test]
+fn test_decode_prometheus_instant_response() {
+    let value = |mock_response: &str| {
+        let response: PrometheusResponse = from_str(mock_response).unwrap();
+        value_from_data(response.data).unwrap()
+    };
+
+    assert_eq!(
+        value(
+            r#"{"data": {"resultType": "vector", "result": [{"metric": {}, "value": [1635171094.561, "0.25"]}]}}"#
+        ),
+        Some(0.25)
+    );
+    assert_eq!(
+        value(
+            r#"{"data": {"resultType": "vector", "result": [{"metric": {}, "value": [1635171094.561, "NaN"]}]}}"#
+        ),
+        None
+    );
+    assert_eq!(
+        value(r#"{"data": {"resultType": "vector", "result": []}}"#),
+        None
+    );
+}
+
+#[test]
+fn test_error_budget_for_slo() {
+    assert!((error_budget_for_slo("success-rate-99").unwrap() - 0.01).abs() < 1e-9);
+    assert!((error_budget_for_slo("latency-99.9").unwrap() - 0.001).abs() < 1e-9);
+    assert_matches!(
+        error_budget_for_slo("latency-high"),
+        Err(PrometheusServiceError::InvalidPercentile(_))
+    );
+    assert_matches!(
+        error_budget_for_slo("availability"),
+        Err(PrometheusServiceError::UnknownSlo(_))
+    );
+}
+
+#[test]
+fn test_error_ratio_query() {
+    let success_rate_query = error_ratio_query("success-rate-99", "api", Some(0.99), "5m")
+        .unwrap()
+        .unwrap();
+    assert!(success_rate_query.contains(r#"result="ok""#));
+    assert!(success_rate_query.contains(r#"objective_percentile="99""#));
+    assert!(success_rate_query.contains("[5m]"));
+
+    let latency_query = error_ratio_query("latency-99", "api", Some(0.25), "3d")
+        .unwrap()
+        .unwrap();
+    assert!(latency_query.contains(r#"le=~"0\\.250*""#));
+    assert!(latency_query.contains("[3d]"));
+
+    let whole_second_query = error_ratio_query("latency-99", "api", Some(1.0), "5m")
+        .unwrap()
+        .unwrap();
+    assert!(whole_second_query.contains(r#"le=~"1(\\.0*)?""#));
+
+    assert_eq!(
+        error_ratio_query("latency-99", "api", None, "5m").unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_expression_from_generator_url() {
+    assert_eq!(
+        expression_from_generator_url(
+            "http://prometheus:9090/graph?g0.expr=up%7Bjob%3D%22api%22%7D+%3D%3D+0&g0.tab=1"
+        ),
+        Some(r#"up{job="api"} == 0"#.to_owned())
+    );
+    assert_eq!(
+        expression_from_generator_url("http://prometheus:9090/graph?g0.tab=1"),
+        None
+    );
+    assert_eq!(
+        expression_from_generator_url("http://prometheus:9090/graph?g0.expr=+"),
+        None
+    );
+    assert_eq!(expression_from_generator_url("not a url"), None);
+}
+
+#[test]
+fn test_split_threshold() {
+    let cases = [
+        (r#"up{job="api"} == 0"#, (r#"up{job="api"}"#, Some(0.0))),
+        (
+            "rate(http_errors_total[5m]) > 0.5",
+            ("rate(http_errors_total[5m])", Some(0.5)),
+        ),
+        (
+            "(node_filesystem_avail_bytes / node_filesystem_size_bytes) * 100 <= 10",
+            (
+                "(node_filesystem_avail_bytes / node_filesystem_size_bytes) * 100",
+                Some(10.0),
+            ),
+        ),
+        ("10 > queue_length", ("queue_length", Some(10.0))),
+        (
+            r#"sum(rate(requests{path=~"/api/.+",code!="200"}[5m])) >= 1e3"#,
+            (
+                r#"sum(rate(requests{path=~"/api/.+",code!="200"}[5m]))"#,
+                Some(1000.0),
+            ),
+        ),
+        (
+            r#"probe_success{target="a>b"}"#,
+            (r#"probe_success{target="a>b"}"#, None),
+        ),
+        ("queue_length > bool 10", ("queue_length > bool 10", None)),
+        ("errors > warnings", ("errors > warnings", None)),
+        (
+            "errors > 5 and errors < 10",
+            ("errors > 5 and errors < 10", None),
+        ),
+    ];
+
+    for (expression, expected) in cases {
+        assert_eq!(split_threshold(expression), expected, "{expression}");
+    }
+}
+
+#[test]
+fn test_parse_prometheus_header() {
+    let header: PrometheusHeader = "X-Scope-OrgID: tenant-1".parse().unwrap();
+    assert!(!format!("{header:?}").contains("tenant-1"));
+
+    assert!("X-Scope-OrgID".parse::<PrometheusHeader>().is_err());
+    assert!("Invalid Name: value".parse::<PrometheusHeader>().is_err());
+    assert!("X-Scope-OrgID: tenant\n1"
+        .parse::<PrometheusHeader>()
+        .is_err());
+}
+
+type RecordedHeaders = Arc<Mutex<Vec<HeaderMap>>>;
+
+/// Starts a stand-in for the Prometheus label values API, which records the
+/// headers of the requests it receives.
+fn start_prometheus_stand_in() -> (Url, RecordedHeaders) {
+    async fn label_values(
+        State(recorded_headers): State<RecordedHeaders>,
+        headers: HeaderMap,
+    ) -> Json<Value> {
+        recorded_headers.lock().unwrap().push(headers);
+        Json(json!({"status": "success", "data": ["0.25"]}))
+    }
+
+    let recorded_headers = RecordedHeaders::default();
+    let router = Router::new()
+        .route("/prometheus/api/v1/label/:label/values", get(label_values))
+        .with_state(recorded_headers.clone());
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = Url::parse(&format!(
+        "http://{}/prometheus",
+        listener.local_addr().unwrap()
+    ))
+    .unwrap();
+    tokio::spawn(
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service()),
+    );
+
+    (url, recorded_headers)
+}
+
+fn label_values_query() -> LabelValuesQuery<'static> {
+    let now = OffsetDateTime::now_utc();
+    LabelValuesQuery {
+        label: "objective_latency_threshold",
+        selector: "function_calls_duration_bucket".to_owned(),
+        time_range: TimeRange {
+            from: Timestamp::from(now - time::Duration::hours(1)),
+            to: Timestamp::from(now),
+        },
+    }
+}
+
+#[tokio::test]
+async fn test_client_authentication() {
+    let (prometheus_url, recorded_headers) = start_prometheus_stand_in();
+
+    let bearer_client = PrometheusClient::new(&PrometheusServiceConfig {
+        prometheus_url: prometheus_url.clone(),
+        prometheus_bearer_token: Some(SecretString::new("secret-token".to_owned())),
+        prometheus_headers: vec!["X-Scope-OrgID: tenant-1".parse().unwrap()],
+        ..PrometheusServiceConfig::new_test_config()
+    })
+    .unwrap();
+    let basic_client = PrometheusClient::new(&PrometheusServiceConfig {
+        prometheus_url,
+        prometheus_username: Some("prometheus".to_owned()),
+        prometheus_password: Some(SecretString::new("password".to_owned())),
+        ..PrometheusServiceConfig::new_test_config()
+    })
+    .unwrap();
+
+    for client in [&bearer_client, &basic_client] {
+        let values = query_label_values(label_values_query(), client)
+            .await
+            .unwrap();
+        assert_eq!(values, vec!["0.25"]);
+    }
+
+    let recorded_headers = recorded_headers.lock().unwrap();
+    assert_eq!(recorded_headers[0]["authorization"], "Bearer secret-token");
+    assert_eq!(recorded_headers[0]["x-scope-orgid"], "tenant-1");
+    // "prometheus:password", base64-encoded.
+    assert_eq!(
+        recorded_headers[1]["authorization"],
+        "Basic cHJvbWV0aGV1czpwYXNzd29yZA=="
+    );
+    assert!(!recorded_headers[1].contains_key("x-scope-orgid"));
+}
+
+#[tokio::test]
+async fn test_headers_may_contain_commas() {
+    #[derive(Parser)]
+    struct Args {
+        #[clap(flatten)]
+        config: PrometheusServiceConfig,
+    }
+
+    let (prometheus_url, recorded_headers) = start_prometheus_stand_in();
+    let args = Args::try_parse_from([
+        "slack-app",
+        "--prometheus-url",
+        prometheus_url.as_str(),
+        "--prometheus-headers",
+        "X-Scope-OrgID: tenant-1\nX-Tags: api, web",
+        "--prometheus-headers",
+        "X-Team: sre",
+    ])
+    .unwrap();
+
+    let client = PrometheusClient::new(&args.config).unwrap();
+    query_label_values(label_values_query(), &client)
+        .await
+        .unwrap();
+
+    let recorded_headers = recorded_headers.lock().unwrap();
+    assert_eq!(args.config.prometheus_headers.len(), 3);
+    assert_eq!(recorded_headers[0]["x-scope-orgid"], "tenant-1");
+    assert_eq!(recorded_headers[0]["x-tags"], "api, web");
+    assert_eq!(recorded_headers[0]["x-team"], "sre");
+}
+
+#[test]
+fn test_client_with_missing_certificate() {
+    let result = PrometheusClient::new(&PrometheusServiceConfig {
+        prometheus_ca_cert: Some("/nonexistent/ca.pem".into()),
+        ..PrometheusServiceConfig::new_test_config()
+    });
+
+    assert_matches!(result.err(), Some(PrometheusServiceError::Config(_)));
+}
@@ -1,9 +1,7 @@
-use super::{types::*, PrometheusServiceConfig, PrometheusServiceError};
+use super::{client::PrometheusClient, types::*, PrometheusServiceError};
 use fiberplane::models::providers::Timeseries;
 use fiberplane::models::timestamps::{TimeRange, Timestamp};
 use reqwest::header::CONTENT_TYPE;
-use reqwest::Client;
-use std::time::Duration;
 use tracing::debug;
 
 pub(crate) struct TimeseriesQuery {
@@ -13,7 +11,7 @@ pub(crate) struct TimeseriesQuery {
 
 pub(crate) async fn query_series(
     query: TimeseriesQuery,
-    config: &PrometheusServiceConfig,
+    client: &PrometheusClient,
 ) -> Result<Vec<Timeseries>, PrometheusServiceError> {
     let from = to_float(query.time_range.from);
     let to = to_float(query.time_range.to);
@@ -28,20 +26,7 @@ pub(crate) async fn query_series(
         form_data.finish()
     };
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(15))
-        .build()
-        .expect("Error building reqwest client");
-
-    let mut url = config.prometheus_url.clone();
-    url.path_segments_mut()
-        .map_err(|_| {
-            PrometheusServiceError::Config(format!(
-                "Cannot append to prometheus base URL: {}",
-                config.prometheus_url
-            ))
-        })?
-        .extend(&["api", "v1", "query_range"]);
+    let mut url = client.api_url(&["api", "v1", "query_range"])?;
 
     url.set_query(Some(&query_string));
 
@@ -62,7 +47,11 @@ pub(crate) async fn query_series(
         ))
     })?;
 
-    let PrometheusData::Matrix(matrix) = response.data;
+    let PrometheusData::Matrix(matrix) = response.data else {
+        return Err(PrometheusServiceError::Deserialization(
+            "Expected a matrix from the query_range api".to_owned(),
+        ));
+    };
 
     matrix.into_iter().map(RangeVector::into_series).collect()
 }
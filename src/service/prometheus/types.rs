@@ -16,6 +16,7 @@ pub struct PrometheusResponse {
 #[serde(tag = "resultType", content = "result", rename_all = "snake_case")]
 pub enum PrometheusData {
     Matrix(Vec<RangeVector>),
+    Vector(Vec<InstantVector>),
 }
 
 #[derive(Deserialize)]
@@ -29,6 +30,12 @@ pub struct PrometheusMetadataResponse {
     pub data: BTreeMap<String, Vec<Metadata>>,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrometheusLabelValuesResponse {
+    pub data: Vec<String>,
+}
+
 #[derive(Deserialize)]
 pub struct PrometheusPoint(f64, String);
 
@@ -43,6 +50,17 @@ impl PrometheusPoint {
     }
 }
 
+#[derive(Deserialize)]
+pub struct InstantVector {
+    pub value: PrometheusPoint,
+}
+
+impl InstantVector {
+    pub fn value(&self) -> Result<f64, ParseFloatError> {
+        self.value.1.parse()
+    }
+}
+
 #[derive(Deserialize)]
 pub struct RangeVector {
     pub metric: BTreeMap<String, String>,
@@ -0,0 +1,26 @@
+use crate::db::DbError;
+use crate::service::ChartServiceError;
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error, Serialize)]
+#[serde(tag = "error", content = "details", rename_all = "snake_case")]
+pub enum RetentionError {
+    #[error("Chart error: {0}")]
+    ChartError(ChartServiceError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(DbError),
+}
+
+impl From<ChartServiceError> for RetentionError {
+    fn from(error: ChartServiceError) -> Self {
+        Self::ChartError(error)
+    }
+}
+
+impl From<DbError> for RetentionError {
+    fn from(error: DbError) -> Self {
+        Self::DatabaseError(error)
+    }
+}
@@ -0,0 +1,219 @@
+mod errors;
+#[cfg(test)]
+mod tests;
+
+use super::Service;
+use crate::db::models::Alert;
+use autometrics::autometrics;
+use errors::RetentionError;
+use once_cell::sync::Lazy;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use std::time::Duration;
+use time::ext::NumericalDuration;
+use time::OffsetDateTime;
+use tracing::{error, info, instrument, warn};
+
+/// Number of alerts that are fetched from the DB at a time while pruning.
+const PRUNE_BATCH_SIZE: i64 = 100;
+
+/// Longest retention period, in days.
+const MAX_RETENTION_DAYS: u32 = 100 * 365;
+
+/// Number of things deleted while pruning, labelled by what was deleted. This
+/// is exported along with the Autometrics metrics, from the same registry.
+static PRUNED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "retention_pruned_total",
+        "Number of alerts, chart files and Slack messages deleted by pruning",
+        &["kind"]
+    )
+    .expect("Unable to register retention metrics")
+});
+
+#[derive(clap::Args, Debug)]
+pub struct RetentionConfig {
+    /// Number of days resolved alerts are kept. Older alerts are deleted,
+    /// together with their history and charts.
+    ///
+    /// Alerts are kept forever if this is not set. At most 100 years.
+    #[clap(
+        long,
+        env,
+        value_parser = clap::value_parser!(u32).range(1..=i64::from(MAX_RETENTION_DAYS)),
+        help_heading = "Retention"
+    )]
+    retention_days: Option<u32>,
+
+    /// Interval at which expired alerts are pruned, in seconds.
+    #[clap(long, env, default_value = "3600", help_heading = "Retention")]
+    retention_interval_secs: u64,
+
+    /// Also delete the Slack messages of the alerts that are pruned.
+    #[clap(long, env, help_heading = "Retention")]
+    retention_delete_slack_messages: bool,
+}
+
+#[cfg(test)]
+impl RetentionConfig {
+    pub fn new_test_config() -> Self {
+        Self {
+            retention_days: Some(30),
+            retention_interval_secs: 3600,
+            retention_delete_slack_messages: false,
+        }
+    }
+}
+
+/// What was deleted by a single pruning run.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct PruneSummary {
+    pub alerts: usize,
+    pub chart_files: usize,
+    pub slack_messages: usize,
+}
+
+impl PruneSummary {
+    /// Adds the deleted alerts, chart files and Slack messages to the
+    /// exported metrics.
+    fn record_metrics(&self) {
+        for (kind, count) in [
+            ("alert", self.alerts),
+            ("chart_file", self.chart_files),
+            ("slack_message", self.slack_messages),
+        ] {
+            PRUNED
+                .with_label_values(&[kind])
+                .inc_by(u64::try_from(count).unwrap_or(u64::MAX));
+        }
+    }
+}
+
+/// Prunes expired alerts at the configured interval, until the task is
+/// aborted. Does nothing if no retention period is configured.
+pub async fn prune_periodically(service: Service, config: RetentionConfig) {
+    let Some(retention_days) = config.retention_days else {
+        info!("No retention period configured, alerts are kept forever");
+        return;
+    };
+
+    info!(retention_days, "Pruning resolved alerts periodically");
+
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(config.retention_interval_secs.max(1)));
+    loop {
+        interval.tick().await;
+
+        let now = OffsetDateTime::now_utc();
+        match prune_expired_alerts(&service, &config, now).await {
+            Ok(summary) => info!(?summary, "Pruned expired alerts"),
+            Err(err) => error!(%err, "Unable to prune expired alerts"),
+        }
+    }
+}
+
+/// Deletes all resolved alerts that haven't been updated within the
+/// retention period, together with their chart files and, if configured,
+/// their Slack messages.
+#[instrument(err, skip(service, config))]
+pub async fn prune_expired_alerts(
+    service: &Service,
+    config: &RetentionConfig,
+    now: OffsetDateTime,
+) -> Result<PruneSummary, RetentionError> {
+    let mut summary = PruneSummary::default();
+    let Some(retention_days) = config.retention_days else {
+        return Ok(summary);
+    };
+
+    let retention_period = i64::from(retention_days.min(MAX_RETENTION_DAYS)).days();
+    let Some(cutoff) = now.checked_sub(retention_period) else {
+        return Ok(summary);
+    };
+
+    loop {
+        let mut tx = service.db.start_transaction().await?;
+        let alerts = service
+            .db
+            .alert_list_resolved_before(&mut tx, cutoff, PRUNE_BATCH_SIZE)
+            .await?;
+        tx.commit().await?;
+
+        if alerts.is_empty() {
+            return Ok(summary);
+        }
+
+        for alert in alerts {
+            let pruned = prune_alert(service, config, &alert).await?;
+            pruned.record_metrics();
+            summary.alerts += pruned.alerts;
+            summary.chart_files += pruned.chart_files;
+            summary.slack_messages += pruned.slack_messages;
+
+            // The alert is kept if some of its Slack messages couldn't be
+            // deleted, and is tried again in the next run.
+            if pruned.alerts == 0 {
+                return Ok(summary);
+            }
+        }
+    }
+}
+
+/// Deletes a single alert. The returned summary counts the alert itself, along
+/// with its chart files and Slack messages.
+///
+/// If a Slack message can't be deleted for now, the alert is kept, so it
+/// still refers to the messages that are left. Only the messages that were
+/// deleted are counted then.
+#[autometrics]
+async fn prune_alert(
+    service: &Service,
+    config: &RetentionConfig,
+    alert: &Alert,
+) -> Result<PruneSummary, RetentionError> {
+    let mut pruned = PruneSummary::default();
+
+    if config.retention_delete_slack_messages {
+        let mut tx = service.db.start_transaction().await?;
+        let messages = service
+            .db
+            .slack_message_list_by_alert(&mut tx, alert.id)
+            .await?;
+        tx.commit().await?;
+
+        for message in &messages {
+            match service.slack.delete_message(message).await {
+                Ok(()) => pruned.slack_messages += 1,
+                // Messages may have been deleted manually already, or can't
+                // be deleted anymore, which shouldn't prevent the alert from
+                // being pruned.
+                Err(err) if err.is_permanent() => {
+                    warn!(%err, alert_id = alert.id, "Unable to delete Slack message")
+                }
+                Err(err) => {
+                    warn!(%err, alert_id = alert.id, "Unable to delete Slack message, will retry");
+                    return Ok(pruned);
+                }
+            }
+
+            // Forget the message right away, so it isn't deleted again when
+            // the alert is retried.
+            let mut tx = service.db.start_transaction().await?;
+            service.db.slack_message_delete(&mut tx, message.id).await?;
+            tx.commit().await?;
+        }
+    }
+
+    let chart_filenames = [&alert.chart_filename, &alert.error_budget_chart_filename];
+    for chart_filename in chart_filenames.into_iter().flatten() {
+        if service.charts.delete_chart(chart_filename).await? {
+            pruned.chart_files += 1;
+        }
+    }
+
+    let mut tx = service.db.start_transaction().await?;
+    service.db.alert_delete(&mut tx, alert.id).await?;
+    tx.commit().await?;
+    pruned.alerts = 1;
+
+    Ok(pruned)
+}
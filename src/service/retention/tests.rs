@@ -0,0 +1,155 @@
+use super::{prune_expired_alerts, PruneSummary, RetentionConfig};
+use crate::db::models::{Alert, NewAlert};
+use crate::db::{Db, DbError};
+use crate::testutil::*;
+use std::path::PathBuf;
+use time::ext::NumericalDuration;
+use time::OffsetDateTime;
+
+async fn create_alert(db: &Db, resolved: bool, chart_filename: Option<String>) -> Alert {
+    let mut tx = db.start_transaction().await.unwrap();
+    let alert = db
+        .alert_create(
+            &mut tx,
+            NewAlert {
+                text: "Something is wrong".to_owned(),
+                resolved,
+                fingerprint: Some(format!("{:016x}", rand::random::<u64>())),
+                notebook_id: None,
+                chart_filename,
+                slack_channel: None,
+                slack_ts: None,
+                sloth_slo: None,
+                sloth_service: None,
+                objective_name: None,
+                severity: None,
+                labels: Default::default(),
+                annotations: Default::default(),
+                generator_url: None,
+                starts_at: None,
+                ends_at: None,
+            },
+        )
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    alert
+}
+
+#[tokio::test]
+async fn prune_deletes_expired_resolved_alerts() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let chart_filename = format!("retention-test-{:016x}.png", rand::random::<u64>());
+            let chart_path = PathBuf::from("/tmp").join(&chart_filename);
+            tokio::fs::write(&chart_path, b"chart").await.unwrap();
+
+            let resolved_alert = create_alert(&db, true, Some(chart_filename)).await;
+            let firing_alert = create_alert(&db, false, None).await;
+
+            let mut tx = db.start_transaction().await.unwrap();
+            db.slack_message_create(&mut tx, resolved_alert.id, "alerts", "C123", "1234.5678")
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            let config = RetentionConfig::new_test_config();
+
+            // act
+            let too_early_summary =
+                prune_expired_alerts(&service, &config, OffsetDateTime::now_utc() + 29.days())
+                    .await
+                    .expect("Error pruning alerts");
+            let summary =
+                prune_expired_alerts(&service, &config, OffsetDateTime::now_utc() + 31.days())
+                    .await
+                    .expect("Error pruning alerts");
+
+            // assert
+            assert_eq!(too_early_summary, PruneSummary::default());
+            assert_eq!(
+                summary,
+                PruneSummary {
+                    alerts: 1,
+                    chart_files: 1,
+                    slack_messages: 0,
+                }
+            );
+            assert!(!chart_path.exists());
+
+            let mut tx = db.start_transaction().await.unwrap();
+            assert_matches!(
+                db.alert_get(&mut tx, resolved_alert.id).await,
+                Err(DbError::NotFound)
+            );
+            assert_matches!(db.alert_get(&mut tx, firing_alert.id).await, Ok(_));
+
+            let messages = db
+                .slack_message_list_by_alert(&mut tx, resolved_alert.id)
+                .await
+                .unwrap();
+            assert!(messages.is_empty());
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn prune_does_nothing_without_retention_period() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let alert = create_alert(&db, true, None).await;
+            let config = RetentionConfig {
+                retention_days: None,
+                ..RetentionConfig::new_test_config()
+            };
+
+            // act
+            let summary =
+                prune_expired_alerts(&service, &config, OffsetDateTime::now_utc() + 365.days())
+                    .await
+                    .expect("Error pruning alerts");
+
+            // assert
+            assert_eq!(summary, PruneSummary::default());
+
+            let mut tx = db.start_transaction().await.unwrap();
+            assert_matches!(db.alert_get(&mut tx, alert.id).await, Ok(_));
+        },
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn prune_keeps_alerts_for_long_retention_periods() {
+    run_test(
+        service_setup,
+        service_cleanup,
+        |ServiceContext { db, service }| async move {
+            // arrange
+            let alert = create_alert(&db, true, None).await;
+            let config = RetentionConfig {
+                retention_days: Some(u32::MAX),
+                ..RetentionConfig::new_test_config()
+            };
+
+            // act
+            let summary = prune_expired_alerts(&service, &config, OffsetDateTime::now_utc())
+                .await
+                .expect("Error pruning alerts");
+
+            // assert
+            assert_eq!(summary, PruneSummary::default());
+
+            let mut tx = db.start_transaction().await.unwrap();
+            assert_matches!(db.alert_get(&mut tx, alert.id).await, Ok(_));
+        },
+    )
+    .await;
+}
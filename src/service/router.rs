@@ -1,6 +1,9 @@
+use super::admin::handlers::{dead_letter_event_replay, dead_letter_events_list};
 use super::alertmanager::handlers::receive_alertmanager_webhook;
-use super::charts::handlers::charts_get;
+use super::alerts::handlers::{alert_get, alerts_list};
+use super::charts::handlers::{charts_get, error_budget_charts_get};
 use super::metrics::metrics_get;
+use super::slack::handlers::{receive_slack_command, receive_slack_interaction};
 use super::GlobalState;
 use crate::service::Service;
 use axum::routing::{get, post};
@@ -13,8 +16,26 @@ pub fn create_router(service: Service) -> Router<()> {
         .route("/", get(|| async { "No slackin'!" }))
         .route("/healthz", get(|| async { "healthy" }))
         .route("/metrics", get(metrics_get))
-        .route("/api/alerts", post(receive_alertmanager_webhook))
-        .route("/api/chart/:alert_id", get(charts_get));
+        .route(
+            "/api/alerts",
+            get(alerts_list).post(receive_alertmanager_webhook),
+        )
+        .route("/api/alerts/:alert_id", get(alert_get))
+        .route("/api/chart/:alert_id", get(charts_get))
+        .route(
+            "/api/chart/:alert_id/error-budget",
+            get(error_budget_charts_get),
+        )
+        .route("/api/slack/interactions", post(receive_slack_interaction))
+        .route("/api/slack/commands", post(receive_slack_command))
+        .route(
+            "/api/admin/dead-letter-events",
+            get(dead_letter_events_list),
+        )
+        .route(
+            "/api/admin/dead-letter-events/:dead_letter_event_id/replay",
+            post(dead_letter_event_replay),
+        );
 
     let state = GlobalState { db, service };
 
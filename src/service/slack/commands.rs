@@ -0,0 +1,186 @@
+use super::{alert_color, build_summary_fields, SlackRequestHandlerError};
+use crate::db::models::Alert;
+use serde::Serialize;
+use slack_morphism::prelude::*;
+use time::Duration;
+
+/// Payload Slack sends to the request URL of a slash command.
+///
+/// Only the fields we need are included.
+///
+/// See: https://api.slack.com/interactivity/slash-commands#app_command_handling
+#[derive(Debug, Default)]
+pub struct SlackCommandPayload {
+    /// Everything that was typed after the command.
+    pub text: String,
+
+    /// ID of the Slack user who invoked the command.
+    pub user_id: String,
+
+    /// Username of the Slack user who invoked the command.
+    pub user_name: String,
+}
+
+impl SlackCommandPayload {
+    /// Parses the payload from the form-encoded body of a command request.
+    pub fn from_form_body(body: &[u8]) -> Result<Self, SlackRequestHandlerError> {
+        let mut payload = Self::default();
+        for (key, value) in form_urlencoded::parse(body) {
+            match key.as_ref() {
+                "text" => payload.text = value.into_owned(),
+                "user_id" => payload.user_id = value.into_owned(),
+                "user_name" => payload.user_name = value.into_owned(),
+                _ => {}
+            }
+        }
+
+        if payload.user_id.is_empty() {
+            return Err(SlackRequestHandlerError::InvalidPayload(
+                "missing user_id".to_owned(),
+            ));
+        }
+        if payload.user_name.is_empty() {
+            payload.user_name = payload.user_id.clone();
+        }
+
+        Ok(payload)
+    }
+}
+
+/// Subcommands of the `/alerts` command.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AlertsCommand {
+    /// Lists all open alerts.
+    Open,
+
+    /// Lists the open alerts for the given Sloth service.
+    Service(String),
+
+    /// Shows the alert with the given ID.
+    Show(i64),
+
+    /// Silences the alert with the given ID for the given duration.
+    Silence { alert_id: i64, duration: Duration },
+
+    /// Shows how to use the command.
+    Help,
+}
+
+impl AlertsCommand {
+    pub const USAGE: &'static str = "*Usage*\n\
+        `/alerts open` lists all open alerts\n\
+        `/alerts service <name>` lists the open alerts for a service\n\
+        `/alerts show <id>` shows an alert\n\
+        `/alerts silence <id> <duration>` silences an alert, for a duration such as `30m`, `4h` or `2d`, of at most 30 days";
+
+    /// Parses the text that was typed after the command. Returns a message
+    /// that explains what is wrong if the text cannot be parsed.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let args: Vec<&str> = text.split_whitespace().collect();
+        match args.as_slice() {
+            [] | ["help"] => Ok(Self::Help),
+            ["open"] => Ok(Self::Open),
+            ["service", name] => Ok(Self::Service((*name).to_owned())),
+            ["show", alert_id] => Ok(Self::Show(parse_alert_id(alert_id)?)),
+            ["silence", alert_id, duration] => Ok(Self::Silence {
+                alert_id: parse_alert_id(alert_id)?,
+                duration: parse_duration(duration)
+                    .ok_or_else(|| format!("`{duration}` is not a valid duration."))?,
+            }),
+            _ => Err(format!("Unknown command: `{text}`\n\n{}", Self::USAGE)),
+        }
+    }
+}
+
+fn parse_alert_id(alert_id: &str) -> Result<i64, String> {
+    alert_id
+        .trim_start_matches('#')
+        .parse()
+        .map_err(|_| format!("`{alert_id}` is not a valid alert ID."))
+}
+
+/// Longest duration for which an alert can be silenced from Slack.
+const MAX_SILENCE_DURATION: Duration = Duration::days(30);
+
+/// Parses a positive duration with a single unit, such as `90s`, `30m`, `4h`
+/// or `2d`. Durations longer than [`MAX_SILENCE_DURATION`] are rejected.
+fn parse_duration(duration: &str) -> Option<Duration> {
+    let unit_index = duration.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = duration.split_at(unit_index);
+    let amount: i64 = amount.parse().ok().filter(|amount| *amount > 0)?;
+
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+
+    amount
+        .checked_mul(unit_secs)
+        .map(Duration::seconds)
+        .filter(|duration| *duration <= MAX_SILENCE_DURATION)
+}
+
+/// Response to a slash command, which is only shown to the user who invoked
+/// it.
+#[derive(Debug, Serialize)]
+pub struct SlackCommandResponse {
+    response_type: &'static str,
+
+    #[serde(flatten)]
+    content: SlackMessageContent,
+}
+
+impl SlackCommandResponse {
+    pub fn ephemeral(content: SlackMessageContent) -> Self {
+        Self {
+            response_type: "ephemeral",
+            content,
+        }
+    }
+}
+
+pub fn build_text_reply(text: &str) -> SlackMessageContent {
+    SlackMessageContent::new().with_blocks(vec![SlackSectionBlock::new()
+        .with_text(SlackBlockMarkDownText::new(text.to_owned()).into())
+        .into()])
+}
+
+/// Builds a reply that lists the given alerts, using the same colors and
+/// summary fields as the alert messages. If there are more alerts than can be
+/// listed, only the most recent ones are listed.
+pub fn build_alert_list_reply(
+    title: &str,
+    alerts: &[Alert],
+    max_alerts: usize,
+) -> SlackMessageContent {
+    if alerts.is_empty() {
+        return build_text_reply(&format!("*{title}*\nThere are no alerts. :tada:"));
+    }
+
+    let text = if alerts.len() > max_alerts {
+        format!("*{title}*\nShowing the {max_alerts} most recent alerts.")
+    } else {
+        format!("*{title}*")
+    };
+
+    let attachments = alerts
+        .iter()
+        .take(max_alerts)
+        .map(|alert| {
+            let block = SlackSectionBlock::new()
+                .with_text(
+                    SlackBlockMarkDownText::new(format!("*#{}* {}", alert.id, alert.text)).into(),
+                )
+                .with_fields(build_summary_fields(alert));
+
+            SlackMessageAttachment::new()
+                .with_color(alert_color(alert))
+                .with_blocks(vec![block.into()])
+        })
+        .collect();
+
+    build_text_reply(&text).with_attachments(attachments)
+}
@@ -1,19 +1,134 @@
+use crate::db::DbError;
+use crate::service::AlertmanagerServiceError;
+use axum::extract::Json;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
 use serde::Serialize;
 use slack_morphism::errors::SlackClientError;
 use thiserror::Error;
 
+/// Errors returned by the Slack API that will keep occurring however often the
+/// request is retried, because the token, channel or message is wrong.
+const PERMANENT_API_ERRORS: &[&str] = &[
+    "account_inactive",
+    "cant_update_message",
+    "channel_not_found",
+    "invalid_arguments",
+    "invalid_auth",
+    "invalid_blocks",
+    "invalid_blocks_format",
+    "is_archived",
+    "message_not_found",
+    "missing_scope",
+    "msg_too_long",
+    "no_permission",
+    "not_authed",
+    "not_in_channel",
+    "restricted_action",
+    "token_expired",
+    "token_revoked",
+];
+
 #[derive(Debug, Serialize, Error)]
 #[serde(tag = "error", content = "details", rename_all = "snake_case")]
 pub enum SlackServiceError {
+    #[error("Slack API error: {code}")]
+    Api { code: String },
+
     #[error("Config error: {0}")]
     Client(String),
 
     #[error("Cannot update message without timestamp")]
     MissingTimestamp,
+
+    #[error("Rate limited by Slack")]
+    RateLimited { retry_after_secs: Option<u64> },
 }
 
 impl From<SlackClientError> for SlackServiceError {
     fn from(error: SlackClientError) -> Self {
-        Self::Client(error.to_string())
+        match error {
+            SlackClientError::RateLimitError(error) => Self::RateLimited {
+                retry_after_secs: error.retry_after.map(|duration| duration.as_secs()),
+            },
+            SlackClientError::ApiError(error) if error.code == "ratelimited" => Self::RateLimited {
+                retry_after_secs: None,
+            },
+            SlackClientError::ApiError(error) => Self::Api { code: error.code },
+            error => Self::Client(error.to_string()),
+        }
+    }
+}
+
+impl SlackServiceError {
+    /// Returns whether Slack rejected the request for a reason that retrying
+    /// it won't fix.
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            Self::Api { code } => PERMANENT_API_ERRORS.contains(&code.as_str()),
+            Self::Client(_) | Self::MissingTimestamp | Self::RateLimited { .. } => false,
+        }
+    }
+}
+
+#[derive(Debug, Error, Serialize)]
+#[serde(tag = "error", content = "details", rename_all = "snake_case")]
+pub enum SlackRequestHandlerError {
+    #[error("Alertmanager error: {0}")]
+    AlertmanagerError(AlertmanagerServiceError),
+
+    #[error("Database error: {0}")]
+    DatabaseError(DbError),
+
+    #[error("Interactivity is not enabled")]
+    InteractivityDisabled,
+
+    #[error("Invalid request signature")]
+    InvalidSignature,
+
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(String),
+
+    #[error("Entity not found")]
+    NotFound,
+
+    #[error("Slack error: {0}")]
+    SlackError(SlackServiceError),
+}
+
+impl From<AlertmanagerServiceError> for SlackRequestHandlerError {
+    fn from(error: AlertmanagerServiceError) -> Self {
+        Self::AlertmanagerError(error)
+    }
+}
+
+impl From<DbError> for SlackRequestHandlerError {
+    fn from(error: DbError) -> Self {
+        match error {
+            DbError::NotFound => SlackRequestHandlerError::NotFound,
+            error => SlackRequestHandlerError::DatabaseError(error),
+        }
+    }
+}
+
+impl From<SlackServiceError> for SlackRequestHandlerError {
+    fn from(error: SlackServiceError) -> Self {
+        Self::SlackError(error)
+    }
+}
+
+impl IntoResponse for SlackRequestHandlerError {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = match self {
+            Self::AlertmanagerError(_) => StatusCode::BAD_GATEWAY,
+            Self::DatabaseError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InteractivityDisabled => StatusCode::NOT_FOUND,
+            Self::InvalidSignature => StatusCode::UNAUTHORIZED,
+            Self::InvalidPayload(_) => StatusCode::BAD_REQUEST,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::SlackError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        (status_code, Json(self)).into_response()
     }
 }
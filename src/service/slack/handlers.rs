@@ -0,0 +1,237 @@
+use super::commands::{
+    build_alert_list_reply, build_text_reply, AlertsCommand, SlackCommandPayload,
+    SlackCommandResponse,
+};
+use super::interactions::{AlertAction, SlackInteractionPayload};
+use super::SlackRequestHandlerError;
+use crate::db::models::{Alert, AlertFilter};
+use crate::db::DbError;
+use crate::service::{Service, SLACK_APP_SLO};
+use autometrics::autometrics;
+use axum::body::Bytes;
+use axum::extract::{Json, State};
+use axum::http::HeaderMap;
+use slack_morphism::prelude::SlackMessageContent;
+use time::OffsetDateTime;
+use tracing::{debug, instrument};
+
+/// Maximum number of alerts listed in a reply to the `/alerts` command.
+const MAX_LISTED_ALERTS: usize = 20;
+
+#[autometrics(objective = SLACK_APP_SLO)]
+#[instrument(err, skip_all)]
+pub async fn receive_slack_interaction(
+    State(service): State<Service>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(), SlackRequestHandlerError> {
+    service.slack.verify_request(&headers, &body)?;
+
+    let payload = SlackInteractionPayload::from_form_body(&body)?;
+    debug!(?payload, "Received Slack interaction");
+
+    if payload.kind != "block_actions" {
+        return Ok(());
+    }
+
+    for action in &payload.actions {
+        // Buttons that only link somewhere (such as "Open in Explorer") are
+        // reported too, so we ignore them.
+        let (Some(alert_action), Some(value)) = (
+            AlertAction::from_action_id(&action.action_id),
+            action.value.as_ref(),
+        ) else {
+            continue;
+        };
+
+        let alert_id: i64 = value
+            .parse()
+            .map_err(|_| SlackRequestHandlerError::InvalidPayload(value.to_owned()))?;
+
+        perform_alert_action(
+            &service,
+            alert_id,
+            alert_action,
+            &payload.user.id,
+            payload.user.display_name(),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[autometrics(objective = SLACK_APP_SLO)]
+#[instrument(err, skip_all)]
+pub async fn receive_slack_command(
+    State(service): State<Service>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<SlackCommandResponse>, SlackRequestHandlerError> {
+    service.slack.verify_request(&headers, &body)?;
+
+    let payload = SlackCommandPayload::from_form_body(&body)?;
+    debug!(?payload, "Received Slack command");
+
+    let content = match AlertsCommand::parse(&payload.text) {
+        Ok(command) => handle_alerts_command(&service, &payload, command).await?,
+        Err(message) => build_text_reply(&message),
+    };
+
+    Ok(Json(SlackCommandResponse::ephemeral(content)))
+}
+
+async fn handle_alerts_command(
+    service: &Service,
+    payload: &SlackCommandPayload,
+    command: AlertsCommand,
+) -> Result<SlackMessageContent, SlackRequestHandlerError> {
+    let content = match command {
+        AlertsCommand::Help => build_text_reply(AlertsCommand::USAGE),
+        AlertsCommand::Open => {
+            let filter = AlertFilter {
+                resolved: Some(false),
+                ..Default::default()
+            };
+            let alerts = list_alerts(service, &filter).await?;
+            build_alert_list_reply("Open alerts", &alerts, MAX_LISTED_ALERTS)
+        }
+        AlertsCommand::Service(sloth_service) => {
+            let filter = AlertFilter {
+                resolved: Some(false),
+                sloth_service: Some(sloth_service.clone()),
+                ..Default::default()
+            };
+            let alerts = list_alerts(service, &filter).await?;
+            build_alert_list_reply(
+                &format!("Open alerts for `{sloth_service}`"),
+                &alerts,
+                MAX_LISTED_ALERTS,
+            )
+        }
+        AlertsCommand::Show(alert_id) => {
+            let mut tx = service.db.start_transaction().await?;
+            let result = service.db.alert_get(&mut tx, alert_id).await;
+            let content = match result {
+                Ok(alert) => {
+                    let stats = service
+                        .db
+                        .alert_stats(&mut tx, &alert, OffsetDateTime::now_utc())
+                        .await?;
+                    service.slack.build_alert_message(&alert, &stats)?
+                }
+                Err(DbError::NotFound) => alert_not_found_reply(alert_id),
+                Err(err) => return Err(err.into()),
+            };
+            service.db.commit(tx).await?;
+            content
+        }
+        AlertsCommand::Silence { alert_id, duration } => {
+            let result = perform_alert_action(
+                service,
+                alert_id,
+                AlertAction::Silence { duration },
+                &payload.user_id,
+                &payload.user_name,
+            )
+            .await;
+            match result {
+                Ok(alert) => build_text_reply(&format!(
+                    ":mute: Silenced alert #{alert_id} until {}",
+                    alert
+                        .silenced_until
+                        .map(|until| until.to_string())
+                        .unwrap_or_default()
+                )),
+                Err(SlackRequestHandlerError::NotFound) => alert_not_found_reply(alert_id),
+                Err(err) => return Err(err),
+            }
+        }
+    };
+
+    Ok(content)
+}
+
+/// Returns open alerts matching the filter, fetching one more than can be
+/// listed so the reply can mention there are more.
+async fn list_alerts(
+    service: &Service,
+    filter: &AlertFilter,
+) -> Result<Vec<Alert>, SlackRequestHandlerError> {
+    let mut tx = service.db.start_transaction().await?;
+    let alerts = service
+        .db
+        .alert_list(&mut tx, filter, None, MAX_LISTED_ALERTS as i64 + 1)
+        .await?;
+    service.db.commit(tx).await?;
+
+    Ok(alerts)
+}
+
+fn alert_not_found_reply(alert_id: i64) -> SlackMessageContent {
+    build_text_reply(&format!("Alert #{alert_id} does not exist."))
+}
+
+/// Performs the action on the alert on behalf of the given Slack user, and
+/// queues an update of the Slack messages for the alert.
+async fn perform_alert_action(
+    service: &Service,
+    alert_id: i64,
+    alert_action: AlertAction,
+    user_id: &str,
+    user_name: &str,
+) -> Result<Alert, SlackRequestHandlerError> {
+    let now = OffsetDateTime::now_utc();
+    let mut tx = service.db.start_transaction().await?;
+    let alert = service.db.alert_get(&mut tx, alert_id).await?;
+    service.db.commit(tx).await?;
+
+    // Alertmanager is called before the alert is updated, so the transaction
+    // isn't kept open while waiting for it.
+    let mut silence_id = None;
+    if let Some((starts_at, ends_at)) = alert_action.silence_window(now) {
+        if service.alertmanager.silences_enabled() {
+            silence_id = Some(
+                service
+                    .alertmanager
+                    .create_silence(
+                        &alert.labels,
+                        starts_at,
+                        ends_at,
+                        user_name.to_owned(),
+                        format!("Silenced from Slack: {}", alert.text),
+                    )
+                    .await?,
+            );
+        }
+    } else if alert_action == AlertAction::ExpireSilence {
+        if let Some(silence_id) = alert.silence_id.as_ref() {
+            service.alertmanager.expire_silence(silence_id).await?;
+        }
+    }
+
+    let mut tx = service.db.start_transaction().await?;
+
+    // Fetch the alert again, since it may have changed while we were waiting
+    // for Alertmanager.
+    let mut alert = service.db.alert_get(&mut tx, alert_id).await?;
+    if silence_id.is_some() {
+        alert.silence_id = silence_id;
+    }
+    alert_action.apply(&mut alert, user_id, now);
+    service.db.alert_update(&mut tx, &alert).await?;
+    service
+        .db
+        .alert_event_create(&mut tx, alert_id, alert_action.event_kind(), Some(user_id))
+        .await?;
+
+    service
+        .queue_alert_update(&mut tx, &alert, alert_action.transition())
+        .await?;
+
+    service.db.commit(tx).await?;
+
+    service.notify_event_loop();
+
+    Ok(alert)
+}
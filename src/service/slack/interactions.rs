@@ -0,0 +1,161 @@
+use super::SlackRequestHandlerError;
+use crate::db::models::{Alert, AlertEventKind, AlertTransition};
+use serde::Deserialize;
+use time::{Duration, OffsetDateTime};
+
+/// Payload Slack sends to the interactivity request URL.
+///
+/// Only the fields we need are included.
+///
+/// See: https://api.slack.com/reference/interaction-payloads/block-actions
+#[derive(Debug, Deserialize)]
+pub struct SlackInteractionPayload {
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    pub user: SlackInteractionUser,
+
+    #[serde(default)]
+    pub actions: Vec<SlackInteractionAction>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SlackInteractionUser {
+    /// ID of the Slack user who performed the interaction.
+    pub id: String,
+
+    /// Username of the Slack user who performed the interaction.
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+impl SlackInteractionUser {
+    /// Returns a name suitable for attributing actions in other systems.
+    pub fn display_name(&self) -> &str {
+        self.username.as_deref().unwrap_or(&self.id)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SlackInteractionAction {
+    pub action_id: String,
+
+    /// The value of the button, which we set to the alert ID.
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+impl SlackInteractionPayload {
+    /// Parses the payload from the form-encoded body of an interaction
+    /// request.
+    pub fn from_form_body(body: &[u8]) -> Result<Self, SlackRequestHandlerError> {
+        let payload = form_urlencoded::parse(body)
+            .find(|(key, _)| key == "payload")
+            .map(|(_, value)| value)
+            .ok_or_else(|| {
+                SlackRequestHandlerError::InvalidPayload("missing payload".to_owned())
+            })?;
+
+        serde_json::from_str(&payload)
+            .map_err(|err| SlackRequestHandlerError::InvalidPayload(err.to_string()))
+    }
+}
+
+/// Actions that can be performed on an alert, using the buttons in its Slack
+/// message or the `/alerts` command.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AlertAction {
+    Acknowledge,
+    Silence { duration: Duration },
+    ExpireSilence,
+    Resolve,
+}
+
+impl AlertAction {
+    /// Silence durations offered in Slack messages.
+    pub const SILENCE_HOURS: [i64; 3] = [1, 4, 24];
+
+    pub fn action_id(&self) -> String {
+        match self {
+            Self::Acknowledge => "acknowledge".to_owned(),
+            Self::Silence { duration } => format!("silence_{}h", duration.whole_hours()),
+            Self::ExpireSilence => "expire_silence".to_owned(),
+            Self::Resolve => "resolve".to_owned(),
+        }
+    }
+
+    pub fn from_action_id(action_id: &str) -> Option<Self> {
+        match action_id {
+            "acknowledge" => Some(Self::Acknowledge),
+            "expire_silence" => Some(Self::ExpireSilence),
+            "resolve" => Some(Self::Resolve),
+            action_id => action_id
+                .strip_prefix("silence_")
+                .and_then(|hours| hours.strip_suffix('h'))
+                .and_then(|hours| hours.parse().ok())
+                .filter(|hours| Self::SILENCE_HOURS.contains(hours))
+                .map(|hours| Self::Silence {
+                    duration: Duration::hours(hours),
+                }),
+        }
+    }
+
+    /// Returns the time window of the silence this action would create, if
+    /// any.
+    pub fn silence_window(&self, now: OffsetDateTime) -> Option<(OffsetDateTime, OffsetDateTime)> {
+        match self {
+            Self::Silence { duration } => Some((now, now + *duration)),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of event recorded in the history of the alert when
+    /// the action is performed.
+    pub fn event_kind(&self) -> AlertEventKind {
+        match self {
+            Self::Acknowledge => AlertEventKind::Acknowledged,
+            Self::Silence { .. } => AlertEventKind::Silenced,
+            Self::ExpireSilence => AlertEventKind::SilenceExpired,
+            Self::Resolve => AlertEventKind::Resolved,
+        }
+    }
+
+    /// Returns the transition caused by this action, if it is one worth
+    /// notifying people about.
+    pub fn transition(&self) -> Option<AlertTransition> {
+        match self {
+            Self::Acknowledge => Some(AlertTransition::Acknowledged),
+            Self::Resolve => Some(AlertTransition::Resolved),
+            Self::Silence { .. } | Self::ExpireSilence => None,
+        }
+    }
+
+    /// Applies the action to the given alert, on behalf of the given Slack
+    /// user.
+    ///
+    /// Note this only updates the alert itself. Creating and expiring the
+    /// corresponding Alertmanager silences is up to the caller.
+    pub fn apply(&self, alert: &mut Alert, user_id: &str, now: OffsetDateTime) {
+        match self {
+            Self::Acknowledge => {
+                alert.acknowledged_by = Some(user_id.to_owned());
+                alert.acknowledged_at = Some(now);
+            }
+            Self::Silence { duration } => {
+                alert.silenced_by = Some(user_id.to_owned());
+                alert.silenced_at = Some(now);
+                alert.silenced_until = Some(now + *duration);
+            }
+            Self::ExpireSilence => {
+                alert.silenced_by = None;
+                alert.silenced_at = None;
+                alert.silenced_until = None;
+                alert.silence_id = None;
+            }
+            Self::Resolve => {
+                alert.resolved = true;
+                alert.resolved_by = Some(user_id.to_owned());
+            }
+        }
+    }
+}
@@ -1,20 +1,37 @@
+mod commands;
 mod errors;
+mod interactions;
+mod routing;
+mod signature;
 #[cfg(test)]
 mod tests;
+mod uploads;
 
-use crate::db::models::Alert;
+pub mod handlers;
+
+use crate::db::models::{Alert, AlertStats, AlertTransition, ErrorBudget, SlackMessage};
+use crate::service::charts::ChartUrlSigner;
+use crate::service::prometheus::expression_from_generator_url;
+use autometrics::autometrics;
+use axum::http::HeaderMap;
 use fiberplane::models::timestamps::Timestamp;
+use interactions::AlertAction;
+use routing::SlackRoutingTable;
 use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use signature::verify_slack_signature;
 use slack_morphism::prelude::*;
 use time::ext::NumericalDuration;
+use time::OffsetDateTime;
+use uploads::{SlackFileUpload, SlackFileUploader, SLACK_API_URL};
 use url::Url;
 
-pub use errors::SlackServiceError;
+pub use errors::{SlackRequestHandlerError, SlackServiceError};
 
 #[derive(clap::Args, Debug)]
 pub struct SlackServiceConfig {
-    /// Slack channel to post to.
-    /// TODO: This should be a user setting.
+    /// Default Slack channel to post to, if the routing table doesn't specify
+    /// a channel for an alert.
     #[clap(
         long = "slack-channel",
         env = "SLACK_CHANNEL",
@@ -42,6 +59,62 @@ pub struct SlackServiceConfig {
         help_heading = "Slack options"
     )]
     token: SecretString,
+
+    /// Signing secret of the Slack app, used to verify requests sent by Slack.
+    ///
+    /// Interactive buttons are only added to messages if this is set.
+    #[clap(
+        long = "slack-signing-secret",
+        env = "SLACK_SIGNING_SECRET",
+        help_heading = "Slack options"
+    )]
+    signing_secret: Option<SecretString>,
+
+    /// Path to a YAML file with a routing table, that determines which
+    /// channels alerts are posted to based on their labels.
+    ///
+    /// If not set, all alerts are posted to the default channel.
+    #[clap(
+        long = "slack-routing-config",
+        env = "SLACK_ROUTING_CONFIG",
+        value_parser = SlackRoutingTable::from_file,
+        help_heading = "Slack options"
+    )]
+    routing_table: Option<SlackRoutingTable>,
+
+    /// Whether to post a reply in the thread of an alert message whenever the
+    /// alert is resolved, fires again or is acknowledged.
+    ///
+    /// The message itself is always updated in place.
+    #[clap(
+        long = "slack-thread-replies",
+        env = "SLACK_THREAD_REPLIES",
+        help_heading = "Slack options"
+    )]
+    thread_replies: bool,
+
+    /// Comma-separated list of severities for which thread replies are also
+    /// broadcast to the channel.
+    #[clap(
+        long = "slack-broadcast-severities",
+        env = "SLACK_BROADCAST_SEVERITIES",
+        value_delimiter = ',',
+        help_heading = "Slack options"
+    )]
+    broadcast_severities: Vec<String>,
+
+    /// Whether to upload charts to Slack, instead of linking to them.
+    ///
+    /// Uploaded charts are posted in the thread of the alert message. This
+    /// allows running the app on a private network, since Slack doesn't need
+    /// to fetch the charts from it. The alert message itself can't embed
+    /// uploaded charts, so it points to the thread instead.
+    #[clap(
+        long = "slack-upload-charts",
+        env = "SLACK_UPLOAD_CHARTS",
+        help_heading = "Slack options"
+    )]
+    upload_charts: bool,
 }
 
 #[cfg(test)]
@@ -50,6 +123,11 @@ impl SlackServiceConfig {
         Self {
             channel: "test-channel".to_owned(),
             token: token.into(),
+            signing_secret: None,
+            routing_table: None,
+            thread_replies: false,
+            broadcast_severities: Vec::new(),
+            upload_charts: false,
         }
     }
 }
@@ -58,18 +136,42 @@ pub struct SlackService {
     /// Service URL of the Slack app itself.
     ///
     /// This is used by the Slack service to link to images included in
-    /// messages.
+    /// messages, unless charts are uploaded.
     service_base_url: Url,
 
-    /// Slack channel to post alerts to.
-    channel: SlackChannelId,
+    /// Slack channel to post alerts to, if the routing table doesn't specify
+    /// any channels.
+    channel: String,
+
+    /// Table that determines which channels alerts are posted to.
+    routing_table: SlackRoutingTable,
+
+    /// Whether to post thread replies for alert transitions.
+    thread_replies: bool,
+
+    /// Severities for which thread replies are broadcast to the channel.
+    broadcast_severities: Vec<String>,
+
+    /// Whether charts are uploaded to Slack, instead of linked to.
+    upload_charts: bool,
+
+    /// Signs the URLs of charts that are linked to, if configured.
+    chart_url_signer: Option<ChartUrlSigner>,
 
     /// Slack client.
     client: SlackClient<SlackClientHyperHttpsConnector>,
 
+    /// Uploads charts, which the Slack client doesn't support.
+    uploader: SlackFileUploader,
+
     /// The API token for authenticating with Slack.
     token: SlackApiToken,
 
+    /// Optional secret for verifying requests sent by Slack.
+    ///
+    /// If no secret is provided, interactive features are disabled.
+    signing_secret: Option<SecretString>,
+
     /// URL of the Prometheus instance, used in links to Explorer.
     prometheus_url: Url,
 
@@ -86,34 +188,137 @@ impl SlackService {
         config: SlackServiceConfig,
         prometheus_url: Url,
         explorer_base_url: Option<Url>,
+        chart_url_signer: Option<ChartUrlSigner>,
     ) -> Self {
-        let channel = SlackChannelId(config.channel.clone());
         let client = SlackClient::new(SlackClientHyperConnector::new());
+        let uploader = SlackFileUploader::new(
+            Url::parse(SLACK_API_URL).expect("Invalid Slack API URL"),
+            config.token.clone(),
+        );
         let token_value: SlackApiTokenValue = config.token.expose_secret().into();
         let token: SlackApiToken = SlackApiToken::new(token_value);
 
         Self {
             service_base_url,
-            channel,
+            channel: config.channel,
+            routing_table: config.routing_table.unwrap_or_default(),
+            thread_replies: config.thread_replies,
+            broadcast_severities: config.broadcast_severities,
+            upload_charts: config.upload_charts,
+            chart_url_signer,
             client,
+            uploader,
             prometheus_url,
             explorer_base_url,
             token,
+            signing_secret: config.signing_secret,
         }
     }
 
+    /// Verifies the signature of a request sent by Slack.
+    pub fn verify_request(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<(), SlackRequestHandlerError> {
+        let Some(signing_secret) = self.signing_secret.as_ref() else {
+            return Err(SlackRequestHandlerError::InteractivityDisabled);
+        };
+
+        verify_slack_signature(signing_secret, headers, body, OffsetDateTime::now_utc())
+    }
+
+    pub fn thread_replies_enabled(&self) -> bool {
+        self.thread_replies
+    }
+
+    pub fn uploads_charts(&self) -> bool {
+        self.upload_charts
+    }
+
+    /// Returns the URL of the chart to embed in messages for the alert, if it
+    /// has a chart and charts aren't uploaded instead.
+    ///
+    /// The URL explicitly asks for a PNG, because Slack can't display SVGs.
+    /// It includes a version derived from the chart filename, so Slack
+    /// fetches the chart again once it is regenerated. It is signed if a
+    /// signing key is configured.
+    fn chart_url(&self, alert: &Alert, now: OffsetDateTime) -> Option<Url> {
+        self.signed_chart_url(alert, "", alert.chart_filename.as_ref()?, now)
+    }
+
+    /// Returns the URL of the error budget chart to embed in messages for the
+    /// alert, like [`Self::chart_url`].
+    fn error_budget_chart_url(&self, alert: &Alert, now: OffsetDateTime) -> Option<Url> {
+        self.signed_chart_url(
+            alert,
+            "/error-budget",
+            alert.error_budget_chart_filename.as_ref()?,
+            now,
+        )
+    }
+
+    fn signed_chart_url(
+        &self,
+        alert: &Alert,
+        path_suffix: &str,
+        chart_filename: &str,
+        now: OffsetDateTime,
+    ) -> Option<Url> {
+        if self.upload_charts {
+            return None;
+        }
+
+        let version = hex::encode(&Sha256::digest(chart_filename.as_bytes())[..4]);
+        let mut url = self
+            .service_base_url
+            .join(&format!(
+                "/api/chart/{}{path_suffix}?format=png&v={version}",
+                alert.id
+            ))
+            .ok()?;
+        if let Some(chart_url_signer) = self.chart_url_signer.as_ref() {
+            chart_url_signer.sign(&mut url, alert.id, now);
+        }
+
+        Some(url)
+    }
+
+    /// Returns the channels the given alert should be posted to.
+    pub fn channels_for_alert(&self, alert: &Alert) -> Vec<String> {
+        self.routing_table
+            .channels_for(&alert.labels, &self.channel)
+    }
+
+    /// Builds the message for an alert, as it is posted to Slack.
+    pub fn build_alert_message(
+        &self,
+        alert: &Alert,
+        stats: &AlertStats,
+    ) -> Result<SlackMessageContent, SlackServiceError> {
+        let now = OffsetDateTime::now_utc();
+        build_message(
+            self.chart_url(alert, now).as_ref(),
+            self.error_budget_chart_url(alert, now).as_ref(),
+            self.upload_charts,
+            &self.prometheus_url,
+            self.explorer_base_url.as_ref(),
+            self.signing_secret.is_some(),
+            alert,
+            stats,
+        )
+    }
+
+    /// Posts the alert to the given channel.
     pub async fn send_alert(
         &self,
         alert: &Alert,
+        stats: &AlertStats,
+        channel: &str,
     ) -> Result<(SlackChannelId, SlackTs), SlackServiceError> {
         let post_message_request = SlackApiChatPostMessageRequest::new(
-            self.channel.clone(),
-            build_message(
-                &self.service_base_url,
-                &self.prometheus_url,
-                self.explorer_base_url.as_ref(),
-                alert,
-            )?,
+            SlackChannelId::new(channel.to_owned()),
+            self.build_alert_message(alert, stats)?,
         );
 
         let response = self
@@ -125,53 +330,142 @@ impl SlackService {
         Ok((response.channel, response.ts))
     }
 
-    pub async fn update_alert(&self, alert: &Alert) -> Result<(), SlackServiceError> {
-        let Some(ts) = alert.slack_ts.as_ref() else {
+    /// Updates all the messages that were posted for the alert.
+    pub async fn update_alert(
+        &self,
+        alert: &Alert,
+        stats: &AlertStats,
+        messages: &[SlackMessage],
+    ) -> Result<(), SlackServiceError> {
+        if messages.is_empty() {
             return Err(SlackServiceError::MissingTimestamp);
-        };
+        }
 
-        let channel = alert
-            .slack_channel
+        let session = self.client.open_session(&self.token);
+        for message in messages {
+            let update_request = SlackApiChatUpdateRequest::new(
+                SlackChannelId::new(message.channel_id.clone()),
+                self.build_alert_message(alert, stats)?,
+                message.ts.clone().into(),
+            )
+            .with_as_user(true);
+
+            session.chat_update(&update_request).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Posts a reply about the given transition in the thread of the given
+    /// message.
+    pub async fn post_thread_reply(
+        &self,
+        alert: &Alert,
+        message: &SlackMessage,
+        transition: AlertTransition,
+    ) -> Result<(), SlackServiceError> {
+        let broadcast = alert
+            .severity
             .as_ref()
-            .cloned()
-            .map(SlackChannelId::new)
-            .unwrap_or_else(|| self.channel.clone());
-
-        let update_request = SlackApiChatUpdateRequest::new(
-            channel,
-            build_message(
-                &self.service_base_url,
-                &self.prometheus_url,
-                self.explorer_base_url.as_ref(),
-                alert,
-            )?,
-            ts.into(),
+            .map(|severity| self.broadcast_severities.contains(severity))
+            .unwrap_or(false);
+
+        let post_message_request = SlackApiChatPostMessageRequest::new(
+            SlackChannelId::new(message.channel_id.clone()),
+            build_thread_reply(transition, alert),
+        )
+        .with_thread_ts(message.ts.clone().into())
+        .with_reply_broadcast(broadcast);
+
+        let session = self.client.open_session(&self.token);
+        session.chat_post_message(&post_message_request).await?;
+
+        Ok(())
+    }
+
+    /// Uploads the chart of the alert to the thread of the given message.
+    /// Returns the ID of the uploaded file.
+    #[autometrics]
+    pub async fn upload_chart(
+        &self,
+        alert: &Alert,
+        message: &SlackMessage,
+        chart: Vec<u8>,
+    ) -> Result<String, SlackServiceError> {
+        self.upload_file(
+            message,
+            chart,
+            format!("alert-{}.png", alert.id),
+            chart_title(alert),
+        )
+        .await
+    }
+
+    /// Uploads the error budget chart of the alert to the thread of the given
+    /// message. Returns the ID of the uploaded file.
+    #[autometrics]
+    pub async fn upload_error_budget_chart(
+        &self,
+        alert: &Alert,
+        message: &SlackMessage,
+        chart: Vec<u8>,
+    ) -> Result<String, SlackServiceError> {
+        self.upload_file(
+            message,
+            chart,
+            format!("alert-{}-error-budget.png", alert.id),
+            error_budget_chart_title(alert),
+        )
+        .await
+    }
+
+    async fn upload_file(
+        &self,
+        message: &SlackMessage,
+        chart: Vec<u8>,
+        filename: String,
+        title: String,
+    ) -> Result<String, SlackServiceError> {
+        let upload = SlackFileUpload {
+            channel_id: message.channel_id.clone(),
+            thread_ts: message.ts.clone(),
+            filename,
+            title,
+            content: chart,
+        };
+
+        self.uploader.upload(upload).await
+    }
+
+    /// Deletes the given message from Slack.
+    #[autometrics]
+    pub async fn delete_message(&self, message: &SlackMessage) -> Result<(), SlackServiceError> {
+        let delete_request = SlackApiChatDeleteRequest::new(
+            SlackChannelId::new(message.channel_id.clone()),
+            message.ts.clone().into(),
         )
         .with_as_user(true);
 
         self.client
             .open_session(&self.token)
-            .chat_update(&update_request)
+            .chat_delete(&delete_request)
             .await?;
 
         Ok(())
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_message(
-    service_base_url: &Url,
+    chart_url: Option<&Url>,
+    error_budget_chart_url: Option<&Url>,
+    charts_in_thread: bool,
     prometheus_url: &Url,
     explorer_url: Option<&Url>,
+    interactive: bool,
     alert: &Alert,
+    stats: &AlertStats,
 ) -> Result<SlackMessageContent, SlackServiceError> {
-    let color = if alert.resolved {
-        // Green
-        "#2EC95A".to_owned()
-    } else {
-        // Red
-        "#F2303C".to_owned()
-    };
-
     let header_text = if alert.resolved {
         ":white_check_mark: Alert was resolved".to_owned()
     } else {
@@ -182,35 +476,97 @@ fn build_message(
         SlackBlockPlainText::new(header_text).with_emoji(true),
     ));
 
-    let severity_text = match alert.severity.as_deref() {
-        Some("page") => ":pager: Page".to_owned(),
-        Some("ticket") => ":ticket: Ticket".to_owned(),
-        Some(severity) => format!(":question: {}", severity),
-        None => ":question: Unknown".to_owned(),
-    };
+    let mut fields = build_summary_fields(alert);
+    if alert.occurrence > 1 {
+        fields.push(
+            SlackBlockMarkDownText::new(format!(
+                "*Occurrence*\n#{} of this alert",
+                alert.occurrence
+            ))
+            .into(),
+        );
+    }
+    if let (Some(user_id), Some(acknowledged_at)) =
+        (alert.acknowledged_by.as_ref(), alert.acknowledged_at)
+    {
+        fields.push(
+            SlackBlockMarkDownText::new(format!(
+                "*Acknowledged*\nby <@{user_id}> at {acknowledged_at}"
+            ))
+            .into(),
+        );
+    }
+    if let (Some(user_id), Some(silenced_at), Some(silenced_until)) = (
+        alert.silenced_by.as_ref(),
+        alert.silenced_at,
+        alert.silenced_until,
+    ) {
+        fields.push(
+            SlackBlockMarkDownText::new(format!(
+                "*Silenced*\nfrom {silenced_at} until {silenced_until} by <@{user_id}>"
+            ))
+            .into(),
+        );
+    }
+    if let Some(user_id) = alert.resolved_by.as_ref() {
+        fields.push(SlackBlockMarkDownText::new(format!("*Resolved*\nby <@{user_id}>")).into());
+    }
+    if stats.fired_last_24h > 1 {
+        fields.push(
+            SlackBlockMarkDownText::new(format!(
+                "*History*\nFired {} times in the last 24h",
+                stats.fired_last_24h
+            ))
+            .into(),
+        );
+    }
+    if let Some(time_to_resolve_secs) = stats.time_to_resolve_secs {
+        fields.push(
+            SlackBlockMarkDownText::new(format!(
+                "*Time to resolve*\n{}",
+                format_duration(time_to_resolve_secs)
+            ))
+            .into(),
+        );
+    }
+    if let Some(error_budget) = alert.error_budget.as_ref() {
+        fields.push(
+            SlackBlockMarkDownText::new(format!(
+                "*Error budget*\n{}",
+                format_remaining_error_budget(error_budget)
+            ))
+            .into(),
+        );
+        fields.push(
+            SlackBlockMarkDownText::new(format!(
+                "*Burn rate*\n{}",
+                format_burn_rates(error_budget)
+            ))
+            .into(),
+        );
+    }
 
     let description_block = SlackSectionBlock::new()
         .with_text(SlackBlockMarkDownText::new(alert.text.clone()).into())
-        .with_fields(vec![
-            SlackBlockMarkDownText::new(format!("*Severity*\n{}", severity_text)).into(),
-            SlackBlockMarkDownText::new(format!("*Created*\n{}", alert.created_at)).into(),
-        ]);
-
-    let chart_block = if let Some(_chart_filename) = alert.chart_filename.as_ref() {
-        let section: SlackBlock = SlackImageBlock::new(
-            service_base_url
-                .join(&format!("/api/chart/{}", alert.id))
-                .unwrap(),
-            format!(
-                "Chart for slo `{}`",
-                alert.sloth_slo.as_deref().unwrap_or("unknown")
-            ),
-        )
-        .into();
-        Some(section)
-    } else {
-        None
-    };
+        .with_fields(fields);
+
+    let chart_block: Option<SlackBlock> = chart_url
+        .map(|chart_url| SlackImageBlock::new(chart_url.clone(), chart_title(alert)).into());
+    let error_budget_chart_block: Option<SlackBlock> = error_budget_chart_url.map(|chart_url| {
+        SlackImageBlock::new(chart_url.clone(), error_budget_chart_title(alert)).into()
+    });
+
+    // Uploaded charts can't be embedded, since image blocks need a URL Slack
+    // can fetch the image from, so the message points to the thread instead.
+    let charts_in_thread_block: Option<SlackBlock> =
+        (charts_in_thread && alert.chart_filename.is_some()).then(|| {
+            SlackContextBlock::new(vec![SlackContextBlockElement::MarkDown(
+                SlackBlockMarkDownText::new(
+                    ":chart_with_upwards_trend: Charts are in the thread".to_owned(),
+                ),
+            )])
+            .into()
+        });
 
     let actions_block = if let Some(explorer_alert_url) =
         get_explorer_alert_url(explorer_url, prometheus_url, alert)
@@ -234,16 +590,25 @@ fn build_message(
         None
     };
 
+    let buttons_block = if interactive && !alert.resolved {
+        Some(build_action_buttons(alert, OffsetDateTime::now_utc()))
+    } else {
+        None
+    };
+
     let blocks_maybe: Vec<Option<SlackBlock>> = vec![
         Some(header_block.into()),
         Some(description_block.into()),
         chart_block,
+        error_budget_chart_block,
+        charts_in_thread_block,
         actions_block,
+        buttons_block,
     ];
     let blocks: Vec<SlackBlock> = blocks_maybe.into_iter().flatten().collect();
 
     let attachment = SlackMessageAttachment::new()
-        .with_color(color)
+        .with_color(alert_color(alert))
         .with_blocks(blocks);
 
     let content = SlackMessageContent::new().with_attachments(vec![attachment]);
@@ -251,6 +616,102 @@ fn build_message(
     Ok(content)
 }
 
+/// Number of characters of the expression of an alerting rule that are shown
+/// in the title of its chart.
+const MAX_TITLE_EXPRESSION_CHARS: usize = 200;
+
+/// Returns the title of the chart for the alert, which is also used as its
+/// alternative text.
+///
+/// Charts of alerts that aren't about an SLO show the expression of their
+/// alerting rule instead, shortened to [`MAX_TITLE_EXPRESSION_CHARS`], since
+/// Slack limits the length of alternative texts.
+fn chart_title(alert: &Alert) -> String {
+    let expression = alert
+        .generator_url
+        .as_deref()
+        .and_then(expression_from_generator_url);
+
+    match (alert.sloth_slo.as_deref(), expression) {
+        (None, Some(expression)) if expression.chars().count() > MAX_TITLE_EXPRESSION_CHARS => {
+            let expression: String = expression
+                .chars()
+                .take(MAX_TITLE_EXPRESSION_CHARS)
+                .collect();
+            format!("Chart for `{expression}…`")
+        }
+        (None, Some(expression)) => format!("Chart for `{expression}`"),
+        (slo, _) => format!("Chart for slo `{}`", slo.unwrap_or("unknown")),
+    }
+}
+
+/// Returns the title of the error budget chart for the alert.
+fn error_budget_chart_title(alert: &Alert) -> String {
+    format!(
+        "Error budget for slo `{}`",
+        alert.sloth_slo.as_deref().unwrap_or("unknown")
+    )
+}
+
+/// Returns the color of the bar next to messages about the alert.
+fn alert_color(alert: &Alert) -> String {
+    if alert.resolved {
+        // Green
+        "#2EC95A".to_owned()
+    } else {
+        // Red
+        "#F2303C".to_owned()
+    }
+}
+
+/// Builds the fields with the severity and creation time of the alert, which
+/// are shown for every alert.
+fn build_summary_fields(alert: &Alert) -> Vec<SlackBlockText> {
+    let severity_text = match alert.severity.as_deref() {
+        Some("page") => ":pager: Page".to_owned(),
+        Some("ticket") => ":ticket: Ticket".to_owned(),
+        Some(severity) => format!(":question: {}", severity),
+        None => ":question: Unknown".to_owned(),
+    };
+
+    vec![
+        SlackBlockMarkDownText::new(format!("*Severity*\n{}", severity_text)).into(),
+        SlackBlockMarkDownText::new(format!("*Created*\n{}", alert.created_at)).into(),
+    ]
+}
+
+/// Builds the block with buttons for interacting with a firing alert.
+fn build_action_buttons(alert: &Alert, now: OffsetDateTime) -> SlackBlock {
+    let mut actions = Vec::new();
+    if alert.acknowledged_by.is_none() {
+        actions.push((AlertAction::Acknowledge, "Acknowledge".to_owned()));
+    }
+    let silenced = matches!(alert.silenced_until, Some(until) if until > now);
+    if silenced {
+        actions.push((AlertAction::ExpireSilence, "Expire silence".to_owned()));
+    } else {
+        for hours in AlertAction::SILENCE_HOURS {
+            let duration = time::Duration::hours(hours);
+            actions.push((
+                AlertAction::Silence { duration },
+                format!("Silence {hours}h"),
+            ));
+        }
+    }
+    actions.push((AlertAction::Resolve, "Mark resolved".to_owned()));
+
+    let elements = actions
+        .into_iter()
+        .map(|(action, label)| {
+            SlackBlockButtonElement::new(action.action_id().into(), label.into())
+                .with_value(alert.id.to_string())
+                .into()
+        })
+        .collect();
+
+    SlackActionsBlock::new(elements).into()
+}
+
 /// Returns the URL to link to Explorer for a given alert.
 fn get_explorer_alert_url(
     base_url: Option<&Url>,
@@ -284,3 +745,79 @@ fn get_explorer_alert_url(
         _ => None,
     }
 }
+
+/// Returns a link to the given message. Slack redirects it to the workspace
+/// the message was posted in.
+pub fn message_permalink(message: &SlackMessage) -> String {
+    format!(
+        "https://slack.com/archives/{}/p{}",
+        message.channel_id,
+        message.ts.replace('.', "")
+    )
+}
+
+/// Formats a number of seconds using the two largest units, such as
+/// "2h 5m" or "42s".
+fn format_duration(secs: i64) -> String {
+    let secs = secs.max(0);
+    let (days, hours, minutes, seconds) = (
+        secs / 86_400,
+        secs % 86_400 / 3_600,
+        secs % 3_600 / 60,
+        secs % 60,
+    );
+
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{seconds}s"),
+        (0, 0, _) => format!("{minutes}m {seconds}s"),
+        (0, _, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h"),
+    }
+}
+
+/// Formats the remaining error budget, such as "42.1% left of 30d".
+fn format_remaining_error_budget(error_budget: &ErrorBudget) -> String {
+    match error_budget.remaining {
+        Some(remaining) if remaining <= 0.0 => {
+            format!(":fire: Exhausted for {}d", error_budget.period_days)
+        }
+        Some(remaining) => format!(
+            "{:.1}% left of {}d",
+            remaining * 100.0,
+            error_budget.period_days
+        ),
+        None => format!("No traffic in {}d", error_budget.period_days),
+    }
+}
+
+/// Formats the burn rates of the error budget, such as
+/// "5m: 14.4x · 1h: 6.0x · 6h: 1.2x · 3d: 0.3x".
+fn format_burn_rates(error_budget: &ErrorBudget) -> String {
+    error_budget
+        .burn_rates
+        .iter()
+        .map(|burn_rate| match burn_rate.rate {
+            Some(rate) => format!("{}: {rate:.1}x", burn_rate.window),
+            None => format!("{}: n/a", burn_rate.window),
+        })
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// Builds the reply that is posted in the thread of an alert message when the
+/// alert transitions.
+fn build_thread_reply(transition: AlertTransition, alert: &Alert) -> SlackMessageContent {
+    let text = match transition {
+        AlertTransition::Resolved => match alert.resolved_by.as_ref() {
+            Some(user_id) => format!(":white_check_mark: Alert was resolved by <@{user_id}>"),
+            None => ":white_check_mark: Alert was resolved".to_owned(),
+        },
+        AlertTransition::Refired => ":rotating_light: Alert is firing again".to_owned(),
+        AlertTransition::Acknowledged => match alert.acknowledged_by.as_ref() {
+            Some(user_id) => format!(":eyes: Alert was acknowledged by <@{user_id}>"),
+            None => ":eyes: Alert was acknowledged".to_owned(),
+        },
+    };
+
+    SlackMessageContent::new().with_text(text)
+}
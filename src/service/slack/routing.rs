@@ -0,0 +1,81 @@
+use crate::service::matchers::LabelMatcher;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Table that determines which Slack channels an alert is posted to, based on
+/// its labels.
+///
+/// Example:
+///
+/// ```yaml
+/// routes:
+///   - matchers: ["team=payments"]
+///     channels: ["#payments-alerts"]
+///   - matchers: ["severity=page", "environment=~prod.*"]
+///     channels: ["#oncall", "#incidents"]
+/// default_channels: ["#alerts"]
+/// ```
+///
+/// An alert is posted to the channels of every route for which all the
+/// matchers match. If no route matches, it is posted to the default channels.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SlackRoutingTable {
+    #[serde(default)]
+    pub routes: Vec<SlackRoute>,
+
+    /// Channels to post alerts to if no route matches.
+    ///
+    /// If empty, the channel configured using `--slack-channel` is used.
+    #[serde(default)]
+    pub default_channels: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SlackRoute {
+    pub matchers: Vec<LabelMatcher>,
+    pub channels: Vec<String>,
+}
+
+impl SlackRoutingTable {
+    /// Loads the routing table from a YAML file.
+    ///
+    /// This is used as a Clap value parser, so configuration errors are
+    /// reported on startup.
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(Path::new(path))
+            .map_err(|err| format!("Cannot read routing table {path}: {err}"))?;
+
+        serde_yaml::from_str(&contents)
+            .map_err(|err| format!("Invalid routing table {path}: {err}"))
+    }
+
+    /// Returns the channels an alert with the given labels should be posted
+    /// to, without duplicates.
+    pub fn channels_for(
+        &self,
+        labels: &BTreeMap<String, String>,
+        fallback_channel: &str,
+    ) -> Vec<String> {
+        let mut channels: Vec<String> = Vec::new();
+        for route in &self.routes {
+            if LabelMatcher::all_match(&route.matchers, labels) {
+                for channel in &route.channels {
+                    if !channels.contains(channel) {
+                        channels.push(channel.clone());
+                    }
+                }
+            }
+        }
+
+        if channels.is_empty() {
+            channels = if self.default_channels.is_empty() {
+                vec![fallback_channel.to_owned()]
+            } else {
+                self.default_channels.clone()
+            };
+        }
+
+        channels
+    }
+}
@@ -0,0 +1,65 @@
+use super::SlackRequestHandlerError;
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::Sha256;
+use time::OffsetDateTime;
+
+const SIGNATURE_HEADER: &str = "x-slack-signature";
+const TIMESTAMP_HEADER: &str = "x-slack-request-timestamp";
+const SIGNATURE_VERSION: &str = "v0";
+
+/// Maximum age of a request before we consider it a possible replay attack.
+const MAX_REQUEST_AGE_SECS: u64 = 5 * 60;
+
+/// Verifies the `X-Slack-Signature` header of a request sent by Slack.
+///
+/// See: https://api.slack.com/authentication/verifying-requests-from-slack
+pub fn verify_slack_signature(
+    signing_secret: &SecretString,
+    headers: &HeaderMap,
+    body: &[u8],
+    now: OffsetDateTime,
+) -> Result<(), SlackRequestHandlerError> {
+    let timestamp = header_str(headers, TIMESTAMP_HEADER)?;
+    let signature = header_str(headers, SIGNATURE_HEADER)?;
+
+    let request_time: i64 = timestamp
+        .parse()
+        .map_err(|_| SlackRequestHandlerError::InvalidSignature)?;
+    // The timestamp isn't verified yet, so it may be anything.
+    let request_age = now
+        .unix_timestamp()
+        .checked_sub(request_time)
+        .map(i64::unsigned_abs);
+    if !request_age.is_some_and(|age| age <= MAX_REQUEST_AGE_SECS) {
+        return Err(SlackRequestHandlerError::InvalidSignature);
+    }
+
+    let signature = signature
+        .strip_prefix(SIGNATURE_VERSION)
+        .and_then(|signature| signature.strip_prefix('='))
+        .and_then(|signature| hex::decode(signature).ok())
+        .ok_or(SlackRequestHandlerError::InvalidSignature)?;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(signing_secret.expose_secret().as_bytes())
+        .map_err(|_| SlackRequestHandlerError::InvalidSignature)?;
+    mac.update(SIGNATURE_VERSION.as_bytes());
+    mac.update(b":");
+    mac.update(timestamp.as_bytes());
+    mac.update(b":");
+    mac.update(body);
+
+    mac.verify_slice(&signature)
+        .map_err(|_| SlackRequestHandlerError::InvalidSignature)
+}
+
+fn header_str<'a>(
+    headers: &'a HeaderMap,
+    name: &'static str,
+) -> Result<&'a str, SlackRequestHandlerError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(SlackRequestHandlerError::InvalidSignature)
+}
@@ -1,10 +1,31 @@
-use super::build_message;
-use crate::db::models::Alert;
+use super::commands::{
+    build_alert_list_reply, AlertsCommand, SlackCommandPayload, SlackCommandResponse,
+};
+use super::interactions::{AlertAction, SlackInteractionPayload};
+use super::routing::SlackRoutingTable;
+use super::signature::verify_slack_signature;
+use super::uploads::{SlackFileUpload, SlackFileUploader};
+use super::{build_message, build_thread_reply, format_duration, format_remaining_error_budget};
+use super::{SlackRequestHandlerError, SlackService, SlackServiceConfig, SlackServiceError};
+use crate::db::models::{Alert, AlertStats, AlertTransition, BurnRate, ErrorBudget};
+use crate::service::charts::{ChartUrlSignature, ChartUrlSigner};
+use crate::service::matchers::LabelMatcher;
+use axum::body::Bytes;
+use axum::extract::{Form, Json as AxumJson, Path as AxumPath, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Router, Server};
 use once_cell::sync::Lazy;
-use time::OffsetDateTime;
+use secrecy::SecretString;
+use sqlx::types::Json;
+use std::collections::{BTreeMap, HashMap};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use time::{Duration, OffsetDateTime};
 use url::Url;
 
-static SERVICE_URL: Lazy<Url> = Lazy::new(|| Url::parse("http://localhost:3031").unwrap());
+static CHART_URL: Lazy<Url> =
+    Lazy::new(|| Url::parse("http://localhost:3031/api/chart/1234").unwrap());
 static PROMETHEUS_URL: Lazy<Url> =
     Lazy::new(|| Url::parse("http://localhost:9090/prometheus").unwrap());
 static EXPLORER_URL: Lazy<Url> = Lazy::new(|| Url::parse("http://explorer.pmmp.dev").unwrap());
@@ -17,20 +38,44 @@ fn test_firing_alert_message() {
         text: "High Error Rate for \"api\" [environment=production]".to_owned(),
         resolved: false,
         fingerprint: None,
+        occurrence: 1,
         notebook_id: None,
         chart_filename: None,
+        error_budget: None,
+        error_budget_chart_filename: None,
         sloth_service: None,
         sloth_slo: None,
         objective_name: Some("api".to_owned()),
         severity: None,
         slack_channel: None,
         slack_ts: None,
+        acknowledged_by: None,
+        acknowledged_at: None,
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: None,
+        ends_at: None,
         created_at: now,
         updated_at: now,
     };
 
-    let message =
-        build_message(&SERVICE_URL, &PROMETHEUS_URL, Some(&EXPLORER_URL), &alert).unwrap();
+    let message = build_message(
+        None,
+        None,
+        false,
+        &PROMETHEUS_URL,
+        Some(&EXPLORER_URL),
+        false,
+        &alert,
+        &AlertStats::default(),
+    )
+    .unwrap();
 
     insta::assert_yaml_snapshot!(message);
 }
@@ -43,20 +88,44 @@ fn test_resolved_alert_message() {
         text: "High Error Rate for \"api\" [environment=production]".to_owned(),
         resolved: true,
         fingerprint: None,
+        occurrence: 1,
         notebook_id: None,
         chart_filename: None,
+        error_budget: None,
+        error_budget_chart_filename: None,
         sloth_service: None,
         sloth_slo: None,
         objective_name: Some("api".to_owned()),
         severity: None,
         slack_channel: None,
         slack_ts: None,
+        acknowledged_by: None,
+        acknowledged_at: None,
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: None,
+        ends_at: None,
         created_at: now,
         updated_at: now,
     };
 
-    let message =
-        build_message(&SERVICE_URL, &PROMETHEUS_URL, Some(&EXPLORER_URL), &alert).unwrap();
+    let message = build_message(
+        None,
+        None,
+        false,
+        &PROMETHEUS_URL,
+        Some(&EXPLORER_URL),
+        false,
+        &alert,
+        &AlertStats::default(),
+    )
+    .unwrap();
 
     insta::assert_yaml_snapshot!(message);
 }
@@ -69,20 +138,44 @@ fn test_alert_message_with_chart() {
         text: "High Error Rate for \"api\" [environment=production]".to_owned(),
         resolved: true,
         fingerprint: None,
+        occurrence: 1,
         notebook_id: None,
         chart_filename: Some("1234.png".to_owned()),
+        error_budget: None,
+        error_budget_chart_filename: None,
         sloth_service: None,
         sloth_slo: None,
         objective_name: Some("api".to_owned()),
         severity: None,
         slack_channel: None,
         slack_ts: None,
+        acknowledged_by: None,
+        acknowledged_at: None,
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: None,
+        ends_at: None,
         created_at: now,
         updated_at: now,
     };
 
-    let message =
-        build_message(&SERVICE_URL, &PROMETHEUS_URL, Some(&EXPLORER_URL), &alert).unwrap();
+    let message = build_message(
+        Some(&*CHART_URL),
+        None,
+        false,
+        &PROMETHEUS_URL,
+        Some(&EXPLORER_URL),
+        false,
+        &alert,
+        &AlertStats::default(),
+    )
+    .unwrap();
 
     insta::assert_yaml_snapshot!(message);
 }
@@ -95,20 +188,1029 @@ fn test_alert_message_with_explorer_button() {
         text: "High Error Rate for \"api\" [environment=production]".to_owned(),
         resolved: true,
         fingerprint: None,
+        occurrence: 1,
         notebook_id: None,
         chart_filename: Some("1234.png".to_owned()),
+        error_budget: None,
+        error_budget_chart_filename: None,
         sloth_service: Some("api".to_owned()),
         sloth_slo: Some("success-rate-99".to_owned()),
         objective_name: Some("api".to_owned()),
         severity: None,
         slack_channel: None,
         slack_ts: None,
+        acknowledged_by: None,
+        acknowledged_at: None,
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: None,
+        ends_at: None,
         created_at: now,
         updated_at: now,
     };
 
-    let message =
-        build_message(&SERVICE_URL, &PROMETHEUS_URL, Some(&EXPLORER_URL), &alert).unwrap();
+    let message = build_message(
+        Some(&*CHART_URL),
+        None,
+        false,
+        &PROMETHEUS_URL,
+        Some(&EXPLORER_URL),
+        false,
+        &alert,
+        &AlertStats::default(),
+    )
+    .unwrap();
 
     insta::assert_yaml_snapshot!(message);
 }
+
+#[test]
+fn test_firing_alert_message_with_action_buttons() {
+    let now = OffsetDateTime::UNIX_EPOCH;
+    let alert = Alert {
+        id: 1234,
+        text: "High Error Rate for \"api\" [environment=production]".to_owned(),
+        resolved: false,
+        fingerprint: None,
+        occurrence: 1,
+        notebook_id: None,
+        chart_filename: None,
+        error_budget: None,
+        error_budget_chart_filename: None,
+        sloth_service: None,
+        sloth_slo: None,
+        objective_name: Some("api".to_owned()),
+        severity: None,
+        slack_channel: None,
+        slack_ts: None,
+        acknowledged_by: Some("U0123ABCD".to_owned()),
+        acknowledged_at: Some(now),
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: None,
+        ends_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let message = build_message(
+        None,
+        None,
+        false,
+        &PROMETHEUS_URL,
+        Some(&EXPLORER_URL),
+        true,
+        &alert,
+        &AlertStats::default(),
+    )
+    .unwrap();
+
+    insta::assert_yaml_snapshot!(message);
+}
+
+// Example taken from: https://api.slack.com/authentication/verifying-requests-from-slack
+const SIGNING_SECRET: &str = "8f742231b10e8888abcd99yyyzzz85a5";
+const SIGNED_TIMESTAMP: &str = "1531420618";
+const SIGNED_BODY: &str = "token=xyzz0WbapA4vBCDEFasx0q6G&team_id=T1DC2JH3J&team_domain=testteamnow&channel_id=G8PSS9T3V&channel_name=foobar&user_id=U2CERLKJA&user_name=roadrunner&command=%2Fwebhook-collect&text=&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2FT1DC2JH3J%2F397700885554%2F96rGlfmibIGlgcZRskXaIFfN&trigger_id=398738663015.47445629121.803a0bc887a14d10d2c447fce8b6703c";
+const SIGNATURE: &str = "v0=a2114d57b48eac39b9ad189dd8316235a7b4a8d21a10bd27519666489c69b503";
+
+fn signed_headers(signature: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-slack-request-timestamp",
+        SIGNED_TIMESTAMP.parse().unwrap(),
+    );
+    headers.insert("x-slack-signature", signature.parse().unwrap());
+    headers
+}
+
+#[test]
+fn test_verify_valid_signature() {
+    let secret = SecretString::new(SIGNING_SECRET.to_owned());
+    let now = OffsetDateTime::from_unix_timestamp(1531420618 + 10).unwrap();
+
+    let result = verify_slack_signature(
+        &secret,
+        &signed_headers(SIGNATURE),
+        SIGNED_BODY.as_bytes(),
+        now,
+    );
+
+    assert_matches!(result, Ok(()));
+}
+
+#[test]
+fn test_verify_invalid_signature() {
+    let secret = SecretString::new(SIGNING_SECRET.to_owned());
+    let now = OffsetDateTime::from_unix_timestamp(1531420618 + 10).unwrap();
+
+    let result =
+        verify_slack_signature(&secret, &signed_headers(SIGNATURE), b"token=tampered", now);
+
+    assert_matches!(result, Err(SlackRequestHandlerError::InvalidSignature));
+}
+
+#[test]
+fn test_verify_expired_signature() {
+    let secret = SecretString::new(SIGNING_SECRET.to_owned());
+    let now = OffsetDateTime::from_unix_timestamp(1531420618 + 3600).unwrap();
+
+    let result = verify_slack_signature(
+        &secret,
+        &signed_headers(SIGNATURE),
+        SIGNED_BODY.as_bytes(),
+        now,
+    );
+
+    assert_matches!(result, Err(SlackRequestHandlerError::InvalidSignature));
+}
+
+#[test]
+fn test_verify_signature_with_extreme_timestamp() {
+    let secret = SecretString::new(SIGNING_SECRET.to_owned());
+    let now = OffsetDateTime::from_unix_timestamp(1531420618).unwrap();
+
+    for timestamp in [i64::MIN, i64::MAX] {
+        let mut headers = signed_headers(SIGNATURE);
+        headers.insert(
+            "x-slack-request-timestamp",
+            timestamp.to_string().parse().unwrap(),
+        );
+
+        let result = verify_slack_signature(&secret, &headers, SIGNED_BODY.as_bytes(), now);
+
+        assert_matches!(result, Err(SlackRequestHandlerError::InvalidSignature));
+    }
+}
+
+#[test]
+fn test_parse_interaction_payload() {
+    let payload = r#"{"type":"block_actions","user":{"id":"U0123ABCD","username":"jane"},"actions":[{"action_id":"silence_4h","value":"1234"}]}"#;
+    let body = form_urlencoded::Serializer::new(String::new())
+        .append_pair("payload", payload)
+        .finish();
+
+    let payload = SlackInteractionPayload::from_form_body(body.as_bytes()).unwrap();
+
+    assert_eq!(payload.kind, "block_actions");
+    assert_eq!(payload.user.id, "U0123ABCD");
+    assert_eq!(payload.actions.len(), 1);
+    assert_eq!(
+        AlertAction::from_action_id(&payload.actions[0].action_id),
+        Some(AlertAction::Silence {
+            duration: Duration::hours(4),
+        })
+    );
+    assert_eq!(payload.actions[0].value.as_deref(), Some("1234"));
+}
+
+#[test]
+fn test_alert_action_ids_round_trip() {
+    let actions = [
+        AlertAction::Acknowledge,
+        AlertAction::Silence {
+            duration: Duration::hours(1),
+        },
+        AlertAction::Silence {
+            duration: Duration::hours(4),
+        },
+        AlertAction::Silence {
+            duration: Duration::hours(24),
+        },
+        AlertAction::ExpireSilence,
+        AlertAction::Resolve,
+    ];
+
+    for action in actions {
+        assert_eq!(
+            AlertAction::from_action_id(&action.action_id()),
+            Some(action)
+        );
+    }
+
+    assert_eq!(AlertAction::from_action_id("silence_3h"), None);
+    assert_eq!(AlertAction::from_action_id("open_in_explorer"), None);
+}
+
+#[test]
+fn test_silenced_alert_message_with_expire_button() {
+    let now = OffsetDateTime::now_utc();
+    let alert = Alert {
+        id: 1234,
+        text: "High Error Rate for \"api\" [environment=production]".to_owned(),
+        resolved: false,
+        fingerprint: None,
+        occurrence: 1,
+        notebook_id: None,
+        chart_filename: None,
+        error_budget: None,
+        error_budget_chart_filename: None,
+        sloth_service: None,
+        sloth_slo: None,
+        objective_name: Some("api".to_owned()),
+        severity: None,
+        slack_channel: None,
+        slack_ts: None,
+        acknowledged_by: None,
+        acknowledged_at: None,
+        silenced_by: Some("U0123ABCD".to_owned()),
+        silenced_at: Some(now),
+        silenced_until: Some(now + time::Duration::hours(4)),
+        silence_id: Some("a1b2c3".to_owned()),
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: None,
+        ends_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let message = build_message(
+        None,
+        None,
+        false,
+        &PROMETHEUS_URL,
+        Some(&EXPLORER_URL),
+        true,
+        &alert,
+        &AlertStats::default(),
+    )
+    .unwrap();
+
+    let json = serde_json::to_string(&message).unwrap();
+    assert!(json.contains("\"action_id\":\"expire_silence\""));
+    assert!(!json.contains("\"action_id\":\"silence_1h\""));
+    assert!(json.contains("by <@U0123ABCD>"));
+}
+
+const ROUTING_TABLE: &str = r##"
+routes:
+  - matchers: ["team=payments"]
+    channels: ["#payments-alerts"]
+  - matchers: ["severity=page", "environment=~prod.*"]
+    channels: ["#oncall", "#payments-alerts"]
+default_channels: ["#alerts"]
+"##;
+
+fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_routing_table_matches_all_routes() {
+    let routing_table: SlackRoutingTable = serde_yaml::from_str(ROUTING_TABLE).unwrap();
+
+    let channels = routing_table.channels_for(
+        &labels(&[
+            ("team", "payments"),
+            ("severity", "page"),
+            ("environment", "production"),
+        ]),
+        "#fallback",
+    );
+
+    assert_eq!(channels, vec!["#payments-alerts", "#oncall"]);
+}
+
+#[test]
+fn test_routing_table_uses_default_channels() {
+    let routing_table: SlackRoutingTable = serde_yaml::from_str(ROUTING_TABLE).unwrap();
+
+    let channels = routing_table.channels_for(
+        &labels(&[("severity", "page"), ("environment", "staging")]),
+        "#fallback",
+    );
+    assert_eq!(channels, vec!["#alerts"]);
+
+    let channels = SlackRoutingTable::default().channels_for(&labels(&[]), "#fallback");
+    assert_eq!(channels, vec!["#fallback"]);
+}
+
+#[test]
+fn test_label_matchers() {
+    let labels = labels(&[("team", "payments"), ("environment", "production")]);
+
+    let matches = |matcher: &str| matcher.parse::<LabelMatcher>().unwrap().matches(&labels);
+
+    assert!(matches("team=payments"));
+    assert!(matches("team=\"payments\""));
+    assert!(!matches("team!=payments"));
+    assert!(matches("environment=~prod.*"));
+    assert!(!matches("environment=~prod"));
+    assert!(matches("environment!~staging|dev"));
+    assert!(matches("severity="));
+    assert!(!matches("severity=page"));
+
+    assert!("team".parse::<LabelMatcher>().is_err());
+    assert!("=payments".parse::<LabelMatcher>().is_err());
+    assert!("team=~(".parse::<LabelMatcher>().is_err());
+}
+
+#[test]
+fn test_thread_replies() {
+    let now = OffsetDateTime::UNIX_EPOCH;
+    let mut alert = Alert {
+        id: 1234,
+        text: "High Error Rate for \"api\" [environment=production]".to_owned(),
+        resolved: true,
+        fingerprint: None,
+        occurrence: 1,
+        notebook_id: None,
+        chart_filename: None,
+        error_budget: None,
+        error_budget_chart_filename: None,
+        sloth_service: None,
+        sloth_slo: None,
+        objective_name: None,
+        severity: Some("page".to_owned()),
+        slack_channel: None,
+        slack_ts: None,
+        acknowledged_by: Some("U0123ABCD".to_owned()),
+        acknowledged_at: Some(now),
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: None,
+        ends_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let text = |transition, alert: &Alert| build_thread_reply(transition, alert).text.unwrap();
+
+    assert_eq!(
+        text(AlertTransition::Resolved, &alert),
+        ":white_check_mark: Alert was resolved"
+    );
+    assert_eq!(
+        text(AlertTransition::Acknowledged, &alert),
+        ":eyes: Alert was acknowledged by <@U0123ABCD>"
+    );
+    assert_eq!(
+        text(AlertTransition::Refired, &alert),
+        ":rotating_light: Alert is firing again"
+    );
+
+    alert.resolved_by = Some("U0123ABCD".to_owned());
+    assert_eq!(
+        text(AlertTransition::Resolved, &alert),
+        ":white_check_mark: Alert was resolved by <@U0123ABCD>"
+    );
+
+    assert_eq!(
+        AlertAction::Acknowledge.transition(),
+        Some(AlertTransition::Acknowledged)
+    );
+    assert_eq!(
+        AlertAction::Resolve.transition(),
+        Some(AlertTransition::Resolved)
+    );
+    assert_eq!(
+        AlertAction::Silence {
+            duration: Duration::hours(1),
+        }
+        .transition(),
+        None
+    );
+}
+
+#[test]
+fn test_resolved_alert_message_with_stats() {
+    let now = OffsetDateTime::UNIX_EPOCH;
+    let alert = Alert {
+        id: 1234,
+        text: "High Error Rate for \"api\" [environment=production]".to_owned(),
+        resolved: true,
+        fingerprint: None,
+        occurrence: 1,
+        notebook_id: None,
+        chart_filename: None,
+        error_budget: None,
+        error_budget_chart_filename: None,
+        sloth_service: None,
+        sloth_slo: None,
+        objective_name: None,
+        severity: None,
+        slack_channel: None,
+        slack_ts: None,
+        acknowledged_by: None,
+        acknowledged_at: None,
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: None,
+        ends_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let stats = AlertStats {
+        fired_last_24h: 3,
+        time_to_resolve_secs: Some(754),
+    };
+
+    let message = build_message(
+        None,
+        None,
+        false,
+        &PROMETHEUS_URL,
+        None,
+        false,
+        &alert,
+        &stats,
+    )
+    .unwrap();
+
+    let json = serde_json::to_string(&message).unwrap();
+    assert!(json.contains("Fired 3 times in the last 24h"));
+    assert!(json.contains("*Time to resolve*\\n12m 34s"));
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(format_duration(42), "42s");
+    assert_eq!(format_duration(754), "12m 34s");
+    assert_eq!(format_duration(7_500), "2h 5m");
+    assert_eq!(format_duration(90_000), "1d 1h");
+}
+
+#[test]
+fn test_parse_command_payload() {
+    let body = form_urlencoded::Serializer::new(String::new())
+        .append_pair("command", "/alerts")
+        .append_pair("text", "silence 1234 4h")
+        .append_pair("user_id", "U0123ABCD")
+        .append_pair("user_name", "jane")
+        .finish();
+
+    let payload = SlackCommandPayload::from_form_body(body.as_bytes()).unwrap();
+
+    assert_eq!(payload.text, "silence 1234 4h");
+    assert_eq!(payload.user_id, "U0123ABCD");
+    assert_eq!(payload.user_name, "jane");
+    assert_matches!(
+        SlackCommandPayload::from_form_body(b"command=%2Falerts&text=open"),
+        Err(SlackRequestHandlerError::InvalidPayload(_))
+    );
+}
+
+#[test]
+fn test_parse_alerts_command() {
+    assert_eq!(AlertsCommand::parse(""), Ok(AlertsCommand::Help));
+    assert_eq!(AlertsCommand::parse("open"), Ok(AlertsCommand::Open));
+    assert_eq!(
+        AlertsCommand::parse("service  payments "),
+        Ok(AlertsCommand::Service("payments".to_owned()))
+    );
+    assert_eq!(
+        AlertsCommand::parse("show #1234"),
+        Ok(AlertsCommand::Show(1234))
+    );
+    assert_eq!(
+        AlertsCommand::parse("silence 1234 30m"),
+        Ok(AlertsCommand::Silence {
+            alert_id: 1234,
+            duration: Duration::minutes(30),
+        })
+    );
+    assert_eq!(
+        AlertsCommand::parse("silence 1234 2d"),
+        Ok(AlertsCommand::Silence {
+            alert_id: 1234,
+            duration: Duration::days(2),
+        })
+    );
+    assert_eq!(
+        AlertsCommand::parse("silence 1234 30d"),
+        Ok(AlertsCommand::Silence {
+            alert_id: 1234,
+            duration: Duration::days(30),
+        })
+    );
+
+    assert_matches!(AlertsCommand::parse("show abc"), Err(_));
+    assert_matches!(AlertsCommand::parse("silence 1234 0h"), Err(_));
+    assert_matches!(AlertsCommand::parse("silence 1234 4w"), Err(_));
+    assert_matches!(AlertsCommand::parse("silence 1234 31d"), Err(_));
+    assert_matches!(
+        AlertsCommand::parse("silence 1234 9223372036854775807d"),
+        Err(_)
+    );
+    assert_matches!(AlertsCommand::parse("silence 1234"), Err(_));
+    assert_matches!(AlertsCommand::parse("snooze 1234"), Err(_));
+}
+
+#[test]
+fn test_alert_list_reply() {
+    let now = OffsetDateTime::UNIX_EPOCH;
+    let alerts: Vec<Alert> = (1..=3)
+        .map(|id| Alert {
+            id,
+            text: format!("Alert number {id}"),
+            resolved: false,
+            fingerprint: None,
+            occurrence: 1,
+            notebook_id: None,
+            chart_filename: None,
+            error_budget: None,
+            error_budget_chart_filename: None,
+            sloth_service: Some("payments".to_owned()),
+            sloth_slo: None,
+            objective_name: None,
+            severity: Some("page".to_owned()),
+            slack_channel: None,
+            slack_ts: None,
+            acknowledged_by: None,
+            acknowledged_at: None,
+            silenced_by: None,
+            silenced_at: None,
+            silenced_until: None,
+            silence_id: None,
+            resolved_by: None,
+            labels: Default::default(),
+            annotations: Default::default(),
+            generator_url: None,
+            starts_at: None,
+            ends_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+        .collect();
+
+    let reply = build_alert_list_reply("Open alerts", &alerts, 2);
+    let json = serde_json::to_value(SlackCommandResponse::ephemeral(reply)).unwrap();
+
+    assert_eq!(json["response_type"], "ephemeral");
+    assert_eq!(json["attachments"].as_array().unwrap().len(), 2);
+    let json = json.to_string();
+    assert!(json.contains("Showing the 2 most recent alerts."));
+    assert!(json.contains("*#1* Alert number 1"));
+    assert!(json.contains(":pager: Page"));
+    assert!(!json.contains("Alert number 3"));
+
+    let empty_reply =
+        serde_json::to_string(&build_alert_list_reply("Open alerts", &[], 2)).unwrap();
+    assert!(empty_reply.contains("There are no alerts."));
+}
+
+#[test]
+fn test_chart_url() {
+    let now = OffsetDateTime::UNIX_EPOCH;
+    let alert = Alert {
+        id: 1234,
+        text: "High Error Rate for \"api\" [environment=production]".to_owned(),
+        resolved: false,
+        fingerprint: None,
+        occurrence: 1,
+        notebook_id: None,
+        chart_filename: Some("1234.png".to_owned()),
+        error_budget: None,
+        error_budget_chart_filename: None,
+        sloth_service: None,
+        sloth_slo: None,
+        objective_name: None,
+        severity: None,
+        slack_channel: None,
+        slack_ts: None,
+        acknowledged_by: None,
+        acknowledged_at: None,
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: None,
+        ends_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let service_url = Url::parse("http://localhost:3031").unwrap();
+
+    let linking_service = SlackService::new(
+        service_url.clone(),
+        SlackServiceConfig::new_test_config("12345678".to_owned()),
+        PROMETHEUS_URL.clone(),
+        None,
+        None,
+    );
+    let uploading_service = SlackService::new(
+        service_url.clone(),
+        SlackServiceConfig {
+            upload_charts: true,
+            ..SlackServiceConfig::new_test_config("12345678".to_owned())
+        },
+        PROMETHEUS_URL.clone(),
+        None,
+        None,
+    );
+    let chart_url_signer = ChartUrlSigner::new(
+        SecretString::new("chart-secret".to_owned()),
+        Vec::new(),
+        Duration::hours(1),
+    );
+    let signing_service = SlackService::new(
+        service_url,
+        SlackServiceConfig::new_test_config("12345678".to_owned()),
+        PROMETHEUS_URL.clone(),
+        None,
+        Some(chart_url_signer.clone()),
+    );
+
+    assert_eq!(
+        linking_service.chart_url(&alert, now),
+        Some(Url::parse("http://localhost:3031/api/chart/1234?format=png&v=44ba5a8a").unwrap())
+    );
+    assert_eq!(uploading_service.chart_url(&alert, now), None);
+    assert_eq!(
+        linking_service.chart_url(
+            &Alert {
+                chart_filename: None,
+                ..alert.clone()
+            },
+            now
+        ),
+        None
+    );
+
+    let signed_url = signing_service.chart_url(&alert, now).unwrap();
+    assert_eq!(signed_url.path(), "/api/chart/1234");
+    let query: BTreeMap<_, _> = signed_url.query_pairs().collect();
+    let signature = ChartUrlSignature {
+        expires: query.get("expires").map(|expires| expires.parse().unwrap()),
+        signature: query
+            .get("signature")
+            .map(|signature| signature.to_string()),
+    };
+    assert_eq!(query["format"], "png");
+    assert_eq!(signature.expires, Some(3600));
+    assert!(chart_url_signer.verify(1234, &signature, now));
+
+    assert_eq!(linking_service.error_budget_chart_url(&alert, now), None);
+    assert_eq!(
+        linking_service.error_budget_chart_url(
+            &Alert {
+                error_budget_chart_filename: Some("1234-error-budget.png".to_owned()),
+                ..alert.clone()
+            },
+            now
+        ),
+        Some(
+            Url::parse("http://localhost:3031/api/chart/1234/error-budget?format=png&v=8b63121c")
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn test_alert_message_with_error_budget() {
+    let now = OffsetDateTime::UNIX_EPOCH;
+    let error_budget = ErrorBudget {
+        period_days: 30,
+        remaining: Some(0.421),
+        burn_rates: vec![
+            BurnRate {
+                window: "5m".to_owned(),
+                rate: Some(14.4),
+            },
+            BurnRate {
+                window: "1h".to_owned(),
+                rate: Some(6.0),
+            },
+            BurnRate {
+                window: "6h".to_owned(),
+                rate: Some(1.23),
+            },
+            BurnRate {
+                window: "3d".to_owned(),
+                rate: None,
+            },
+        ],
+    };
+    let alert = Alert {
+        id: 1234,
+        text: "High Error Rate for \"api\" [environment=production]".to_owned(),
+        resolved: false,
+        fingerprint: None,
+        occurrence: 1,
+        notebook_id: None,
+        chart_filename: Some("1234.png".to_owned()),
+        error_budget: Some(Json(error_budget.clone())),
+        error_budget_chart_filename: Some("1234-error-budget.png".to_owned()),
+        sloth_service: Some("api".to_owned()),
+        sloth_slo: Some("success-rate-99".to_owned()),
+        objective_name: Some("api".to_owned()),
+        severity: None,
+        slack_channel: None,
+        slack_ts: None,
+        acknowledged_by: None,
+        acknowledged_at: None,
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: None,
+        starts_at: None,
+        ends_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let error_budget_chart_url =
+        Url::parse("http://localhost:3031/api/chart/1234/error-budget").unwrap();
+
+    let message = build_message(
+        Some(&*CHART_URL),
+        Some(&error_budget_chart_url),
+        false,
+        &PROMETHEUS_URL,
+        None,
+        false,
+        &alert,
+        &AlertStats::default(),
+    )
+    .unwrap();
+
+    let json = serde_json::to_string(&message).unwrap();
+    assert!(!json.contains("Charts are in the thread"));
+    assert!(json.contains("*Error budget*\\n42.1% left of 30d"));
+    assert!(json.contains("*Burn rate*\\n5m: 14.4x · 1h: 6.0x · 6h: 1.2x · 3d: n/a"));
+    assert!(json.contains("http://localhost:3031/api/chart/1234/error-budget"));
+    assert!(json.contains("Error budget for slo `success-rate-99`"));
+
+    let uploaded_message = build_message(
+        None,
+        None,
+        true,
+        &PROMETHEUS_URL,
+        None,
+        false,
+        &alert,
+        &AlertStats::default(),
+    )
+    .unwrap();
+    let uploaded_json = serde_json::to_string(&uploaded_message).unwrap();
+    assert!(uploaded_json.contains("Charts are in the thread"));
+    assert!(!uploaded_json.contains("http://localhost:3031/api/chart/1234"));
+
+    let exhausted = ErrorBudget {
+        remaining: Some(-0.5),
+        ..error_budget.clone()
+    };
+    assert_eq!(
+        format_remaining_error_budget(&exhausted),
+        ":fire: Exhausted for 30d"
+    );
+    let without_traffic = ErrorBudget {
+        remaining: None,
+        ..error_budget
+    };
+    assert_eq!(
+        format_remaining_error_budget(&without_traffic),
+        "No traffic in 30d"
+    );
+}
+
+#[test]
+fn test_chart_title() {
+    let now = OffsetDateTime::UNIX_EPOCH;
+    let alert = Alert {
+        id: 1234,
+        text: "Instance is down".to_owned(),
+        resolved: false,
+        fingerprint: None,
+        occurrence: 1,
+        notebook_id: None,
+        chart_filename: Some("1234.png".to_owned()),
+        error_budget: None,
+        error_budget_chart_filename: None,
+        sloth_service: None,
+        sloth_slo: None,
+        objective_name: None,
+        severity: None,
+        slack_channel: None,
+        slack_ts: None,
+        acknowledged_by: None,
+        acknowledged_at: None,
+        silenced_by: None,
+        silenced_at: None,
+        silenced_until: None,
+        silence_id: None,
+        resolved_by: None,
+        labels: Default::default(),
+        annotations: Default::default(),
+        generator_url: Some("http://prometheus:9090/graph?g0.expr=up+%3D%3D+0&g0.tab=1".to_owned()),
+        starts_at: None,
+        ends_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    assert_eq!(chart_title(&alert), "Chart for `up == 0`");
+    assert_eq!(
+        chart_title(&Alert {
+            sloth_slo: Some("success-rate-99".to_owned()),
+            ..alert.clone()
+        }),
+        "Chart for slo `success-rate-99`"
+    );
+    assert_eq!(
+        chart_title(&Alert {
+            generator_url: None,
+            ..alert.clone()
+        }),
+        "Chart for slo `unknown`"
+    );
+
+    let long_expression = format!("up{{job=\"{}\"}} == 0", "a".repeat(3000));
+    let long_title = chart_title(&Alert {
+        generator_url: Some(
+            Url::parse_with_params(
+                "http://prometheus:9090/graph",
+                [("g0.expr", long_expression.as_str()), ("g0.tab", "1")],
+            )
+            .unwrap()
+            .to_string(),
+        ),
+        ..alert
+    });
+    assert_eq!(
+        long_title,
+        format!("Chart for `up{{job=\"{}…`", "a".repeat(192))
+    );
+}
+
+/// Files uploaded to the Slack stand-in, by file ID, together with the
+/// request that shared them.
+type Uploads = Arc<Mutex<HashMap<String, (Vec<u8>, Option<serde_json::Value>)>>>;
+
+/// Starts a stand-in for the Slack file upload API. Returns its API URL and
+/// the files uploaded to it.
+fn start_slack_upload_stand_in() -> (Url, Uploads) {
+    #[derive(Clone)]
+    struct StandIn {
+        base_url: Url,
+        uploads: Uploads,
+    }
+
+    async fn get_upload_url(
+        State(stand_in): State<StandIn>,
+        headers: HeaderMap,
+        Form(form): Form<HashMap<String, String>>,
+    ) -> AxumJson<serde_json::Value> {
+        if !is_authorized(&headers) {
+            return AxumJson(serde_json::json!({ "ok": false, "error": "not_authed" }));
+        }
+
+        let file_id = format!("F{}", stand_in.uploads.lock().unwrap().len() + 1);
+        stand_in
+            .uploads
+            .lock()
+            .unwrap()
+            .insert(file_id.clone(), (Vec::new(), None));
+        let upload_url = stand_in
+            .base_url
+            .join(&format!("upload/{file_id}"))
+            .unwrap();
+
+        assert!(form.contains_key("filename"));
+        assert!(form.contains_key("length"));
+        AxumJson(serde_json::json!({
+            "ok": true,
+            "upload_url": upload_url,
+            "file_id": file_id,
+        }))
+    }
+
+    async fn upload(
+        State(stand_in): State<StandIn>,
+        AxumPath(file_id): AxumPath<String>,
+        body: Bytes,
+    ) -> StatusCode {
+        match stand_in.uploads.lock().unwrap().get_mut(&file_id) {
+            Some((content, _)) => {
+                *content = body.to_vec();
+                StatusCode::OK
+            }
+            None => StatusCode::NOT_FOUND,
+        }
+    }
+
+    async fn complete_upload(
+        State(stand_in): State<StandIn>,
+        headers: HeaderMap,
+        AxumJson(request): AxumJson<serde_json::Value>,
+    ) -> AxumJson<serde_json::Value> {
+        if !is_authorized(&headers) {
+            return AxumJson(serde_json::json!({ "ok": false, "error": "not_authed" }));
+        }
+        if request["channel_id"] == "C_MISSING" {
+            return AxumJson(serde_json::json!({ "ok": false, "error": "channel_not_found" }));
+        }
+
+        let file_id = request["files"][0]["id"].as_str().unwrap().to_owned();
+        if let Some((_, shared)) = stand_in.uploads.lock().unwrap().get_mut(&file_id) {
+            *shared = Some(request);
+        }
+        AxumJson(serde_json::json!({ "ok": true, "files": [{ "id": file_id }] }))
+    }
+
+    fn is_authorized(headers: &HeaderMap) -> bool {
+        headers
+            .get("authorization")
+            .is_some_and(|value| value == "Bearer xoxb-token")
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    let uploads = Uploads::default();
+    let router = Router::new()
+        .route("/api/files.getUploadURLExternal", post(get_upload_url))
+        .route("/api/files.completeUploadExternal", post(complete_upload))
+        .route("/upload/:file_id", post(upload))
+        .with_state(StandIn {
+            base_url: base_url.clone(),
+            uploads: uploads.clone(),
+        });
+    tokio::spawn(
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service()),
+    );
+
+    (base_url.join("api/").unwrap(), uploads)
+}
+
+fn chart_upload(channel_id: &str) -> SlackFileUpload {
+    SlackFileUpload {
+        channel_id: channel_id.to_owned(),
+        thread_ts: "1697040000.123456".to_owned(),
+        filename: "alert-1234.png".to_owned(),
+        title: "Chart of alert 1234".to_owned(),
+        content: b"chart".to_vec(),
+    }
+}
+
+#[tokio::test]
+async fn test_upload_file_to_thread() {
+    let (api_url, uploads) = start_slack_upload_stand_in();
+    let uploader = SlackFileUploader::new(api_url.clone(), SecretString::new("xoxb-token".into()));
+    let unauthorized_uploader = SlackFileUploader::new(api_url, SecretString::new("wrong".into()));
+
+    let file_id = uploader
+        .upload(chart_upload("C123"))
+        .await
+        .expect("Error uploading file");
+    let missing_channel_result = uploader.upload(chart_upload("C_MISSING")).await;
+    let unauthorized_result = unauthorized_uploader.upload(chart_upload("C123")).await;
+
+    let uploads = uploads.lock().unwrap();
+    let (content, shared) = uploads.get(&file_id).expect("File wasn't uploaded");
+    assert_eq!(content, b"chart");
+    assert_eq!(
+        shared.as_ref().expect("File wasn't shared"),
+        &serde_json::json!({
+            "files": [{ "id": file_id, "title": "Chart of alert 1234" }],
+            "channel_id": "C123",
+            "thread_ts": "1697040000.123456",
+        })
+    );
+    assert_matches!(
+        missing_channel_result,
+        Err(SlackServiceError::Api { code }) if code == "channel_not_found"
+    );
+    assert_matches!(
+        unauthorized_result,
+        Err(SlackServiceError::Api { code }) if code == "not_authed"
+    );
+}
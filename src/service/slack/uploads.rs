@@ -0,0 +1,166 @@
+use super::SlackServiceError;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use url::Url;
+
+/// Base URL of the Slack Web API.
+pub const SLACK_API_URL: &str = "https://slack.com/api/";
+
+/// Uploads files to Slack using the external upload flow, which replaces the
+/// retired `files.upload` method:
+///
+/// 1. `files.getUploadURLExternal` returns the ID of the file, and a URL to
+///    upload its contents to.
+/// 2. The contents are uploaded to that URL.
+/// 3. `files.completeUploadExternal` shares the file in a thread.
+///
+/// See: https://api.slack.com/messaging/files#uploading_files
+pub struct SlackFileUploader {
+    client: Client,
+    api_url: Url,
+    token: SecretString,
+}
+
+impl SlackFileUploader {
+    pub fn new(api_url: Url, token: SecretString) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("Error building reqwest client");
+
+        Self {
+            client,
+            api_url,
+            token,
+        }
+    }
+
+    /// Uploads a file to the thread of the given message. Returns the ID of
+    /// the uploaded file.
+    pub async fn upload(&self, upload: SlackFileUpload) -> Result<String, SlackServiceError> {
+        let length = upload.content.len().to_string();
+        let upload_url: GetUploadUrlResponse = call(
+            self.client
+                .post(self.method_url("files.getUploadURLExternal")?)
+                .bearer_auth(self.token.expose_secret())
+                .form(&[
+                    ("filename", upload.filename.as_str()),
+                    ("length", length.as_str()),
+                    ("alt_txt", upload.title.as_str()),
+                ]),
+        )
+        .await?;
+
+        self.client
+            .post(upload_url.upload_url)
+            .body(upload.content)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| SlackServiceError::Client(err.to_string()))?;
+
+        let _: IgnoredAny = call(
+            self.client
+                .post(self.method_url("files.completeUploadExternal")?)
+                .bearer_auth(self.token.expose_secret())
+                .json(&CompleteUploadRequest {
+                    files: vec![UploadedFile {
+                        id: upload_url.file_id.clone(),
+                        title: upload.title,
+                    }],
+                    channel_id: upload.channel_id,
+                    thread_ts: upload.thread_ts,
+                }),
+        )
+        .await?;
+
+        Ok(upload_url.file_id)
+    }
+
+    fn method_url(&self, method: &str) -> Result<Url, SlackServiceError> {
+        self.api_url
+            .join(method)
+            .map_err(|err| SlackServiceError::Client(format!("Invalid Slack API URL: {err}")))
+    }
+}
+
+/// A file to upload to the thread of a message.
+pub struct SlackFileUpload {
+    pub channel_id: String,
+    pub thread_ts: String,
+    pub filename: String,
+
+    /// Title of the file, which is also used as its alt text.
+    pub title: String,
+
+    pub content: Vec<u8>,
+}
+
+/// Calls a Slack API method, and returns its response if it succeeded.
+async fn call<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, SlackServiceError> {
+    let response = request
+        .send()
+        .await
+        .map_err(|err| SlackServiceError::Client(err.to_string()))?;
+
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after_secs = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        return Err(SlackServiceError::RateLimited { retry_after_secs });
+    }
+
+    let body = response
+        .error_for_status()
+        .map_err(|err| SlackServiceError::Client(err.to_string()))?
+        .bytes()
+        .await
+        .map_err(|err| SlackServiceError::Client(err.to_string()))?;
+
+    let status: ApiStatus = serde_json::from_slice(&body)
+        .map_err(|err| SlackServiceError::Client(format!("Invalid Slack response: {err}")))?;
+    if !status.ok {
+        return Err(match status.error {
+            Some(code) if code == "ratelimited" => SlackServiceError::RateLimited {
+                retry_after_secs: None,
+            },
+            code => SlackServiceError::Api {
+                code: code.unwrap_or_else(|| "unknown_error".to_owned()),
+            },
+        });
+    }
+
+    serde_json::from_slice(&body)
+        .map_err(|err| SlackServiceError::Client(format!("Invalid Slack response: {err}")))
+}
+
+#[derive(Deserialize)]
+struct ApiStatus {
+    ok: bool,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GetUploadUrlResponse {
+    upload_url: Url,
+    file_id: String,
+}
+
+#[derive(Serialize)]
+struct CompleteUploadRequest {
+    files: Vec<UploadedFile>,
+    channel_id: String,
+    thread_ts: String,
+}
+
+#[derive(Serialize)]
+struct UploadedFile {
+    id: String,
+    title: String,
+}
@@ -1,13 +1,19 @@
 use crate::db::Db;
-use crate::events::Event;
-use crate::service::{ChartServiceConfig, PrometheusServiceConfig, Service, SlackServiceConfig};
+use crate::events::EventLoopSignal;
+use crate::service::{
+    AdminServiceConfig, AlertmanagerServiceConfig, ChartServiceConfig, PrometheusServiceConfig,
+    Service, SlackServiceConfig,
+};
 use futures::{Future, FutureExt};
-use sqlx::pool::PoolConnection;
-use sqlx::{Sqlite, SqlitePool};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Connection, Executor, PgConnection, SqlitePool};
 use std::panic::AssertUnwindSafe;
 use tokio::sync::mpsc::Receiver;
 use url::Url;
 
+/// How long events claimed by tests stay claimed.
+pub const CLAIM_LEASE: time::Duration = time::Duration::minutes(5);
+
 #[macro_export]
 macro_rules! assert_matches {
     ($expression:expr, $pattern:pat $( if $guard: expr )? $(,)?) => {
@@ -45,19 +51,19 @@ pub struct ServiceContext {
 }
 
 pub struct ServiceCleanup {
-    db_cleanup: SqliteCleanup,
+    db_cleanup: DbCleanup,
     // The receiver is kept so the channel doesn't close before cleanup.
-    _event_receiver: Receiver<Event>,
+    _event_receiver: Receiver<EventLoopSignal>,
 }
 
 pub async fn service_setup() -> (ServiceContext, ServiceCleanup) {
-    let (pool, db_cleanup) = sqlite_setup().await;
-
-    let db = Db::new(pool.clone());
+    let (db, db_cleanup) = db_setup().await;
 
-    let (event_sender, event_receiver) = tokio::sync::mpsc::channel::<Event>(16);
+    let (event_sender, event_receiver) = tokio::sync::mpsc::channel::<EventLoopSignal>(16);
     let service = Service::new(
         Url::parse("http://localhost:3031").unwrap(),
+        AdminServiceConfig::new_test_config(),
+        AlertmanagerServiceConfig::new_test_config(),
         ChartServiceConfig::new_test_config(),
         db.clone(),
         event_sender,
@@ -77,29 +83,81 @@ pub async fn service_setup() -> (ServiceContext, ServiceCleanup) {
 }
 
 pub async fn service_cleanup(context: ServiceCleanup) {
-    sqlite_cleanup(context.db_cleanup).await;
+    db_cleanup(context.db_cleanup).await;
 }
 
-struct SqliteCleanup {
-    pool: SqlitePool,
+struct DbCleanup {
+    db: Db,
+    /// The connection string and schema of the Postgres test DB, if any.
+    postgres: Option<(String, String)>,
+}
+
+/// Creates a DB for a single test. Tests run against an in-memory SQLite DB,
+/// unless `TEST_DATABASE_URL` points to a Postgres server, in which case every
+/// test gets its own schema.
+async fn db_setup() -> (Db, DbCleanup) {
+    match std::env::var("TEST_DATABASE_URL") {
+        Ok(url) if crate::db::is_postgres(&url) => postgres_setup(url).await,
+        _ => sqlite_setup().await,
+    }
 }
 
-async fn sqlite_setup() -> (SqlitePool, SqliteCleanup) {
-    // Randomize test DBs to avoid collissions between parallel tests.
+async fn sqlite_setup() -> (Db, DbCleanup) {
     let pool = SqlitePool::connect(":memory:")
         .await
         .expect("Could not create DB");
 
-    let mut connection: PoolConnection<Sqlite> = pool.acquire().await.unwrap();
-    sqlx::migrate!("./migrations")
-        .run(&mut connection)
+    let db = Db::from_sqlite_pool(pool);
+    db.migrate().await.expect("Could not initialize DB");
+
+    let cleanup = DbCleanup {
+        db: db.clone(),
+        postgres: None,
+    };
+    (db, cleanup)
+}
+
+async fn postgres_setup(url: String) -> (Db, DbCleanup) {
+    // Randomize schemas to avoid collisions between parallel tests.
+    let schema = format!("test_{:016x}", rand::random::<u64>());
+
+    let mut connection = PgConnection::connect(&url)
+        .await
+        .expect("Could not connect to DB");
+    connection
+        .execute(format!("CREATE SCHEMA {schema}").as_str())
+        .await
+        .expect("Could not create schema");
+    connection.close().await.ok();
+
+    let options: PgConnectOptions = url.parse().expect("Invalid TEST_DATABASE_URL");
+    let pool = PgPoolOptions::new()
+        .connect_with(options.options([("search_path", schema.as_str())]))
         .await
-        .expect("Could not initialize DB");
+        .expect("Could not create DB");
+
+    let db = Db::from_postgres_pool(pool);
+    db.migrate().await.expect("Could not initialize DB");
 
-    (pool.clone(), SqliteCleanup { pool })
+    let cleanup = DbCleanup {
+        db: db.clone(),
+        postgres: Some((url, schema)),
+    };
+    (db, cleanup)
 }
 
-async fn sqlite_cleanup(cleanup: SqliteCleanup) {
+async fn db_cleanup(cleanup: DbCleanup) {
     // make sure the pool is closed
-    cleanup.pool.close().await;
+    cleanup.db.close().await;
+
+    if let Some((url, schema)) = cleanup.postgres {
+        let mut connection = PgConnection::connect(&url)
+            .await
+            .expect("Could not connect to DB");
+        connection
+            .execute(format!("DROP SCHEMA {schema} CASCADE").as_str())
+            .await
+            .expect("Could not drop schema");
+        connection.close().await.ok();
+    }
 }